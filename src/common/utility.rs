@@ -3,7 +3,7 @@ use crate::storage::heap::TableHeap;
 use crate::storage::page::{Page, RecordId, TablePage};
 use crate::storage::tuple::{Row, TupleMetadata};
 use crate::types::field::Field;
-use crate::types::{Column, DataType, Table};
+use crate::types::{Column, DataType, Decimal, Table};
 use rand::{random, Rng};
 use rand_chacha::ChaCha8Rng;
 use rand_core::SeedableRng;
@@ -29,7 +29,7 @@ pub fn create_n_rows(
 
 pub fn create_random_column_definition(column_name: &String) -> Column {
     let mut rng = rand::thread_rng();
-    let data_type_id = rng.gen_range(0..4);
+    let data_type_id = rng.gen_range(0..7);
     match data_type_id {
         0 => Column::builder()
             .name(column_name.to_string())
@@ -51,6 +51,18 @@ pub fn create_random_column_definition(column_name: &String) -> Column {
                 .max_str_len(size_bound)
                 .build()
         }
+        4 => Column::builder()
+            .name(column_name.to_string())
+            .data_type(DataType::BigInt)
+            .build(),
+        5 => Column::builder()
+            .name(column_name.to_string())
+            .data_type(DataType::Double)
+            .build(),
+        6 => Column::builder()
+            .name(column_name.to_string())
+            .data_type(DataType::Decimal(10, 2))
+            .build(),
         _ => Column::builder()
             .name(column_name.to_string())
             .data_type(DataType::Invalid)
@@ -78,10 +90,24 @@ pub fn create_random_fields(schema: &Table, seed_in: Option<u64>) -> Vec<Field>
                 let i_field = rng.gen_range(0..1000);
                 fields[i] = Field::from(i_field);
             }
+            DataType::BigInt => {
+                let i_field: i64 = rng.gen_range(-1_000_000_000_000..1_000_000_000_000);
+                fields[i] = Field::from(i_field);
+            }
             DataType::Float => {
                 let f: f32 = rng.gen_range(0.0..100000.0);
                 fields[i] = Field::from(f);
             }
+            DataType::Double => {
+                let f: f64 = rng.gen_range(-1_000_000_000.0..1_000_000_000.0);
+                fields[i] = Field::Double(f);
+            }
+            DataType::Decimal(precision, scale) => {
+                // Stay within the column's precision.
+                let bound = 10_i128.pow(precision.min(18) as u32);
+                let value = rng.gen_range(-bound + 1..bound);
+                fields[i] = Field::from(Decimal::new(value, scale));
+            }
             DataType::Text => {
                 let size = schema.get_column(i).get_max_str_len();
                 let len = rng.gen_range(0..size);
@@ -214,7 +240,7 @@ pub fn create_table_definition_mixed_fields(count: usize) -> Table {
 
     for i in 0..count {
         let column_name = format!("column{}", i);
-        let data_type_id = rng.gen_range(0..4);
+        let data_type_id = rng.gen_range(0..7);
         match data_type_id {
             0 => {
                 table.add_column(
@@ -250,6 +276,30 @@ pub fn create_table_definition_mixed_fields(count: usize) -> Table {
                         .build(),
                 );
             }
+            4 => {
+                table.add_column(
+                    &Column::builder()
+                        .name(column_name.to_string())
+                        .data_type(DataType::BigInt)
+                        .build(),
+                );
+            }
+            5 => {
+                table.add_column(
+                    &Column::builder()
+                        .name(column_name.to_string())
+                        .data_type(DataType::Double)
+                        .build(),
+                );
+            }
+            6 => {
+                table.add_column(
+                    &Column::builder()
+                        .name(column_name.to_string())
+                        .data_type(DataType::Decimal(10, 2))
+                        .build(),
+                );
+            }
             _ => {}
         }
     }
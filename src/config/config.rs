@@ -2,3 +2,5 @@ pub const RUSTY_DB_PAGE_SIZE_BYTES: usize = 4096;
 pub const MAX_STRING_LENGTH: usize = 2048;
 // relative path from the project root, i.e., the root of the repository that contains `cargo.toml`
 pub const RUST_DB_DATA_DIR: &str = "data";
+// tuples whose payload is larger than this are stored out of line on a chain of overflow pages
+pub const MAX_INLINE_TUPLE_SIZE_BYTES: usize = RUSTY_DB_PAGE_SIZE_BYTES / 4;
@@ -0,0 +1,57 @@
+use super::log::{self, BitcaskOptions, Log};
+use crate::common::Result;
+use crate::storage::kv::{KvEngine, Store};
+use std::ops::RangeBounds;
+use std::path::Path;
+
+/// A log-structured storage engine, which stores tables in a Bitcask [`Log`]. Writes only ever
+/// append to the log, which makes the engine a good fit for write-heavy workloads, at the cost of
+/// keeping every key in memory.
+pub type Bitcask = KvEngine<Log>;
+
+impl Bitcask {
+    /// Opens the engine stored in the given directory, creating it if needed.
+    pub fn open(dir: impl AsRef<Path>) -> Result<Self> {
+        Self::open_with(dir, BitcaskOptions::default())
+    }
+
+    /// Opens the engine stored in the given directory with the given log options.
+    pub fn open_with(dir: impl AsRef<Path>, options: BitcaskOptions) -> Result<Self> {
+        KvEngine::new(Log::open_with(dir, options)?)
+    }
+
+    #[cfg(test)]
+    /// Creates an engine in a temporary directory, which is removed when the engine is dropped.
+    pub fn new_for_test() -> Self {
+        let temp_dir = tempfile::tempdir().expect("Unable to create temp dir");
+        let mut bitcask = Self::open(temp_dir.path()).expect("Unable to open bitcask");
+        bitcask.temp_dir = Some(temp_dir);
+        bitcask
+    }
+}
+
+impl Store for Log {
+    type ScanIterator<'a> = log::ScanIterator<'a>;
+
+    const NAME: &'static str = "bitcask";
+
+    fn get(&mut self, key: &[u8]) -> Result<Option<Vec<u8>>> {
+        self.get(key)
+    }
+
+    fn set(&mut self, key: &[u8], value: &[u8]) -> Result<()> {
+        self.set(key, value)
+    }
+
+    fn delete(&mut self, key: &[u8]) -> Result<()> {
+        self.delete(key)
+    }
+
+    fn scan_range(&mut self, range: impl RangeBounds<Vec<u8>>) -> Self::ScanIterator<'_> {
+        self.scan(range)
+    }
+
+    fn disk_size(&mut self) -> Result<u64> {
+        Ok(self.status()?.disk_size)
+    }
+}
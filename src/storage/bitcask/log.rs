@@ -0,0 +1,648 @@
+use crate::common::{Error, Result};
+use crate::errdata;
+use crate::storage::kv;
+use std::collections::btree_map::{self, Entry};
+use std::collections::BTreeMap;
+use std::fs::{self, File, OpenOptions};
+use std::io::{BufReader, BufWriter, ErrorKind, Read, Seek, SeekFrom, Write};
+use std::ops::RangeBounds;
+use std::path::{Path, PathBuf};
+use std::thread::JoinHandle;
+
+/// The size of an entry's header: the length of the key as a u32, and the length of the value as
+/// an i32, both big-endian. A value length of [`TOMBSTONE`] marks a deleted key.
+const ENTRY_HEADER_SIZE: u64 = 8;
+
+/// The value length of a tombstone, which has no value.
+const TOMBSTONE: i32 = -1;
+
+/// The size of a hint's header: the length of the key as a u32, the position of the value as a
+/// u64, and the length of the value as a u32, all big-endian.
+const HINT_HEADER_SIZE: usize = 16;
+
+/// Data files hold the entries, and are named by their id, e.g. `00000003.log`.
+const DATA_FILE: &str = "log";
+/// Hint files hold the key and value location of each entry of the data file with the same id.
+const HINT_FILE: &str = "hint";
+/// A merge being written, which is discarded if the merge didn't finish.
+const MERGE_FILE: &str = "merge";
+/// The hint file of a merge being written.
+const MERGE_HINT_FILE: &str = "merge-hint";
+/// A finished merge, which replaces the data files up to its id once it's installed.
+const MERGED_FILE: &str = "merged";
+
+/// Options for a [`Log`].
+#[derive(Clone, Debug)]
+pub struct BitcaskOptions {
+    /// The size in bytes at which the active data file is closed and a new one started.
+    pub max_file_size: u64,
+    /// The fraction of the log's bytes that must be stale before a merge starts on its own.
+    pub merge_threshold: f64,
+    /// The number of bytes that must be stale before a merge starts on its own.
+    pub merge_min_bytes: u64,
+}
+
+impl Default for BitcaskOptions {
+    fn default() -> Self {
+        Self {
+            max_file_size: 64 << 20,
+            merge_threshold: 0.5,
+            merge_min_bytes: 16 << 20,
+        }
+    }
+}
+
+/// Log status.
+#[derive(Clone, Debug, PartialEq)]
+pub struct LogStatus {
+    /// The number of live keys.
+    pub keys: u64,
+    /// The size in bytes of the entries of live keys.
+    pub live_size: u64,
+    /// The size in bytes of stale entries, i.e. overwritten or deleted values and tombstones,
+    /// which a merge would reclaim.
+    pub garbage_size: u64,
+    /// The number of data files.
+    pub files: u64,
+    /// The size in bytes of the files in the log's directory.
+    pub disk_size: u64,
+}
+
+/// The location of a live value in the data files.
+#[derive(Clone, Copy, Debug, PartialEq)]
+struct ValueLocation {
+    file_id: u32,
+    pos: u64,
+    len: u32,
+}
+
+impl ValueLocation {
+    /// Returns the size of the entry that holds the value under the given key.
+    fn entry_size(&self, key: &[u8]) -> u64 {
+        ENTRY_HEADER_SIZE + key.len() as u64 + self.len as u64
+    }
+}
+
+/// Maps each live key to the location of its value.
+type KeyDir = BTreeMap<Vec<u8>, ValueLocation>;
+
+/// Each merged key, with the location of its value before and after the merge.
+type MergedKeys = Vec<(Vec<u8>, ValueLocation, ValueLocation)>;
+
+/// A log-structured key/value store, after [Bitcask](https://riak.com/assets/bitcask-intro.pdf).
+///
+/// Writes are appended to the active data file as entries of the form
+///
+/// | key length | value length | key | value |
+///
+/// and the keydir, an in-memory map from each live key to the location of its latest value, is
+/// updated to point at them. Reads take a single seek. Deletes append a tombstone, an entry
+/// without a value. Once the active file reaches [`BitcaskOptions::max_file_size`], it's closed
+/// and a new one started, so that only the newest data file is ever written to.
+///
+/// Overwritten and deleted values stay in the files until a merge rewrites the live entries of
+/// the closed data files into a single file, along with a hint file that lets the keydir be
+/// rebuilt without reading the values. Merges run on a background thread, and start on their
+/// own once enough of the log is stale, see [`BitcaskOptions`]. A merge is committed by renaming
+/// its file, so a merge interrupted by a crash is either discarded or finished when the log is
+/// reopened.
+///
+/// Opening a log rebuilds the keydir from the hint files where there are some, and by reading
+/// the data files otherwise. An entry that was only partly written is truncated.
+pub struct Log {
+    dir: PathBuf,
+    options: BitcaskOptions,
+    index: Index,
+    files: DataFiles,
+    merge: Option<Merge>,
+}
+
+/// The keydir, along with the sizes of the live and stale entries.
+#[derive(Default)]
+struct Index {
+    keydir: KeyDir,
+    /// The size of the entries of live keys.
+    live_size: u64,
+    /// The size of stale entries.
+    garbage_size: u64,
+}
+
+/// The data files of a log, which are read from, and the active one, which is written to.
+struct DataFiles {
+    dir: PathBuf,
+    readers: BTreeMap<u32, File>,
+    active_id: u32,
+    writer: BufWriter<File>,
+    active_len: u64,
+}
+
+/// A merge running in the background.
+struct Merge {
+    /// The id of the newest file being merged, which the merged file takes.
+    file_id: u32,
+    /// The stale bytes in the files being merged, which the merge reclaims.
+    garbage_size: u64,
+    handle: JoinHandle<Result<MergedKeys>>,
+}
+
+impl Log {
+    /// Opens the log in the given directory with the default options, creating it if needed.
+    pub fn open(dir: impl AsRef<Path>) -> Result<Log> {
+        Self::open_with(dir, BitcaskOptions::default())
+    }
+
+    /// Opens the log in the given directory, creating it if needed.
+    pub fn open_with(dir: impl AsRef<Path>, options: BitcaskOptions) -> Result<Log> {
+        let dir = dir.as_ref().to_path_buf();
+        fs::create_dir_all(&dir)?;
+        recover_merges(&dir)?;
+
+        let mut index = Index::default();
+        let mut readers = BTreeMap::new();
+        let file_ids = file_ids(&dir, DATA_FILE)?;
+        for &file_id in &file_ids {
+            let hint_path = file_path(&dir, file_id, HINT_FILE);
+            match hint_path.exists() {
+                true => index.load_hints(&hint_path, file_id)?,
+                false => index.replay(&dir, file_id)?,
+            }
+            readers.insert(file_id, File::open(file_path(&dir, file_id, DATA_FILE))?);
+        }
+
+        // Merged files have hints that would go stale if they were written to.
+        let active_id = match file_ids.last() {
+            Some(&id) if file_path(&dir, id, HINT_FILE).exists() => id + 1,
+            Some(&id) => id,
+            None => 0,
+        };
+        let active_path = file_path(&dir, active_id, DATA_FILE);
+        let active = open_for_append(&active_path)?;
+        if let Entry::Vacant(entry) = readers.entry(active_id) {
+            entry.insert(File::open(&active_path)?);
+        }
+        let files = DataFiles {
+            dir: dir.clone(),
+            readers,
+            active_id,
+            active_len: active.metadata()?.len(),
+            writer: BufWriter::new(active),
+        };
+        Ok(Log {
+            dir,
+            options,
+            index,
+            files,
+            merge: None,
+        })
+    }
+
+    /// Gets the value of a key, if it exists.
+    pub fn get(&mut self, key: &[u8]) -> Result<Option<Vec<u8>>> {
+        match self.index.keydir.get(key) {
+            Some(location) => self.files.read(location).map(Some),
+            None => Ok(None),
+        }
+    }
+
+    /// Sets the value of a key, replacing any existing value.
+    pub fn set(&mut self, key: &[u8], value: &[u8]) -> Result<()> {
+        if value.len() > i32::MAX as usize {
+            return errdata!("value of {} bytes is too large", value.len());
+        }
+        let location = self.files.append(key, Some(value))?;
+        self.index.apply(key, Some(location));
+        self.after_write()
+    }
+
+    /// Deletes a key, if it exists.
+    pub fn delete(&mut self, key: &[u8]) -> Result<()> {
+        if !self.index.keydir.contains_key(key) {
+            return Ok(());
+        }
+        self.files.append(key, None)?;
+        self.index.apply(key, None);
+        self.after_write()
+    }
+
+    /// Returns the live keys and values in the given range of keys, in key order.
+    pub fn scan(&mut self, range: impl RangeBounds<Vec<u8>>) -> ScanIterator<'_> {
+        ScanIterator {
+            range: self.index.keydir.range(kv::range_bounds(range)),
+            files: &mut self.files,
+        }
+    }
+
+    /// Returns the live keys and values whose keys start with the given prefix, in key order.
+    pub fn scan_prefix(&mut self, prefix: &[u8]) -> ScanIterator<'_> {
+        self.scan(kv::prefix_range(prefix))
+    }
+
+    /// Merges the closed data files, waiting for the merge to finish. Waits for a merge that's
+    /// already running instead of starting another.
+    pub fn merge(&mut self) -> Result<()> {
+        if self.merge.is_none() {
+            self.start_merge()?;
+        }
+        self.finish_merge()
+    }
+
+    /// Starts merging the data files in the background, after closing the active one so that
+    /// all entries written so far are merged. Does nothing if a merge is already running.
+    pub fn start_merge(&mut self) -> Result<()> {
+        if self.merge.is_some() {
+            return Ok(());
+        }
+        self.files.rotate()?;
+        let Some(&file_id) = self
+            .files
+            .readers
+            .keys()
+            .rfind(|&&id| id != self.files.active_id)
+        else {
+            return Ok(());
+        };
+        let entries: Vec<(Vec<u8>, ValueLocation)> = self
+            .index
+            .keydir
+            .iter()
+            .filter(|(_, location)| location.file_id <= file_id)
+            .map(|(key, location)| (key.clone(), *location))
+            .collect();
+        let dir = self.dir.clone();
+        self.merge = Some(Merge {
+            file_id,
+            garbage_size: self.index.garbage_size,
+            handle: std::thread::spawn(move || merge_files(&dir, file_id, entries)),
+        });
+        Ok(())
+    }
+
+    /// Waits for the running merge to finish, if there is one, and installs the merged file.
+    pub fn finish_merge(&mut self) -> Result<()> {
+        let Some(merge) = self.merge.take() else {
+            return Ok(());
+        };
+        let moved = match merge.handle.join() {
+            Ok(moved) => moved,
+            Err(_) => Err(Error::IO("merge thread panicked".to_string())),
+        };
+        let moved = match moved {
+            Ok(moved) => moved,
+            Err(err) => {
+                recover_merges(&self.dir)?;
+                return Err(err);
+            }
+        };
+
+        complete_merge(&self.dir, merge.file_id)?;
+        self.files.readers.retain(|&id, _| id > merge.file_id);
+        let reader = File::open(file_path(&self.dir, merge.file_id, DATA_FILE))?;
+        self.files.readers.insert(merge.file_id, reader);
+        // Keys written since the merge started already point past the merged files.
+        for (key, old, new) in moved {
+            if let Some(location) = self.index.keydir.get_mut(&key) {
+                if *location == old {
+                    *location = new;
+                }
+            }
+        }
+        self.index.garbage_size -= merge.garbage_size;
+        Ok(())
+    }
+
+    /// Flushes the active data file to disk.
+    pub fn flush(&mut self) -> Result<()> {
+        self.files.writer.flush()?;
+        self.files.writer.get_ref().sync_all()?;
+        Ok(())
+    }
+
+    /// Returns the log's status.
+    pub fn status(&mut self) -> Result<LogStatus> {
+        let mut disk_size = 0;
+        for entry in fs::read_dir(&self.dir)? {
+            disk_size += entry?.metadata()?.len();
+        }
+        Ok(LogStatus {
+            keys: self.index.keydir.len() as u64,
+            live_size: self.index.live_size,
+            garbage_size: self.index.garbage_size,
+            files: self.files.readers.len() as u64,
+            disk_size,
+        })
+    }
+
+    /// Installs a finished merge, and starts one if enough of the log is stale.
+    fn after_write(&mut self) -> Result<()> {
+        if self.files.active_len >= self.options.max_file_size {
+            self.files.rotate()?;
+        }
+        match &self.merge {
+            Some(merge) if merge.handle.is_finished() => self.finish_merge(),
+            Some(_) => Ok(()),
+            None => {
+                let Index {
+                    live_size,
+                    garbage_size,
+                    ..
+                } = self.index;
+                if garbage_size >= self.options.merge_min_bytes
+                    && garbage_size as f64
+                        >= self.options.merge_threshold * (live_size + garbage_size) as f64
+                {
+                    self.start_merge()?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+impl Drop for Log {
+    /// Flushes the active file and waits for a running merge. The merge is installed when the
+    /// log is next opened.
+    fn drop(&mut self) {
+        if let Some(merge) = self.merge.take() {
+            let _ = merge.handle.join();
+        }
+        let _ = self.flush();
+    }
+}
+
+impl Index {
+    /// Adds the entries of a hint file to the keydir.
+    fn load_hints(&mut self, path: &Path, file_id: u32) -> Result<()> {
+        let hints = fs::read(path)?;
+        let mut hints = hints.as_slice();
+        while !hints.is_empty() {
+            if hints.len() < HINT_HEADER_SIZE {
+                return errdata!("truncated hint file {}", path.display());
+            }
+            let (header, rest) = hints.split_at(HINT_HEADER_SIZE);
+            let key_len = u32::from_be_bytes(header[0..4].try_into()?) as usize;
+            let location = ValueLocation {
+                file_id,
+                pos: u64::from_be_bytes(header[4..12].try_into()?),
+                len: u32::from_be_bytes(header[12..16].try_into()?),
+            };
+            if rest.len() < key_len {
+                return errdata!("truncated hint file {}", path.display());
+            }
+            let (key, rest) = rest.split_at(key_len);
+            self.apply(key, Some(location));
+            hints = rest;
+        }
+        Ok(())
+    }
+
+    /// Adds the entries of a data file to the keydir. A partly written entry at the end of the
+    /// file, e.g. from a crash during a write, is truncated.
+    fn replay(&mut self, dir: &Path, file_id: u32) -> Result<()> {
+        let file = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .open(file_path(dir, file_id, DATA_FILE))?;
+        let file_len = file.metadata()?.len();
+        let mut reader = BufReader::new(&file);
+        let mut pos = 0;
+        let mut header = [0; ENTRY_HEADER_SIZE as usize];
+        while pos + ENTRY_HEADER_SIZE <= file_len {
+            reader.read_exact(&mut header)?;
+            let key_len = u32::from_be_bytes(header[0..4].try_into()?);
+            let value_len = match i32::from_be_bytes(header[4..8].try_into()?) {
+                TOMBSTONE => None,
+                len if len >= 0 => Some(len as u32),
+                len => return errdata!("invalid value length {len} at {pos} in file {file_id}"),
+            };
+            let value_pos = pos + ENTRY_HEADER_SIZE + key_len as u64;
+            let end = value_pos + value_len.unwrap_or(0) as u64;
+            if end > file_len {
+                break;
+            }
+            let mut key = vec![0; key_len as usize];
+            reader.read_exact(&mut key)?;
+            reader.seek_relative(value_len.unwrap_or(0) as i64)?;
+            let location = value_len.map(|len| ValueLocation {
+                file_id,
+                pos: value_pos,
+                len,
+            });
+            self.apply(&key, location);
+            pos = end;
+        }
+        if pos < file_len {
+            file.set_len(pos)?;
+        }
+        Ok(())
+    }
+
+    /// Applies an entry to the keydir: a value, or a tombstone if `None`.
+    fn apply(&mut self, key: &[u8], location: Option<ValueLocation>) {
+        let old = match location {
+            Some(location) => {
+                self.live_size += location.entry_size(key);
+                self.keydir.insert(key.to_vec(), location)
+            }
+            None => {
+                self.garbage_size += ENTRY_HEADER_SIZE + key.len() as u64;
+                self.keydir.remove(key)
+            }
+        };
+        if let Some(old) = old {
+            self.live_size -= old.entry_size(key);
+            self.garbage_size += old.entry_size(key);
+        }
+    }
+}
+
+impl DataFiles {
+    /// Reads a value.
+    fn read(&mut self, location: &ValueLocation) -> Result<Vec<u8>> {
+        let Some(reader) = self.readers.get_mut(&location.file_id) else {
+            return errdata!("data file {} does not exist", location.file_id);
+        };
+        read_value(reader, location)
+    }
+
+    /// Appends an entry to the active file, and returns the location of its value. A `None`
+    /// value writes a tombstone.
+    fn append(&mut self, key: &[u8], value: Option<&[u8]>) -> Result<ValueLocation> {
+        let entry_len = write_entry(&mut self.writer, key, value)?;
+        self.writer.flush()?;
+        let location = ValueLocation {
+            file_id: self.active_id,
+            pos: self.active_len + ENTRY_HEADER_SIZE + key.len() as u64,
+            len: value.map_or(0, |value| value.len() as u32),
+        };
+        self.active_len += entry_len;
+        Ok(location)
+    }
+
+    /// Closes the active file and starts a new one, unless the active file is empty.
+    fn rotate(&mut self) -> Result<()> {
+        if self.active_len == 0 {
+            return Ok(());
+        }
+        self.writer.flush()?;
+        self.writer.get_ref().sync_all()?;
+        self.open_active(self.active_id + 1)
+    }
+
+    /// Makes the data file with the given id the active one, creating it if needed.
+    fn open_active(&mut self, file_id: u32) -> Result<()> {
+        let path = file_path(&self.dir, file_id, DATA_FILE);
+        let file = open_for_append(&path)?;
+        self.active_len = file.metadata()?.len();
+        self.writer = BufWriter::new(file);
+        self.active_id = file_id;
+        if let Entry::Vacant(entry) = self.readers.entry(file_id) {
+            entry.insert(File::open(path)?);
+        }
+        Ok(())
+    }
+}
+
+/// An iterator over the live keys and values in a range of keys.
+pub struct ScanIterator<'a> {
+    range: btree_map::Range<'a, Vec<u8>, ValueLocation>,
+    files: &'a mut DataFiles,
+}
+
+impl Iterator for ScanIterator<'_> {
+    type Item = Result<(Vec<u8>, Vec<u8>)>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let (key, location) = self.range.next()?;
+        Some(self.files.read(location).map(|value| (key.clone(), value)))
+    }
+}
+
+/// Writes the live entries of the data files up to `file_id` to a merge file and its hint file,
+/// and commits the merge by renaming the merge file. Returns each key with the location of its
+/// value before and after the merge.
+fn merge_files(
+    dir: &Path,
+    file_id: u32,
+    entries: Vec<(Vec<u8>, ValueLocation)>,
+) -> Result<MergedKeys> {
+    let mut readers = BTreeMap::new();
+    let mut data = BufWriter::new(File::create(file_path(dir, file_id, MERGE_FILE))?);
+    let mut hints = BufWriter::new(File::create(file_path(dir, file_id, MERGE_HINT_FILE))?);
+    let mut pos = 0;
+    let mut moved = Vec::with_capacity(entries.len());
+    for (key, old) in entries {
+        let reader = match readers.entry(old.file_id) {
+            Entry::Occupied(entry) => entry.into_mut(),
+            Entry::Vacant(entry) => {
+                entry.insert(File::open(file_path(dir, old.file_id, DATA_FILE))?)
+            }
+        };
+        let value = read_value(reader, &old)?;
+        let new = ValueLocation {
+            file_id,
+            pos: pos + ENTRY_HEADER_SIZE + key.len() as u64,
+            len: old.len,
+        };
+        pos += write_entry(&mut data, &key, Some(&value))?;
+        hints.write_all(&(key.len() as u32).to_be_bytes())?;
+        hints.write_all(&new.pos.to_be_bytes())?;
+        hints.write_all(&new.len.to_be_bytes())?;
+        hints.write_all(&key)?;
+        moved.push((key, old, new));
+    }
+    for file in [data, hints] {
+        file.into_inner()
+            .map_err(|err| err.into_error())?
+            .sync_all()?;
+    }
+    fs::rename(
+        file_path(dir, file_id, MERGE_FILE),
+        file_path(dir, file_id, MERGED_FILE),
+    )?;
+    Ok(moved)
+}
+
+/// Replaces the data files up to `file_id` with the merged file, along with their hint files.
+fn complete_merge(dir: &Path, file_id: u32) -> Result<()> {
+    for id in file_ids(dir, DATA_FILE)? {
+        if id <= file_id {
+            fs::remove_file(file_path(dir, id, DATA_FILE))?;
+        }
+        if id < file_id {
+            remove_if_exists(&file_path(dir, id, HINT_FILE))?;
+        }
+    }
+    // The merge hint file may already have replaced the hint file, if this is a retry.
+    let merge_hint_path = file_path(dir, file_id, MERGE_HINT_FILE);
+    if merge_hint_path.exists() {
+        fs::rename(merge_hint_path, file_path(dir, file_id, HINT_FILE))?;
+    }
+    fs::rename(
+        file_path(dir, file_id, MERGED_FILE),
+        file_path(dir, file_id, DATA_FILE),
+    )?;
+    Ok(())
+}
+
+/// Finishes merges that were committed but not installed, and discards ones that weren't
+/// committed.
+fn recover_merges(dir: &Path) -> Result<()> {
+    for file_id in file_ids(dir, MERGED_FILE)? {
+        complete_merge(dir, file_id)?;
+    }
+    for file_id in file_ids(dir, MERGE_FILE)? {
+        fs::remove_file(file_path(dir, file_id, MERGE_FILE))?;
+    }
+    for file_id in file_ids(dir, MERGE_HINT_FILE)? {
+        fs::remove_file(file_path(dir, file_id, MERGE_HINT_FILE))?;
+    }
+    Ok(())
+}
+
+/// Writes an entry, and returns its size. A `None` value writes a tombstone.
+fn write_entry(writer: &mut impl Write, key: &[u8], value: Option<&[u8]>) -> Result<u64> {
+    let value_len = value.map_or(TOMBSTONE, |value| value.len() as i32);
+    writer.write_all(&(key.len() as u32).to_be_bytes())?;
+    writer.write_all(&value_len.to_be_bytes())?;
+    writer.write_all(key)?;
+    writer.write_all(value.unwrap_or_default())?;
+    Ok(ENTRY_HEADER_SIZE + key.len() as u64 + value.map_or(0, |value| value.len() as u64))
+}
+
+fn read_value(reader: &mut File, location: &ValueLocation) -> Result<Vec<u8>> {
+    let mut value = vec![0; location.len as usize];
+    reader.seek(SeekFrom::Start(location.pos))?;
+    reader.read_exact(&mut value)?;
+    Ok(value)
+}
+
+fn file_path(dir: &Path, file_id: u32, extension: &str) -> PathBuf {
+    dir.join(format!("{file_id:08}.{extension}"))
+}
+
+/// Returns the ids of the files in the directory with the given extension, in ascending order.
+fn file_ids(dir: &Path, extension: &str) -> Result<Vec<u32>> {
+    let mut file_ids = Vec::new();
+    for entry in fs::read_dir(dir)? {
+        let path = entry?.path();
+        if path.extension().and_then(|ext| ext.to_str()) != Some(extension) {
+            continue;
+        }
+        if let Some(Ok(file_id)) = path.file_stem().and_then(|s| s.to_str()).map(str::parse) {
+            file_ids.push(file_id);
+        }
+    }
+    file_ids.sort();
+    Ok(file_ids)
+}
+
+fn open_for_append(path: &Path) -> Result<File> {
+    Ok(OpenOptions::new().create(true).append(true).open(path)?)
+}
+
+fn remove_if_exists(path: &Path) -> Result<()> {
+    match fs::remove_file(path) {
+        Err(err) if err.kind() != ErrorKind::NotFound => Err(err.into()),
+        _ => Ok(()),
+    }
+}
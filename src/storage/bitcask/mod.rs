@@ -0,0 +1,13 @@
+//! A log-structured storage engine based on [Bitcask](https://riak.com/assets/bitcask-intro.pdf).
+//!
+//! [`Log`] is an append-only key/value store: every write is appended to the active log file, and
+//! an in-memory key directory maps each live key to the position of its latest value. Old files
+//! are compacted by merging them in the background. [`Bitcask`] stores tables on top of it.
+
+mod engine;
+mod log;
+#[cfg(test)]
+mod tests;
+
+pub use engine::Bitcask;
+pub use log::{BitcaskOptions, Log, LogStatus, ScanIterator};
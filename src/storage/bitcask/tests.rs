@@ -0,0 +1,247 @@
+use super::*;
+use crate::common::Result;
+use crate::storage::tuple::Row;
+use crate::storage::{Engine, Key};
+use crate::types::field::Field;
+use crate::types::{DataType, SchemaChange, Table};
+use std::fs::{self, OpenOptions};
+use std::path::Path;
+
+crate::storage::engine::tests::test_engine!(Bitcask::new_for_test());
+
+#[test]
+fn test_log_store() {
+    let dir = tempfile::tempdir().unwrap();
+    crate::storage::kv::tests::store(Log::open(dir.path()).unwrap());
+}
+
+fn scan(log: &mut Log, prefix: &[u8]) -> Vec<(Vec<u8>, Vec<u8>)> {
+    log.scan_prefix(prefix).collect::<Result<_>>().unwrap()
+}
+
+fn files_with_extension(dir: &Path, extension: &str) -> usize {
+    fs::read_dir(dir)
+        .unwrap()
+        .filter(|entry| {
+            let path = entry.as_ref().unwrap().path();
+            path.extension().and_then(|ext| ext.to_str()) == Some(extension)
+        })
+        .count()
+}
+
+#[test]
+fn test_log_operations() {
+    let dir = tempfile::tempdir().unwrap();
+    let mut log = Log::open(dir.path()).unwrap();
+    log.set(b"a", b"1").unwrap();
+    log.set(b"b", b"2").unwrap();
+    log.set(b"ba", b"3").unwrap();
+    log.set(b"c", b"").unwrap();
+    log.set(b"a", b"4").unwrap();
+    log.delete(b"b").unwrap();
+    log.delete(b"missing").unwrap();
+
+    assert_eq!(log.get(b"a").unwrap(), Some(b"4".to_vec()));
+    assert_eq!(log.get(b"b").unwrap(), None);
+    assert_eq!(log.get(b"c").unwrap(), Some(vec![]));
+    assert_eq!(scan(&mut log, b"b"), vec![(b"ba".to_vec(), b"3".to_vec())]);
+    assert_eq!(scan(&mut log, b"").len(), 3);
+    drop(log);
+
+    let mut log = Log::open(dir.path()).unwrap();
+    assert_eq!(
+        scan(&mut log, b""),
+        vec![
+            (b"a".to_vec(), b"4".to_vec()),
+            (b"ba".to_vec(), b"3".to_vec()),
+            (b"c".to_vec(), vec![]),
+        ]
+    );
+    let status = log.status().unwrap();
+    assert_eq!(status.keys, 3);
+    assert_eq!(status.live_size, (8 + 2) + (8 + 3) + (8 + 1));
+    // The overwritten value of a, the deleted value of b, and b's tombstone.
+    assert_eq!(status.garbage_size, (8 + 2) + (8 + 2) + (8 + 1));
+}
+
+#[test]
+fn test_log_truncates_torn_entry() {
+    let dir = tempfile::tempdir().unwrap();
+    let mut log = Log::open(dir.path()).unwrap();
+    log.set(b"a", b"1").unwrap();
+    log.set(b"b", b"2").unwrap();
+    drop(log);
+
+    // Cut the last entry short, as if the process crashed while writing it.
+    let path = dir.path().join("00000000.log");
+    let file = OpenOptions::new().write(true).open(&path).unwrap();
+    file.set_len(fs::metadata(&path).unwrap().len() - 1)
+        .unwrap();
+    drop(file);
+
+    let mut log = Log::open(dir.path()).unwrap();
+    assert_eq!(log.get(b"a").unwrap(), Some(b"1".to_vec()));
+    assert_eq!(log.get(b"b").unwrap(), None);
+    log.set(b"c", b"3").unwrap();
+    drop(log);
+
+    let mut log = Log::open(dir.path()).unwrap();
+    assert_eq!(scan(&mut log, b"").len(), 2);
+    assert_eq!(log.get(b"c").unwrap(), Some(b"3".to_vec()));
+}
+
+#[test]
+fn test_log_merge() {
+    let dir = tempfile::tempdir().unwrap();
+    let options = BitcaskOptions {
+        max_file_size: 100,
+        merge_threshold: 1.0,
+        merge_min_bytes: u64::MAX,
+    };
+    let mut log = Log::open_with(dir.path(), options.clone()).unwrap();
+    for round in 0..10u8 {
+        for key in 0..10u8 {
+            log.set(&[key], &[round; 10]).unwrap();
+        }
+    }
+    log.delete(&[0]).unwrap();
+    let before = log.status().unwrap();
+    assert!(before.files > 1, "the log should have rotated");
+
+    log.start_merge().unwrap();
+    // Writes made while the merge runs must win over the merged values.
+    log.set(&[1], b"new").unwrap();
+    log.delete(&[2]).unwrap();
+    log.finish_merge().unwrap();
+
+    let after = log.status().unwrap();
+    assert!(after.disk_size < before.disk_size);
+    assert!(after.garbage_size < before.garbage_size);
+    assert_eq!(after.keys, 8);
+    assert_eq!(files_with_extension(dir.path(), "hint"), 1);
+    assert_eq!(log.get(&[0]).unwrap(), None);
+    assert_eq!(log.get(&[1]).unwrap(), Some(b"new".to_vec()));
+    assert_eq!(log.get(&[2]).unwrap(), None);
+    assert_eq!(log.get(&[3]).unwrap(), Some(vec![9; 10]));
+    drop(log);
+
+    // The merged file is loaded from its hint file.
+    let mut log = Log::open_with(dir.path(), options).unwrap();
+    assert_eq!(log.status().unwrap().keys, 8);
+    assert_eq!(log.get(&[1]).unwrap(), Some(b"new".to_vec()));
+    assert_eq!(log.get(&[2]).unwrap(), None);
+    assert_eq!(log.get(&[9]).unwrap(), Some(vec![9; 10]));
+}
+
+#[test]
+fn test_log_merges_on_its_own() {
+    let dir = tempfile::tempdir().unwrap();
+    let options = BitcaskOptions {
+        max_file_size: 1 << 10,
+        merge_threshold: 0.5,
+        merge_min_bytes: 1 << 10,
+    };
+    let mut log = Log::open_with(dir.path(), options).unwrap();
+    for i in 0..1000u32 {
+        log.set(&(i % 10).to_be_bytes(), &i.to_be_bytes()).unwrap();
+    }
+    log.merge().unwrap();
+    assert!(log.status().unwrap().disk_size < 1000 * 16);
+    for i in 990..1000u32 {
+        assert_eq!(
+            log.get(&(i % 10).to_be_bytes()).unwrap(),
+            Some(i.to_be_bytes().to_vec())
+        );
+    }
+}
+
+#[test]
+fn test_log_recovers_interrupted_merge() {
+    let dir = tempfile::tempdir().unwrap();
+    let mut log = Log::open(dir.path()).unwrap();
+    log.set(b"a", b"1").unwrap();
+    log.set(b"a", b"2").unwrap();
+    log.set(b"b", b"3").unwrap();
+    // The log is dropped before the merge is installed.
+    log.start_merge().unwrap();
+    log.set(b"b", b"4").unwrap();
+    drop(log);
+    assert_eq!(files_with_extension(dir.path(), "merged"), 1);
+
+    let mut log = Log::open(dir.path()).unwrap();
+    assert_eq!(files_with_extension(dir.path(), "merged"), 0);
+    assert_eq!(files_with_extension(dir.path(), "hint"), 1);
+    assert_eq!(log.get(b"a").unwrap(), Some(b"2".to_vec()));
+    assert_eq!(log.get(b"b").unwrap(), Some(b"4".to_vec()));
+    drop(log);
+
+    // A merge that wasn't committed is discarded.
+    fs::write(dir.path().join("00000005.merge"), b"partial").unwrap();
+    fs::write(dir.path().join("00000005.merge-hint"), b"partial").unwrap();
+    let mut log = Log::open(dir.path()).unwrap();
+    assert_eq!(files_with_extension(dir.path(), "merge"), 0);
+    assert_eq!(files_with_extension(dir.path(), "merge-hint"), 0);
+    assert_eq!(scan(&mut log, b"").len(), 2);
+}
+
+#[test]
+fn test_bitcask_survives_restart() {
+    let dir = tempfile::tempdir().unwrap();
+    let mut engine = Bitcask::open(dir.path()).unwrap();
+    let table = Table::builder()
+        .name("people")
+        .column("id", DataType::Int, false, None, None)
+        .column("name", DataType::Text, false, None, Some(5))
+        .primary_key(&["id"])
+        .build();
+    engine.create_table(table).unwrap();
+    engine.create_table(Table::from(DataType::Int)).unwrap();
+    let mut rids = Vec::new();
+    for (id, name) in [(1, "ann"), (2, "bob"), (3, "cat")] {
+        let row = Row::from(vec![Field::from(id), Field::from(name)]);
+        rids.push(engine.insert_row("people", row).unwrap());
+    }
+    engine.delete(Key::new("people", &rids[1])).unwrap();
+    engine.delete_table("").unwrap();
+    let renamed = engine
+        .alter_table("people", SchemaChange::RenameTable("persons".to_string()))
+        .unwrap();
+    drop(engine);
+
+    let mut engine = Bitcask::open(dir.path()).unwrap();
+    assert_eq!(engine.get_table("persons").unwrap(), Some(renamed));
+    assert_eq!(engine.get_table("people").unwrap(), None);
+    assert_eq!(engine.get_table("").unwrap(), None);
+    assert_eq!(
+        engine.lookup("persons", &[Field::from(3)]).unwrap(),
+        Some(rids[2].clone())
+    );
+    assert_eq!(engine.lookup("persons", &[Field::from(2)]).unwrap(), None);
+    assert!(engine
+        .insert_row(
+            "persons",
+            Row::from(vec![Field::from(1), Field::from("dan")])
+        )
+        .is_err());
+
+    // New rows get new record ids, after the ones of the rows written before the restart.
+    let rid = engine
+        .insert_row(
+            "persons",
+            Row::from(vec![Field::from(2), Field::from("dan")]),
+        )
+        .unwrap();
+    assert!(!rids.contains(&rid));
+    let rows = engine
+        .scan("persons")
+        .map(|item| item.map(|(rid, _)| rid))
+        .collect::<Result<Vec<_>>>()
+        .unwrap();
+    assert_eq!(rows, vec![rids[0].clone(), rids[2].clone(), rid]);
+
+    engine.store().merge().unwrap();
+    let status = engine.status().unwrap();
+    assert_eq!(status.name, "bitcask");
+    assert_eq!(status.keys, 3);
+    assert_eq!(status.disk_size, engine.store().status().unwrap().disk_size);
+}
@@ -7,6 +7,20 @@ use std::sync::{Arc, RwLock, RwLockWriteGuard};
 pub type FrameId = usize;
 use crate::storage::buffer::lru_k_replacer::AccessType;
 use crate::storage::page::TablePage;
+use serde::{Deserialize, Serialize};
+
+/// Buffer pool status.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct BufferPoolStatus {
+    /// The number of frames in the pool.
+    pub frames: u64,
+    /// The number of frames holding a page.
+    pub cached_pages: u64,
+    /// The number of cached pages that are pinned, and so can't be evicted.
+    pub pinned_pages: u64,
+    /// The number of cached pages with changes that haven't been written to disk.
+    pub dirty_pages: u64,
+}
 
 #[derive(Copy, Clone, Debug)]
 pub struct FrameMetadata {
@@ -140,15 +154,7 @@ impl BufferPoolManager {
     /// - `Some(PageId)`: The identifier of the newly created page if successful.
     /// - `None`: If no new page could be created due to all frames being in use.
     pub fn new_page(&mut self) -> Option<PageId> {
-        let frame_id = if let Some(free_frame) = self.free_list.pop_front() {
-            free_frame
-        } else {
-            self.replacer.write().unwrap().evict()?
-        };
-        // Avoid accessing an out-of-bounds index
-        if frame_id >= self.pages.len() {
-            self.pages.resize_with(frame_id + 1, || Arc::new(RwLock::new(TablePage::create_invalid_page())));
-        }
+        let frame_id = self.acquire_frame()?;
 
         let mut disk_manager = self.disk_manager.write().unwrap();
         let page_id = disk_manager.allocate_new_page();
@@ -197,17 +203,13 @@ impl BufferPoolManager {
             let page = self.pages.get(frame_id)?;
 
             self.page_table.get_mut(page_id)?.increment_pin_count();
-            self.replacer.write().unwrap().record_access(&frame_id, AccessType::Lookup);
+            let mut replacer = self.replacer.write().unwrap();
+            replacer.record_access(&frame_id, AccessType::Lookup);
+            // The page may have been unpinned earlier; it is pinned again now.
+            replacer.set_evictable(&frame_id, false);
             return Some(Arc::clone(page));
         }
-        let frame_id = if let Some(free_frame) = self.free_list.pop_front() {
-            free_frame
-        } else {
-            self.replacer.write().unwrap().evict()?
-        };
-        if frame_id >= self.pages.len() {
-            self.pages.resize_with(frame_id + 1, || Arc::new(RwLock::new(TablePage::create_invalid_page())));
-        }
+        let frame_id = self.acquire_frame()?;
         let mut disk_manager = self.disk_manager.write().unwrap();
         let page = disk_manager.read_page(page_id);
         let page_handle = Arc::new(RwLock::new(page));
@@ -256,7 +258,10 @@ impl BufferPoolManager {
         } else {
             return false;
         }
-        self.set_is_dirty(page_id, is_dirty);
+        // A clean unpin must not discard modifications made by an earlier pin.
+        if is_dirty {
+            self.set_is_dirty(page_id, true);
+        }
         if should_evict {
             self.replacer.write().unwrap().set_evictable(&frame_id, true);
         }
@@ -320,6 +325,7 @@ impl BufferPoolManager {
                 self.pages.resize_with(frame_id + 1, || Arc::new(RwLock::new(TablePage::create_invalid_page())));
             }
             self.page_table.remove(&page_id);
+            self.replacer.write().unwrap().remove(&frame_id);
             self.pages[frame_id] = Arc::new(RwLock::new(TablePage::create_invalid_page()));
             self.free_list.push_back(frame_id);
             self.disk_manager.write().unwrap().deallocate_page(&page_id);
@@ -332,6 +338,52 @@ impl BufferPoolManager {
         self.pool_size
     }
 
+    /// Returns how many frames hold a page, and how many of those are pinned or dirty.
+    pub fn status(&self) -> BufferPoolStatus {
+        let mut status = BufferPoolStatus {
+            frames: self.pool_size as u64,
+            cached_pages: self.page_table.len() as u64,
+            pinned_pages: 0,
+            dirty_pages: 0,
+        };
+        for (page_id, frame_metadata) in &self.page_table {
+            if frame_metadata.pin_count() > 0 {
+                status.pinned_pages += 1;
+            }
+            if self.get_is_dirty(page_id) {
+                status.dirty_pages += 1;
+            }
+        }
+        status
+    }
+
+    /// Finds a frame to hold a page, taking one from the free list if possible and evicting a page
+    /// otherwise. An evicted page is written back to disk if it is dirty, and is removed from the
+    /// page table so that a later fetch reads it back in from disk.
+    ///
+    /// # Returns
+    /// - `Some(FrameId)`: A frame that is free to be overwritten.
+    /// - `None`: If all frames are in use and non-evictable.
+    fn acquire_frame(&mut self) -> Option<FrameId> {
+        let frame_id = match self.free_list.pop_front() {
+            Some(free_frame) => free_frame,
+            None => {
+                let frame_id = self.replacer.write().unwrap().evict()?;
+                let victim = self.pages[frame_id].read().unwrap().clone();
+                if victim.get_is_dirty() {
+                    self.disk_manager.write().unwrap().write_page(victim.clone());
+                }
+                self.page_table.remove(victim.page_id());
+                frame_id
+            }
+        };
+        // Avoid accessing an out-of-bounds index
+        if frame_id >= self.pages.len() {
+            self.pages.resize_with(frame_id + 1, || Arc::new(RwLock::new(TablePage::create_invalid_page())));
+        }
+        Some(frame_id)
+    }
+
     pub(crate) fn get_is_dirty(&self, page_id: &PageId) -> bool {
         let frame_id = self
             .page_table
@@ -325,6 +325,77 @@ fn test_dirty_pages_eviction() {
     );
 }
 
+#[test]
+fn test_status() {
+    let mut bpm = get_bpm_with_pool_size(5);
+    let page_ids = create_n_pages(&mut bpm, 3);
+    bpm.unpin_page(&page_ids[0], true);
+    bpm.unpin_page(&page_ids[1], false);
+
+    let status = bpm.status();
+    assert_eq!(5, status.frames);
+    assert_eq!(3, status.cached_pages);
+    assert_eq!(1, status.pinned_pages);
+    assert_eq!(1, status.dirty_pages);
+
+    bpm.flush_page(&page_ids[0]);
+    bpm.delete_page(page_ids[1]);
+    let status = bpm.status();
+    assert_eq!(2, status.cached_pages);
+    assert_eq!(0, status.dirty_pages);
+}
+
+#[test]
+fn test_evicted_dirty_page_is_written_back() {
+    let mut bpm = get_bpm_with_pool_size(1);
+    let tuple = Tuple::from(&b"Northwestern"[..]);
+
+    let page_id1 = bpm.new_page().expect(NEW_PAGE_ERR_MSG);
+    fetch_page(&page_id1, &mut bpm)
+        .write()
+        .unwrap()
+        .insert_tuple(TupleMetadata::new(false), tuple.clone())
+        .unwrap();
+    bpm.unpin_page(&page_id1, true);
+    bpm.unpin_page(&page_id1, true);
+
+    // The only frame is reused, so page 1 is evicted and must be read back from disk.
+    let page_id2 = bpm.new_page().expect(NEW_PAGE_ERR_MSG);
+    assert!(!page_in_buffer(&bpm, &page_id1));
+    bpm.unpin_page(&page_id2, false);
+
+    let page = fetch_page(&page_id1, &mut bpm);
+    let page_guard = page.read().unwrap();
+    assert_eq!(page_id1, *page_guard.page_id());
+    assert_eq!(
+        tuple,
+        page_guard.get_tuple(&RecordId::new(page_id1, 0)).unwrap()
+    );
+}
+
+#[test]
+fn test_refetched_page_is_not_evictable() {
+    let mut bpm = get_bpm_with_pool_size(1);
+    let page_id = bpm.new_page().expect(NEW_PAGE_ERR_MSG);
+    bpm.unpin_page(&page_id, false);
+
+    // Fetching the unpinned page pins it again, so its frame can't be taken by a new page.
+    fetch_page(&page_id, &mut bpm);
+    assert!(bpm.new_page().is_none());
+    assert_eq!(page_id, fetch_page_get_id(&page_id, &mut bpm));
+}
+
+#[test]
+fn test_clean_unpin_keeps_dirty_flag() {
+    let mut bpm = get_bpm_with_pool_size(5);
+    let page_id = bpm.new_page().expect(NEW_PAGE_ERR_MSG);
+    fetch_page(&page_id, &mut bpm);
+
+    assert!(bpm.unpin_page(&page_id, true));
+    assert!(bpm.unpin_page(&page_id, false));
+    assert!(bpm.get_is_dirty(&page_id));
+}
+
 /// This test is simulating latches and concurrent access to buffer pool manager, but it does
 /// not require the buffer pool manager to be implemented in a thread-safe manner internally.
 #[test]
@@ -0,0 +1,373 @@
+use crate::common::constants::{COULD_NOT_UNWRAP_BPM_MSG, TUPLE_DOESNT_FIT_MSG};
+use crate::common::{Error, Result};
+use crate::errdata;
+use crate::storage::buffer::buffer_pool_manager::BufferPoolManager;
+use crate::storage::disk::disk_manager::PageId;
+use crate::storage::heap::TableHeap;
+use crate::storage::page::{Page, RecordId};
+use crate::storage::tuple::{Row, Tuple, TupleMetadata};
+use crate::types::field::Field;
+use crate::types::{Column, DataType, Table};
+use std::collections::HashMap;
+use std::sync::{Arc, RwLock};
+
+/// The page reserved for the catalog root, which points to the catalog's own heaps. The disk
+/// manager starts allocating pages at 1, so page 0 is never handed out.
+pub const CATALOG_ROOT_PAGE_ID: PageId = 0;
+
+/// The name of the index that holds a table's primary key.
+const PRIMARY_KEY_INDEX: &str = "primary";
+
+/// Identifies a catalog root, as opposed to a page that was never written.
+const CATALOG_MAGIC: &[u8; 4] = b"RDBC";
+
+/// The system catalog: the definitions of the tables in the database, stored in heap tables of
+/// their own so that they survive restarts.
+///
+/// - `tables` holds a row per table: its id, name, the first pages of its heap and of the heap's
+///   free space map, and the history of its schema changes, see [`Table::alter`].
+/// - `columns` holds a row per column of each table, in column order.
+/// - `indexes` holds a row per index of each table, with the positions of its columns. A table's
+///   primary key is the unique index named [`PRIMARY_KEY_INDEX`].
+///
+/// The heaps are found through the catalog root, a tuple on [`CATALOG_ROOT_PAGE_ID`] holding the
+/// first page of each heap and of its free space map, which is written when the catalog is
+/// created.
+#[derive(Debug)]
+pub(crate) struct SystemCatalog {
+    tables: TableHeap,
+    columns: TableHeap,
+    indexes: TableHeap,
+}
+
+/// A table definition as recorded in the catalog.
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) struct CatalogEntry {
+    pub(crate) table: Table,
+    pub(crate) first_page_id: PageId,
+    pub(crate) fsm_page_id: PageId,
+}
+
+impl SystemCatalog {
+    /// Opens the catalog of the database that `bpm` reads from, creating an empty one if the
+    /// database doesn't have one yet.
+    pub(crate) fn open(bpm: &Arc<RwLock<BufferPoolManager>>) -> Result<SystemCatalog> {
+        let root = {
+            let mut bpm_guard = bpm.write().expect(COULD_NOT_UNWRAP_BPM_MSG);
+            let page = bpm_guard
+                .fetch_page(&CATALOG_ROOT_PAGE_ID)
+                .ok_or(Error::CreationError)?;
+            let root = match page.read()?.tuple_count() {
+                0 => None,
+                _ => Some(page.read()?.get_tuple(&root_rid())?),
+            };
+            bpm_guard.unpin_page(&CATALOG_ROOT_PAGE_ID, false);
+            root
+        };
+
+        let Some(root) = root else {
+            let catalog = SystemCatalog {
+                tables: TableHeap::new(tables_schema(), bpm),
+                columns: TableHeap::new(columns_schema(), bpm),
+                indexes: TableHeap::new(indexes_schema(), bpm),
+            };
+            catalog.write_root(bpm)?;
+            return Ok(catalog);
+        };
+
+        let page_ids: Vec<PageId> = match root.data.strip_prefix(CATALOG_MAGIC) {
+            Some(page_ids) if page_ids.len() == 24 => page_ids
+                .chunks_exact(4)
+                .map(|id| u32::from_le_bytes(id.try_into().expect("chunks have 4 bytes")))
+                .collect(),
+            _ => return errdata!("page {CATALOG_ROOT_PAGE_ID} doesn't hold a catalog root"),
+        };
+        Ok(SystemCatalog {
+            tables: TableHeap::open(tables_schema(), page_ids[0], page_ids[1], bpm)?,
+            columns: TableHeap::open(columns_schema(), page_ids[2], page_ids[3], bpm)?,
+            indexes: TableHeap::open(indexes_schema(), page_ids[4], page_ids[5], bpm)?,
+        })
+    }
+
+    /// Returns every table in the catalog.
+    pub(crate) fn load(&self) -> Result<Vec<CatalogEntry>> {
+        // Each heap is scanned once, and its rows grouped by the table they belong to.
+        let mut columns: HashMap<i32, Vec<(i32, Column)>> = HashMap::new();
+        for (_, column) in scan(&self.columns)? {
+            columns
+                .entry(int(&column, 0)?)
+                .or_default()
+                .push((int(&column, 1)?, decode_column(&column)?));
+        }
+        let mut primary_keys: HashMap<i32, String> = HashMap::new();
+        for (_, index) in scan(&self.indexes)? {
+            if text(&index, 1)? == PRIMARY_KEY_INDEX {
+                primary_keys.insert(int(&index, 0)?, text(&index, 2)?);
+            }
+        }
+
+        let mut entries = Vec::new();
+        for (_, row) in scan(&self.tables)? {
+            let table_id = int(&row, 0)?;
+            let history = match row.get_field(4)? {
+                Field::Blob(history) => bincode::deserialize(&history)?,
+                _ => Vec::new(),
+            };
+
+            let mut columns = columns.remove(&table_id).unwrap_or_default();
+            columns.sort_by_key(|(position, _)| *position);
+            let mut table = Table::builder()
+                .name(&text(&row, 1)?)
+                .columns(columns.into_iter().map(|(_, column)| column).collect())
+                .build();
+            table.set_history(history);
+            if let Some(positions) = primary_keys.remove(&table_id) {
+                let names = positions
+                    .split(',')
+                    .map(|position| match position.parse() {
+                        Ok(position) if position < table.col_count() => {
+                            Ok(table.get_column_name(position))
+                        }
+                        _ => errdata!("invalid primary key column {position} in catalog"),
+                    })
+                    .collect::<Result<Vec<_>>>()?;
+                table.set_primary_key(&names.iter().map(String::as_str).collect::<Vec<_>>())?;
+            }
+
+            entries.push(CatalogEntry {
+                table,
+                first_page_id: page_id(&row, 2)?,
+                fsm_page_id: page_id(&row, 3)?,
+            });
+        }
+        Ok(entries)
+    }
+
+    /// Records a new table whose heap starts at `first_page_id`, and whose free space map starts
+    /// at `fsm_page_id`, and returns its id.
+    pub(crate) fn create_table(
+        &mut self,
+        table: &Table,
+        first_page_id: PageId,
+        fsm_page_id: PageId,
+    ) -> Result<i32> {
+        let table_id = scan(&self.tables)?
+            .iter()
+            .map(|(_, row)| int(row, 0))
+            .try_fold(0, |max, id| id.map(|id| max.max(id)))?
+            + 1;
+        let row = Row::from(vec![
+            Field::from(table_id),
+            Field::from(table.name()),
+            Field::from(first_page_id as i64),
+            Field::from(fsm_page_id as i64),
+            encode_history(table)?,
+        ]);
+        self.tables.insert_tuple(row.to_tuple(&tables_schema())?)?;
+        self.insert_columns(table_id, table)?;
+        self.insert_indexes(table_id, table)?;
+        Ok(table_id)
+    }
+
+    /// Replaces the definition of the table that was named `table_name`, e.g. after it was
+    /// altered.
+    pub(crate) fn update_table(&mut self, table_name: &str, table: &Table) -> Result<()> {
+        let (rid, row) = self.find_table(table_name)?;
+        let table_id = int(&row, 0)?;
+        let updated = Row::from(vec![
+            Field::from(table_id),
+            Field::from(table.name()),
+            row.get_field(2)?,
+            row.get_field(3)?,
+            encode_history(table)?,
+        ]);
+        self.tables
+            .update_tuple(&rid, updated.to_tuple(&tables_schema())?)?;
+        delete_where_table(&mut self.columns, table_id)?;
+        delete_where_table(&mut self.indexes, table_id)?;
+        self.insert_columns(table_id, table)?;
+        self.insert_indexes(table_id, table)
+    }
+
+    /// Removes the table, its columns and its indexes from the catalog.
+    pub(crate) fn delete_table(&mut self, table_name: &str) -> Result<()> {
+        let (rid, row) = self.find_table(table_name)?;
+        let table_id = int(&row, 0)?;
+        self.tables.delete_tuple(&rid)?;
+        delete_where_table(&mut self.columns, table_id)?;
+        delete_where_table(&mut self.indexes, table_id)
+    }
+
+    fn find_table(&self, table_name: &str) -> Result<(RecordId, Row)> {
+        scan(&self.tables)?
+            .into_iter()
+            .find(|(_, row)| text(row, 1).ok().as_deref() == Some(table_name))
+            .ok_or_else(|| Error::InvalidData(format!("table {table_name} is not in the catalog")))
+    }
+
+    fn insert_columns(&mut self, table_id: i32, table: &Table) -> Result<()> {
+        for (position, column) in table.columns().iter().enumerate() {
+            let default = match column.default() {
+                Some(default) => Field::Blob(bincode::serialize(default)?),
+                None => Field::Null,
+            };
+            let row = Row::from(vec![
+                Field::from(table_id),
+                Field::from(position as i32),
+                Field::from(column.get_name()),
+                Field::from(format!("{:?}", column.get_data_type())),
+                Field::from(column.is_nullable()),
+                default,
+                Field::from(column.get_max_str_len() as i32),
+                Field::from(column.collation().to_string()),
+            ]);
+            self.columns
+                .insert_tuple(row.to_tuple(&columns_schema())?)?;
+        }
+        Ok(())
+    }
+
+    fn insert_indexes(&mut self, table_id: i32, table: &Table) -> Result<()> {
+        if table.primary_key().is_empty() {
+            return Ok(());
+        }
+        let positions = table
+            .primary_key()
+            .iter()
+            .map(|position| position.to_string());
+        let row = Row::from(vec![
+            Field::from(table_id),
+            Field::from(PRIMARY_KEY_INDEX),
+            Field::from(positions.collect::<Vec<_>>().join(",")),
+            Field::from(true),
+        ]);
+        self.indexes
+            .insert_tuple(row.to_tuple(&indexes_schema())?)?;
+        Ok(())
+    }
+
+    /// Writes the first page of each of the catalog's heaps and of their free space maps to the
+    /// catalog root.
+    fn write_root(&self, bpm: &Arc<RwLock<BufferPoolManager>>) -> Result<()> {
+        let mut data = CATALOG_MAGIC.to_vec();
+        for heap in [&self.tables, &self.columns, &self.indexes] {
+            data.extend(heap.first_page_id.to_le_bytes());
+            data.extend(heap.fsm_page_id().to_le_bytes());
+        }
+        let mut bpm = bpm.write().expect(COULD_NOT_UNWRAP_BPM_MSG);
+        let page = bpm
+            .fetch_page(&CATALOG_ROOT_PAGE_ID)
+            .ok_or(Error::CreationError)?;
+        page.write()?
+            .insert_tuple(TupleMetadata::new(false), Tuple::from(data))
+            .expect(TUPLE_DOESNT_FIT_MSG);
+        bpm.unpin_page(&CATALOG_ROOT_PAGE_ID, true);
+        bpm.flush_page(&CATALOG_ROOT_PAGE_ID);
+        Ok(())
+    }
+}
+
+fn root_rid() -> RecordId {
+    RecordId::new(CATALOG_ROOT_PAGE_ID, 0)
+}
+
+fn tables_schema() -> Table {
+    Table::builder()
+        .name("rusty_tables")
+        .column("table_id", DataType::Int, false, None, None)
+        .column("name", DataType::Text, false, None, None)
+        .column("first_page_id", DataType::BigInt, false, None, None)
+        .column("fsm_page_id", DataType::BigInt, false, None, None)
+        .column("history", DataType::Blob, true, None, Some(u16::MAX))
+        .build()
+}
+
+fn columns_schema() -> Table {
+    Table::builder()
+        .name("rusty_columns")
+        .column("table_id", DataType::Int, false, None, None)
+        .column("position", DataType::Int, false, None, None)
+        .column("name", DataType::Text, false, None, None)
+        .column("data_type", DataType::Text, false, None, None)
+        .column("nullable", DataType::Bool, false, None, None)
+        .column("default", DataType::Blob, true, None, None)
+        .column("max_str_len", DataType::Int, false, None, None)
+        .column("collation", DataType::Text, false, None, None)
+        .build()
+}
+
+fn indexes_schema() -> Table {
+    Table::builder()
+        .name("rusty_indexes")
+        .column("table_id", DataType::Int, false, None, None)
+        .column("name", DataType::Text, false, None, None)
+        .column("columns", DataType::Text, false, None, None)
+        .column("unique", DataType::Bool, false, None, None)
+        .build()
+}
+
+/// Returns the rows of a catalog heap.
+fn scan(heap: &TableHeap) -> Result<Vec<(RecordId, Row)>> {
+    let schema = heap.schema();
+    heap.iter()
+        .map(|row| {
+            let (rid, tuple) = row?;
+            Ok((rid, Row::from_tuple(tuple, &schema)?))
+        })
+        .collect()
+}
+
+/// Deletes the rows of a catalog heap that belong to the table with the given id.
+fn delete_where_table(heap: &mut TableHeap, table_id: i32) -> Result<()> {
+    for (rid, row) in scan(heap)? {
+        if int(&row, 0)? == table_id {
+            heap.delete_tuple(&rid)?;
+        }
+    }
+    Ok(())
+}
+
+/// The history is only recorded for tables that were altered, as it's empty otherwise.
+fn encode_history(table: &Table) -> Result<Field> {
+    match table.history().is_empty() {
+        true => Ok(Field::Null),
+        false => Ok(Field::Blob(bincode::serialize(table.history())?)),
+    }
+}
+
+fn decode_column(row: &Row) -> Result<Column> {
+    let default = match row.get_field(5)? {
+        Field::Blob(default) => Some(bincode::deserialize(&default)?),
+        _ => None,
+    };
+    let mut column = Column::new(
+        &text(row, 2)?,
+        DataType::parse(&text(row, 3)?)?,
+        row.get_field(4)? == Field::Boolean(true),
+        default,
+        Some(int(row, 6)? as u16),
+    );
+    column.set_collation(text(row, 7)?.parse()?);
+    Ok(column)
+}
+
+fn int(row: &Row, index: usize) -> Result<i32> {
+    match row.get_field(index)? {
+        Field::Integer(i) => Ok(i),
+        field => errdata!("expected an int in catalog column {index}, got {field}"),
+    }
+}
+
+fn page_id(row: &Row, index: usize) -> Result<PageId> {
+    match row.get_field(index)? {
+        Field::BigInt(page_id) => Ok(page_id as PageId),
+        field => errdata!("expected a page id in catalog column {index}, got {field}"),
+    }
+}
+
+fn text(row: &Row, index: usize) -> Result<String> {
+    match row.get_field(index)? {
+        Field::String(s) => Ok(s),
+        field => errdata!("expected text in catalog column {index}, got {field}"),
+    }
+}
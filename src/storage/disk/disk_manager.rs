@@ -1,5 +1,6 @@
 use crate::config::config::{RUSTY_DB_PAGE_SIZE_BYTES, RUST_DB_DATA_DIR};
 use crate::storage::page::{Page, TablePage};
+use std::collections::BTreeSet;
 use std::fs::{File, OpenOptions};
 use std::io::{BufReader, BufWriter, Read, Seek, SeekFrom, Write};
 use std::path::Path;
@@ -14,6 +15,9 @@ pub type PageId = u32;
 #[derive(Debug)]
 pub struct DiskManager {
     current_page_no: AtomicU32,
+    /// Pages that were deallocated, and are handed out again before the file is grown. The list
+    /// is only kept in memory, so pages freed before a restart stay unused after it.
+    free_pages: BTreeSet<PageId>,
     writer: BufWriter<File>,
     reader: BufReader<File>,
 }
@@ -28,11 +32,19 @@ impl DiskManager {
             .create(true)
             .open(path)
             .expect("Unable to create or open file {path}.");
+        // Resume allocating after the last page already in the file, so that reopening a database
+        // doesn't hand out pages that are still in use.
+        let file_len = file
+            .metadata()
+            .expect("Unable to read metadata of file {filename}.")
+            .len();
+        let current_page_no = (file_len / RUSTY_DB_PAGE_SIZE_BYTES as u64).saturating_sub(1);
         let reader = file;
         let writer = reader.try_clone().expect("Unable to clone file {filename}");
 
         DiskManager {
-            current_page_no: AtomicU32::new(0),
+            current_page_no: AtomicU32::new(current_page_no as u32),
+            free_pages: BTreeSet::new(),
             writer: BufWriter::new(writer),
             reader: BufReader::new(reader),
         }
@@ -41,17 +53,22 @@ impl DiskManager {
         Arc::new(RwLock::new(Self::new(filename)))
     }
 
+    /// Allocates an empty page, reusing the lowest deallocated page if there is one.
     pub fn allocate_new_page(&mut self) -> PageId {
-        let page_id = self.increment_and_fetch_page_no();
+        let page_id = match self.free_pages.pop_first() {
+            Some(page_id) => page_id,
+            None => self.increment_and_fetch_page_no(),
+        };
         let new_page = TablePage::builder().page_id(page_id).build();
 
         self.write_page(new_page);
         page_id
     }
 
-    /// No-op for now; a little out of scope for this project :)
-    pub fn deallocate_page(&mut self, _page_id: &PageId) {
-        // no-op
+    /// Frees a page, so that it can be handed out again by [`DiskManager::allocate_new_page`].
+    /// The page's contents stay in the file until then.
+    pub fn deallocate_page(&mut self, page_id: &PageId) {
+        self.free_pages.insert(*page_id);
     }
 
     pub fn read_page(&mut self, page_id: &PageId) -> TablePage {
@@ -84,6 +101,15 @@ impl DiskManager {
             .expect("Unable to flush buffer from write at offset {offset} to disk.");
     }
 
+    /// Returns the size of the database file in bytes.
+    pub fn size(&self) -> u64 {
+        self.reader
+            .get_ref()
+            .metadata()
+            .expect("Unable to read metadata of database file.")
+            .len()
+    }
+
     fn calculate_offset(page_id: &PageId) -> u32 {
         page_id * RUSTY_DB_PAGE_SIZE_BYTES as u32
     }
@@ -104,6 +130,7 @@ impl DiskManager {
 
         DiskManager {
             current_page_no: AtomicU32::new(0),
+            free_pages: BTreeSet::new(),
             writer: BufWriter::new(writer),
             reader: BufReader::new(temp_file.into_file()),
         }
@@ -167,6 +167,44 @@ fn test_multiple_page_write_and_read() {
     }
 }
 
+/// Test that a reopened `DiskManager` doesn't allocate pages that are already in the file.
+#[test]
+fn test_allocate_after_reopen() {
+    let temp_file = NamedTempFile::new_in(RUST_DB_DATA_DIR).expect("Failed to create temp file");
+    let file_name = temp_file.path().file_name().unwrap().to_str().unwrap();
+
+    let last_page_id = {
+        let mut dm = DiskManager::new(file_name);
+        dm.allocate_new_page();
+        dm.allocate_new_page()
+    };
+
+    let mut dm = DiskManager::new(file_name);
+    assert_eq!(last_page_id + 1, dm.allocate_new_page());
+}
+
+/// Test that deallocated pages are reused, emptied, before the file grows.
+#[test]
+fn test_allocate_reuses_deallocated_pages() {
+    let disk_manager = new_disk_manager();
+    let mut dm = disk_manager.write().unwrap();
+    let page_ids: Vec<_> = (0..3).map(|_| dm.allocate_new_page()).collect();
+
+    let mut page = TablePage::builder().page_id(page_ids[0]).build();
+    page.insert_tuple(TupleMetadata::new(false), Tuple::from(&b"stale"[..]))
+        .expect("Failed to insert tuple");
+    dm.write_page(page);
+    dm.deallocate_page(&page_ids[2]);
+    dm.deallocate_page(&page_ids[0]);
+    let size = dm.size();
+
+    assert_eq!(page_ids[0], dm.allocate_new_page());
+    assert_eq!(0, dm.read_page(&page_ids[0]).tuple_count());
+    assert_eq!(page_ids[2], dm.allocate_new_page());
+    assert_eq!(size, dm.size());
+    assert_eq!(page_ids[2] + 1, dm.allocate_new_page());
+}
+
 fn new_disk_manager() -> Arc<RwLock<DiskManager>> {
     DiskManager::new_with_handle_for_test()
 }
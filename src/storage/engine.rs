@@ -1,8 +1,13 @@
-use crate::common::Result;
+use crate::common::{Error, Result};
+use crate::errinput;
+use crate::storage::buffer::buffer_pool_manager::BufferPoolStatus;
 use crate::storage::page::RecordId;
-use crate::storage::tuple::Tuple;
-use crate::types::Table;
+use crate::storage::tuple::{Row, Tuple};
+use crate::types::field::Field;
+use crate::types::{Column, SchemaChange, Table};
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::ops::{Bound, RangeBounds};
 
 pub struct Key<'a> {
     pub table_name: &'a str,
@@ -24,6 +29,9 @@ impl<'a> Key<'a> {
 /// both the input and output of all API calls. For the sake of pedantic clarity and
 /// also because I ran out of time to properly implement it, this storage engine
 /// trait will pass around (table name, record id) pairs instead of byte-stream keys.
+///
+/// [`crate::storage::kv::Store`] is such a byte-stream store, and
+/// [`crate::storage::kv::KvEngine`] implements this trait on top of any of them.
 pub trait Engine: Send {
     /// The iterator returned by scan()
     type ScanIterator<'a>: ScanIterator + 'a
@@ -39,6 +47,10 @@ pub trait Engine: Send {
     /// Gets a table with the given table name.
     fn get_table(&mut self, table_name: &str) -> Result<Option<Table>>;
 
+    /// Changes the schema of a table, and returns the new schema. Rows already stored keep the
+    /// layout they were written with, and are upgraded when read, see [`Table::alter`].
+    fn alter_table(&mut self, table_name: &str, change: SchemaChange) -> Result<Table>;
+
     /// Deletes a key if one exists. Otherwise, does nothing.
     fn delete(&mut self, key: Key) -> Result<()>;
 
@@ -56,13 +68,125 @@ pub trait Engine: Send {
 
     /// Scan, but can be used from trait objects. This iterator uses
     /// dynamic dispatch, which incurs a runtime performance penalty.
-    fn scan_dyn(&mut self) -> Box<dyn ScanIterator + '_>;
+    /// Fails if the table doesn't exist.
+    fn scan_dyn(&mut self, table_name: &str) -> Result<Box<dyn ScanIterator + '_>>;
+
+    /// Creates an iterator over the table's key/value pairs in a range of record ids or primary
+    /// keys, see [`ScanRange`]. Fails if the table doesn't exist, or if the range is over primary
+    /// keys and the table has no primary key.
+    fn scan_range(
+        &mut self,
+        table_name: &str,
+        range: ScanRange,
+    ) -> Result<Box<dyn ScanIterator + '_>>;
 
     /// Updates a tuple corresponding to the given record id with the provided value.
     fn update(&mut self, key: Key, value: Tuple) -> Result<()>;
 
+    /// Finds the record id of the row with the given primary key values, one for each primary
+    /// key column, if the table has such a row. Fails if the table has no primary key.
+    fn lookup(&mut self, table_name: &str, key: &[Field]) -> Result<Option<RecordId>>;
+
     /// Returns engine status.
     fn status(&mut self) -> Result<Status>;
+
+    /// Gets the row for a key, decoded with the table's schema.
+    fn get_row(&mut self, key: Key) -> Result<Row> {
+        let table = self.must_get_table(key.table_name)?;
+        Row::from_tuple(self.get(key)?, &table)
+    }
+
+    /// Inserts a row into the table with name `table_name`, after validating it against the
+    /// table's schema. Fails with [`Error::InvalidInput`] naming the offending column if the row
+    /// doesn't match the schema.
+    fn insert_row(&mut self, table_name: &str, row: Row) -> Result<RecordId> {
+        let table = self.must_get_table(table_name)?;
+        row.validate(&table)?;
+        self.insert(table_name, row.to_tuple(&table)?)
+    }
+
+    /// Replaces the row corresponding to the given key, after validating it against the table's
+    /// schema like [`Engine::insert_row`].
+    fn update_row(&mut self, key: Key, row: Row) -> Result<()> {
+        let table = self.must_get_table(key.table_name)?;
+        row.validate(&table)?;
+        self.update(key, row.to_tuple(&table)?)
+    }
+
+    /// Gets the row with the given primary key values, see [`Engine::lookup`].
+    fn get_by_key(&mut self, table_name: &str, key: &[Field]) -> Result<Option<Row>> {
+        match self.lookup(table_name, key)? {
+            Some(record_id) => self.get_row(Key::new(table_name, &record_id)).map(Some),
+            None => Ok(None),
+        }
+    }
+
+    /// Replaces the row with the given primary key values, like [`Engine::update_row`]. Returns
+    /// true if the row exists and false otherwise.
+    fn update_by_key(&mut self, table_name: &str, key: &[Field], row: Row) -> Result<bool> {
+        match self.lookup(table_name, key)? {
+            Some(record_id) => self
+                .update_row(Key::new(table_name, &record_id), row)
+                .map(|_| true),
+            None => Ok(false),
+        }
+    }
+
+    /// Deletes the row with the given primary key values. Returns true if the row exists and
+    /// false otherwise.
+    fn delete_by_key(&mut self, table_name: &str, key: &[Field]) -> Result<bool> {
+        match self.lookup(table_name, key)? {
+            Some(record_id) => self.delete(Key::new(table_name, &record_id)).map(|_| true),
+            None => Ok(false),
+        }
+    }
+
+    /// Adds a column after the table's existing columns. Existing rows get the column's default
+    /// value, so it must have one.
+    fn add_column(&mut self, table_name: &str, column: Column) -> Result<Table> {
+        self.alter_table(table_name, SchemaChange::AddColumn(column))
+    }
+
+    /// Drops a column from a table.
+    fn drop_column(&mut self, table_name: &str, column_name: &str) -> Result<Table> {
+        self.alter_table(
+            table_name,
+            SchemaChange::DropColumn(column_name.to_string()),
+        )
+    }
+
+    /// Renames a column of a table.
+    fn rename_column(&mut self, table_name: &str, from: &str, to: &str) -> Result<Table> {
+        let change = SchemaChange::RenameColumn {
+            from: from.to_string(),
+            to: to.to_string(),
+        };
+        self.alter_table(table_name, change)
+    }
+
+    /// Renames a table.
+    fn rename_table(&mut self, table_name: &str, new_name: &str) -> Result<Table> {
+        self.alter_table(table_name, SchemaChange::RenameTable(new_name.to_string()))
+    }
+
+    /// Deletes every row of a table, but keeps the table and its schema. Fails if the table
+    /// doesn't exist.
+    fn truncate_table(&mut self, table_name: &str) -> Result<()> {
+        let record_ids = self
+            .scan_dyn(table_name)?
+            .map(|item| item.map(|(record_id, _)| record_id))
+            .collect::<Result<Vec<_>>>()?;
+        for record_id in record_ids {
+            self.delete(Key::new(table_name, &record_id))?;
+        }
+        Ok(())
+    }
+
+    /// Gets the schema of the table with name `table_name`, failing if there is no such table.
+    fn must_get_table(&mut self, table_name: &str) -> Result<Table> {
+        self.get_table(table_name)?
+            .ok_or_else(|| Error::InvalidInput(format!("table {table_name} does not exist")))
+    }
 }
 
 /// A scan iterator over a table
@@ -70,6 +194,98 @@ pub trait ScanIterator: Iterator<Item = Result<(RecordId, Tuple)>> {}
 /// Blanket implementation of ScanIterator for any `I` satisfying the trait bound.
 impl<I: Iterator<Item = Result<(RecordId, Tuple)>>> ScanIterator for I {}
 
+/// The rows of a table that [`Engine::scan_range`] returns, and their order.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ScanRange {
+    pub bounds: ScanBounds,
+    /// Whether the rows are returned in descending order.
+    pub reverse: bool,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum ScanBounds {
+    /// The rows with record ids in the range, in record id order.
+    RecordIds(Bound<RecordId>, Bound<RecordId>),
+    /// The rows with primary keys in the range, in primary key order. Each key has a value for
+    /// each primary key column, like the keys of [`Engine::lookup`].
+    PrimaryKeys(Bound<Vec<Field>>, Bound<Vec<Field>>),
+}
+
+impl ScanRange {
+    /// All rows, in record id order.
+    pub fn all() -> Self {
+        Self::record_ids(..)
+    }
+
+    /// The rows with record ids in the range, in record id order.
+    pub fn record_ids(range: impl RangeBounds<RecordId>) -> Self {
+        Self {
+            bounds: ScanBounds::RecordIds(range.start_bound().cloned(), range.end_bound().cloned()),
+            reverse: false,
+        }
+    }
+
+    /// The rows with primary keys in the range, in primary key order.
+    pub fn primary_keys(range: impl RangeBounds<Vec<Field>>) -> Self {
+        Self {
+            bounds: ScanBounds::PrimaryKeys(
+                range.start_bound().cloned(),
+                range.end_bound().cloned(),
+            ),
+            reverse: false,
+        }
+    }
+
+    /// Returns the same rows in descending order.
+    pub fn reverse(self) -> Self {
+        Self {
+            reverse: !self.reverse,
+            ..self
+        }
+    }
+}
+
+impl Default for ScanRange {
+    fn default() -> Self {
+        Self::all()
+    }
+}
+
+/// The bounds of a range of encoded primary keys.
+type KeyBounds = (Bound<Vec<u8>>, Bound<Vec<u8>>);
+
+/// Encodes the bounds of a primary key range with [`Table::encode_primary_key`]. Fails if the
+/// table has no primary key, even if the range is unbounded.
+pub(crate) fn encode_key_range(
+    schema: &Table,
+    start: &Bound<Vec<Field>>,
+    end: &Bound<Vec<Field>>,
+) -> Result<KeyBounds> {
+    if schema.primary_key().is_empty() {
+        return errinput!("table {} has no primary key", schema.name());
+    }
+    let encode = |bound: &Bound<Vec<Field>>| -> Result<Bound<Vec<u8>>> {
+        Ok(match bound {
+            Bound::Included(key) => Bound::Included(schema.encode_primary_key(key)?),
+            Bound::Excluded(key) => Bound::Excluded(schema.encode_primary_key(key)?),
+            Bound::Unbounded => Bound::Unbounded,
+        })
+    };
+    Ok((encode(start)?, encode(end)?))
+}
+
+/// Returns whether a range can't contain anything. [`BTreeMap::range`] panics on such ranges,
+/// unless they're empty because their bounds are equal and one of them is included.
+pub(crate) fn is_empty_range<T: Ord>(range: &impl RangeBounds<T>) -> bool {
+    match (range.start_bound(), range.end_bound()) {
+        (Bound::Included(start), Bound::Included(end)) => start > end,
+        (Bound::Included(start), Bound::Excluded(end))
+        | (Bound::Excluded(start), Bound::Included(end))
+        | (Bound::Excluded(start), Bound::Excluded(end)) => start >= end,
+        _ => false,
+    }
+}
+
 /// Engine status.
 #[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
 pub struct Status {
@@ -79,4 +295,432 @@ pub struct Status {
     pub keys: u64,
     /// The logical size of live key/value pairs.
     pub size: u64,
+    /// The status of each table, by table name.
+    pub tables: BTreeMap<String, TableStatus>,
+    /// The number of pages allocated in the database file, including free ones.
+    pub pages: u64,
+    /// The size of the database file in bytes.
+    pub disk_size: u64,
+    /// The state of the buffer pool, if the engine has one.
+    pub buffer_pool: Option<BufferPoolStatus>,
+}
+
+/// Table status.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct TableStatus {
+    /// The number of live keys in the table.
+    pub keys: u64,
+    /// The logical size of the table's live key/value pairs.
+    pub size: u64,
+    /// The number of pages the table's rows are stored on, not counting overflow pages.
+    pub pages: u64,
+}
+
+/// A conformance test suite that every [`Engine`] implementation runs, to catch behavior that
+/// differs between them. Invoke [`tests::test_engine!`] with an expression that creates an empty
+/// engine from the implementation's test module.
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use crate::types::DataType;
+
+    macro_rules! test_engine {
+        ($setup:expr) => {
+            fn setup() -> impl $crate::storage::Engine {
+                $setup
+            }
+
+            #[test]
+            fn engine_tables() {
+                $crate::storage::engine::tests::tables(setup())
+            }
+
+            #[test]
+            fn engine_point_operations() {
+                $crate::storage::engine::tests::point_operations(setup())
+            }
+
+            #[test]
+            fn engine_rows() {
+                $crate::storage::engine::tests::rows(setup())
+            }
+
+            #[test]
+            fn engine_scan() {
+                $crate::storage::engine::tests::scan(setup())
+            }
+
+            #[test]
+            fn engine_scan_range() {
+                $crate::storage::engine::tests::scan_range(setup())
+            }
+
+            #[test]
+            fn engine_truncate_table() {
+                $crate::storage::engine::tests::truncate_table(setup())
+            }
+
+            #[test]
+            fn engine_alter_table() {
+                $crate::storage::engine::tests::alter_table(setup())
+            }
+
+            #[test]
+            fn engine_primary_key() {
+                $crate::storage::engine::tests::primary_key(setup())
+            }
+
+            #[test]
+            fn engine_status() {
+                $crate::storage::engine::tests::status(setup())
+            }
+        };
+    }
+    pub(crate) use test_engine;
+
+    fn people() -> Table {
+        Table::builder()
+            .name("people")
+            .column("id", DataType::Int, false, None, None)
+            .column("name", DataType::Text, false, None, Some(10))
+            .build()
+    }
+
+    fn person(id: i32, name: &str) -> Row {
+        Row::from(vec![Field::from(id), Field::from(name)])
+    }
+
+    fn tuple(id: i32, name: &str) -> Tuple {
+        person(id, name).to_tuple(&people()).unwrap()
+    }
+
+    fn scan_all(engine: &mut impl Engine, table_name: &str) -> Vec<(RecordId, Tuple)> {
+        engine.scan(table_name).collect::<Result<Vec<_>>>().unwrap()
+    }
+
+    pub(crate) fn tables(mut engine: impl Engine) {
+        assert_eq!(engine.get_table("people").unwrap(), None);
+        engine.create_table(people()).unwrap();
+        assert_eq!(engine.get_table("people").unwrap(), Some(people()));
+        assert!(engine.create_table(people()).is_err());
+
+        engine.insert("people", tuple(1, "ann")).unwrap();
+        assert!(engine.delete_table("people").unwrap());
+        assert!(!engine.delete_table("people").unwrap());
+        assert_eq!(engine.get_table("people").unwrap(), None);
+        assert!(engine.insert("people", tuple(1, "ann")).is_err());
+
+        engine.create_table(people()).unwrap();
+        assert!(scan_all(&mut engine, "people").is_empty());
+    }
+
+    pub(crate) fn point_operations(mut engine: impl Engine) {
+        engine.create_table(people()).unwrap();
+        let ann = engine.insert("people", tuple(1, "ann")).unwrap();
+        let bob = engine.insert("people", tuple(2, "bob")).unwrap();
+        assert_ne!(ann, bob);
+        assert_eq!(
+            engine.get(Key::new("people", &ann)).unwrap(),
+            tuple(1, "ann")
+        );
+        assert_eq!(
+            engine.get(Key::new("people", &bob)).unwrap(),
+            tuple(2, "bob")
+        );
+
+        let long_name = tuple(2, "robert");
+        engine
+            .update(Key::new("people", &bob), long_name.clone())
+            .unwrap();
+        assert_eq!(engine.get(Key::new("people", &bob)).unwrap(), long_name);
+
+        engine.delete(Key::new("people", &ann)).unwrap();
+        assert!(engine.get(Key::new("people", &ann)).is_err());
+        assert!(engine
+            .update(Key::new("people", &ann), tuple(1, "ann"))
+            .is_err());
+        engine.delete(Key::new("people", &ann)).unwrap();
+        assert_eq!(engine.get(Key::new("people", &bob)).unwrap(), long_name);
+
+        assert!(engine.get(Key::new("pets", &bob)).is_err());
+        assert!(engine.insert("pets", tuple(1, "rex")).is_err());
+    }
+
+    pub(crate) fn rows(mut engine: impl Engine) {
+        engine.create_table(people()).unwrap();
+        let ann = engine.insert_row("people", person(1, "ann")).unwrap();
+        assert_eq!(
+            engine.get_row(Key::new("people", &ann)).unwrap(),
+            person(1, "ann")
+        );
+        engine
+            .update_row(Key::new("people", &ann), person(1, "anna"))
+            .unwrap();
+        assert_eq!(
+            engine.get_row(Key::new("people", &ann)).unwrap(),
+            person(1, "anna")
+        );
+
+        let invalid = Row::from(vec![Field::Null, Field::from("bob")]);
+        assert!(matches!(
+            engine.insert_row("people", invalid.clone()),
+            Err(Error::InvalidInput(_))
+        ));
+        assert!(matches!(
+            engine.update_row(Key::new("people", &ann), invalid),
+            Err(Error::InvalidInput(_))
+        ));
+        assert!(matches!(
+            engine.insert_row("pets", person(1, "rex")),
+            Err(Error::InvalidInput(_))
+        ));
+        assert_eq!(scan_all(&mut engine, "people").len(), 1);
+    }
+
+    pub(crate) fn scan(mut engine: impl Engine) {
+        engine.create_table(people()).unwrap();
+        engine.create_table(Table::from(DataType::Int)).unwrap();
+        let names = ["ann", "bob", "carol", "dan"];
+        let rids: Vec<RecordId> = names
+            .iter()
+            .enumerate()
+            .map(|(id, name)| engine.insert("people", tuple(id as i32, name)).unwrap())
+            .collect();
+        engine.delete(Key::new("people", &rids[1])).unwrap();
+
+        let expected: Vec<(RecordId, Tuple)> = [0, 2, 3]
+            .into_iter()
+            .map(|i| (rids[i].clone(), tuple(i as i32, names[i])))
+            .collect();
+        assert_eq!(scan_all(&mut engine, "people"), expected);
+        let scanned = engine
+            .scan_dyn("people")
+            .unwrap()
+            .collect::<Result<Vec<_>>>()
+            .unwrap();
+        assert_eq!(scanned, expected);
+        assert!(scan_all(&mut engine, "").is_empty());
+        assert!(matches!(
+            engine.scan_dyn("missing"),
+            Err(Error::InvalidInput(_))
+        ));
+    }
+
+    pub(crate) fn scan_range(mut engine: impl Engine) {
+        let mut table = people();
+        table.set_primary_key(&["id"]).unwrap();
+        engine.create_table(table).unwrap();
+        engine.create_table(Table::from(DataType::Int)).unwrap();
+        let names = ["ann", "bob", "carol", "dan", "eve"];
+        // Insert the rows out of primary key order, so that record id and key order differ.
+        let rids: Vec<RecordId> = [3, 0, 4, 1, 2]
+            .into_iter()
+            .map(|id| {
+                engine
+                    .insert_row("people", person(id, names[id as usize]))
+                    .unwrap()
+            })
+            .collect();
+        engine.delete(Key::new("people", &rids[2])).unwrap();
+        let mut scan = |range: ScanRange| -> Vec<RecordId> {
+            engine
+                .scan_range("people", range)
+                .unwrap()
+                .map(|item| item.map(|(record_id, _)| record_id))
+                .collect::<Result<_>>()
+                .unwrap()
+        };
+        let select = |indexes: &[usize]| -> Vec<RecordId> {
+            indexes.iter().map(|&i| rids[i].clone()).collect()
+        };
+
+        assert_eq!(scan(ScanRange::all()), select(&[0, 1, 3, 4]));
+        assert_eq!(scan(ScanRange::all().reverse()), select(&[4, 3, 1, 0]));
+        assert_eq!(
+            scan(ScanRange::record_ids(rids[1].clone()..)),
+            select(&[1, 3, 4])
+        );
+        assert_eq!(
+            scan(ScanRange::record_ids(rids[1].clone()..rids[4].clone())),
+            select(&[1, 3])
+        );
+        assert_eq!(
+            scan(ScanRange::record_ids(rids[1].clone()..=rids[4].clone()).reverse()),
+            select(&[4, 3, 1])
+        );
+        assert_eq!(
+            scan(ScanRange::record_ids((
+                Bound::Excluded(rids[0].clone()),
+                Bound::Excluded(rids[3].clone())
+            ))),
+            select(&[1])
+        );
+        assert_eq!(
+            scan(ScanRange::record_ids(rids[3].clone()..rids[1].clone())),
+            vec![]
+        );
+        assert_eq!(
+            scan(ScanRange::record_ids(rids[1].clone()..rids[1].clone())),
+            vec![]
+        );
+
+        // Keys 0 through 4 are at rids 1, 3, 4, 0 and 2, and key 4 is deleted.
+        let key = |id: i32| vec![Field::from(id)];
+        assert_eq!(scan(ScanRange::primary_keys(..)), select(&[1, 3, 4, 0]));
+        assert_eq!(
+            scan(ScanRange::primary_keys(key(1)..=key(3))),
+            select(&[3, 4, 0])
+        );
+        assert_eq!(
+            scan(ScanRange::primary_keys(key(1)..key(3)).reverse()),
+            select(&[4, 3])
+        );
+        assert_eq!(
+            scan(ScanRange::primary_keys((
+                Bound::Excluded(key(2)),
+                Bound::Unbounded
+            ))),
+            select(&[0])
+        );
+        assert_eq!(scan(ScanRange::primary_keys(key(3)..key(1))), vec![]);
+
+        let rows = engine
+            .scan_range("people", ScanRange::primary_keys(key(3)..))
+            .unwrap()
+            .collect::<Result<Vec<_>>>()
+            .unwrap();
+        assert_eq!(rows, vec![(rids[0].clone(), tuple(3, "dan"))]);
+        assert!(engine.scan_range("pets", ScanRange::all()).is_err());
+        assert!(engine.scan_range("", ScanRange::primary_keys(..)).is_err());
+        assert!(engine
+            .scan_range("people", ScanRange::primary_keys(..key(0)))
+            .unwrap()
+            .next()
+            .is_none());
+    }
+
+    pub(crate) fn truncate_table(mut engine: impl Engine) {
+        let mut table = people();
+        table.set_primary_key(&["id"]).unwrap();
+        engine.create_table(table.clone()).unwrap();
+        engine.create_table(Table::from(DataType::Int)).unwrap();
+        let ann = engine.insert_row("people", person(1, "ann")).unwrap();
+        engine.insert_row("people", person(2, "bob")).unwrap();
+        let other = Row::from(vec![Field::from(7)]);
+        let other_rid = engine.insert_row("", other.clone()).unwrap();
+
+        engine.truncate_table("people").unwrap();
+        assert_eq!(engine.get_table("people").unwrap(), Some(table));
+        assert!(scan_all(&mut engine, "people").is_empty());
+        assert!(engine.get(Key::new("people", &ann)).is_err());
+        assert_eq!(engine.lookup("people", &[Field::from(1)]).unwrap(), None);
+        assert_eq!(engine.get_row(Key::new("", &other_rid)).unwrap(), other);
+
+        let ann = engine.insert_row("people", person(1, "ann")).unwrap();
+        assert_eq!(
+            engine.lookup("people", &[Field::from(1)]).unwrap(),
+            Some(ann.clone())
+        );
+        assert_eq!(
+            scan_all(&mut engine, "people"),
+            vec![(ann, tuple(1, "ann"))]
+        );
+        assert!(engine.truncate_table("pets").is_err());
+    }
+
+    pub(crate) fn alter_table(mut engine: impl Engine) {
+        engine.create_table(people()).unwrap();
+        engine.create_table(Table::from(DataType::Int)).unwrap();
+        let ann = engine.insert_row("people", person(1, "ann")).unwrap();
+
+        let score = Column::new("score", DataType::Int, false, Some(Field::from(0)), None);
+        let table = engine.add_column("people", score).unwrap();
+        assert_eq!(table.version(), 1);
+        assert_eq!(engine.get_table("people").unwrap(), Some(table));
+        let upgraded = Row::from(vec![Field::from(1), Field::from("ann"), Field::from(0)]);
+        assert_eq!(engine.get_row(Key::new("people", &ann)).unwrap(), upgraded);
+
+        engine.drop_column("people", "name").unwrap();
+        engine.rename_column("people", "id", "person_id").unwrap();
+        assert!(engine.rename_table("people", "").is_err());
+        let table = engine.rename_table("people", "persons").unwrap();
+        assert_eq!(table.name(), "persons");
+        assert_eq!(table.get_column_name(0), "person_id");
+        assert_eq!(engine.get_table("people").unwrap(), None);
+        assert_eq!(
+            engine.get_row(Key::new("persons", &ann)).unwrap(),
+            Row::from(vec![Field::from(1), Field::from(0)])
+        );
+
+        assert!(engine.drop_column("persons", "name").is_err());
+        assert!(engine.rename_table("people", "humans").is_err());
+        assert_eq!(engine.get_table("persons").unwrap().unwrap().version(), 4);
+    }
+
+    pub(crate) fn primary_key(mut engine: impl Engine) {
+        let mut table = people();
+        table.set_primary_key(&["id"]).unwrap();
+        engine.create_table(table).unwrap();
+        let mut pets = people();
+        pets.set_name("pets");
+        engine.create_table(pets).unwrap();
+        let ann = engine.insert_row("people", person(1, "ann")).unwrap();
+        let bob = engine.insert_row("people", person(2, "bob")).unwrap();
+
+        assert!(matches!(
+            engine.insert_row("people", person(1, "carol")),
+            Err(Error::InvalidInput(_))
+        ));
+        assert!(engine
+            .update_row(Key::new("people", &bob), person(1, "bob"))
+            .is_err());
+        engine
+            .update_row(Key::new("people", &bob), person(3, "bob"))
+            .unwrap();
+        assert_eq!(
+            engine.lookup("people", &[Field::from(1)]).unwrap(),
+            Some(ann)
+        );
+        assert_eq!(engine.lookup("people", &[Field::from(2)]).unwrap(), None);
+        assert_eq!(
+            engine.get_by_key("people", &[Field::from(3)]).unwrap(),
+            Some(person(3, "bob"))
+        );
+        assert!(engine.lookup("people", &[Field::from("3")]).is_err());
+
+        assert!(engine
+            .update_by_key("people", &[Field::from(1)], person(1, "anna"))
+            .unwrap());
+        assert!(!engine
+            .update_by_key("people", &[Field::from(2)], person(2, "bob"))
+            .unwrap());
+        assert!(engine.delete_by_key("people", &[Field::from(1)]).unwrap());
+        assert!(!engine.delete_by_key("people", &[Field::from(1)]).unwrap());
+        engine.insert_row("people", person(1, "carol")).unwrap();
+        assert_eq!(
+            engine.get_by_key("people", &[Field::from(1)]).unwrap(),
+            Some(person(1, "carol"))
+        );
+
+        engine.insert_row("pets", person(1, "rex")).unwrap();
+        engine.insert_row("pets", person(1, "rex")).unwrap();
+        assert!(engine.lookup("pets", &[Field::from(1)]).is_err());
+        assert!(engine.lookup("cars", &[Field::from(1)]).is_err());
+    }
+
+    pub(crate) fn status(mut engine: impl Engine) {
+        engine.create_table(people()).unwrap();
+        engine.create_table(Table::from(DataType::Int)).unwrap();
+        let ann = engine.insert("people", tuple(1, "ann")).unwrap();
+        engine.insert("people", tuple(2, "bob")).unwrap();
+        engine.delete(Key::new("people", &ann)).unwrap();
+
+        let status = engine.status().unwrap();
+        let people = &status.tables["people"];
+        assert_eq!(people.keys, 1);
+        assert_eq!(people.size, tuple(2, "bob").data.len() as u64);
+        assert_eq!(status.tables[""].keys, 0);
+        assert_eq!(status.tables.len(), 2);
+        assert_eq!((status.keys, status.size), (people.keys, people.size));
+    }
 }
@@ -0,0 +1,237 @@
+use crate::common::constants::{COULD_NOT_UNWRAP_BPM_MSG, INVALID_PID, TUPLE_DOESNT_FIT_MSG};
+use crate::common::{Error, Result};
+use crate::config::config::RUSTY_DB_PAGE_SIZE_BYTES;
+use crate::storage::buffer::buffer_pool_manager::BufferPoolManager;
+use crate::storage::disk::disk_manager::PageId;
+use crate::storage::page::{
+    Page, RecordId, MIN_TUPLE_ALLOCATION, TABLE_PAGE_HEADER_SIZE, TUPLE_INFO_SIZE,
+};
+use crate::storage::tuple::{Tuple, TupleMetadata};
+use std::collections::HashMap;
+use std::sync::{Arc, RwLock};
+
+/// The size in bytes of a serialized entry: | page_id (u32) | category (u8) |.
+const FSM_ENTRY_SIZE: usize = 5;
+
+/// The number of heap pages tracked by a single page of the free space map.
+pub const FSM_ENTRIES_PER_PAGE: usize =
+    (RUSTY_DB_PAGE_SIZE_BYTES - TABLE_PAGE_HEADER_SIZE - TUPLE_INFO_SIZE) / FSM_ENTRY_SIZE;
+
+/// The number of free bytes represented by one step of a page's category.
+const FSM_CATEGORY_SIZE: usize = RUSTY_DB_PAGE_SIZE_BYTES / 256;
+
+/// Tracks roughly how much free space each page of a table heap has, so that inserts can fill
+/// the space left behind by deletes instead of always appending to the last page.
+///
+/// Free space is recorded as a one byte category, i.e. in steps of [`FSM_CATEGORY_SIZE`] bytes,
+/// rounded down. A page is therefore never reported to have more space than it actually has.
+///
+/// The map is persisted in its own chain of [`crate::storage::page::TablePage`]s, each holding a
+/// single fixed-size tuple of [`FSM_ENTRIES_PER_PAGE`] entries. Like overflow pages, the map's
+/// pages are only pinned while they are being read or written.
+#[derive(Debug)]
+pub struct FreeSpaceMap {
+    buffer_pool_manager: Arc<RwLock<BufferPoolManager>>,
+    // the pages storing the map, in the order they are linked
+    page_ids: Vec<PageId>,
+    // (heap page id, category) pairs, in the order the heap pages were added
+    entries: Vec<(PageId, u8)>,
+    positions: HashMap<PageId, usize>,
+}
+
+impl FreeSpaceMap {
+    /// Creates an empty free space map, allocating its first page.
+    pub fn new(bpm: &Arc<RwLock<BufferPoolManager>>) -> Result<FreeSpaceMap> {
+        let mut fsm = FreeSpaceMap {
+            buffer_pool_manager: Arc::clone(bpm),
+            page_ids: Vec::new(),
+            entries: Vec::new(),
+            positions: HashMap::new(),
+        };
+        fsm.append_page()?;
+        Ok(fsm)
+    }
+
+    /// Loads the free space map stored in the chain of pages starting at `first_page_id`.
+    pub fn open(
+        first_page_id: PageId,
+        bpm: &Arc<RwLock<BufferPoolManager>>,
+    ) -> Result<FreeSpaceMap> {
+        let mut fsm = FreeSpaceMap {
+            buffer_pool_manager: Arc::clone(bpm),
+            page_ids: Vec::new(),
+            entries: Vec::new(),
+            positions: HashMap::new(),
+        };
+
+        let mut bpm = bpm.write().expect(COULD_NOT_UNWRAP_BPM_MSG);
+        let mut page_id = first_page_id;
+        while page_id != INVALID_PID {
+            let page = bpm.fetch_page(&page_id).ok_or_else(|| {
+                Error::InvalidData(format!("free space map page {page_id} is missing"))
+            })?;
+            let (data, next_page_id) = {
+                let page_guard = page.read()?;
+                let data = page_guard.get_tuple(&RecordId::new(page_id, 0))?.data;
+                (data, page_guard.get_next_page_id())
+            };
+            bpm.unpin_page(&page_id, false);
+
+            for entry in data.chunks_exact(FSM_ENTRY_SIZE) {
+                let heap_page_id = u32::from_le_bytes(entry[0..4].try_into()?);
+                if heap_page_id == INVALID_PID {
+                    break;
+                }
+                fsm.positions.insert(heap_page_id, fsm.entries.len());
+                fsm.entries.push((heap_page_id, entry[4]));
+            }
+            fsm.page_ids.push(page_id);
+            page_id = next_page_id;
+        }
+
+        if fsm.page_ids.is_empty() {
+            return Err(Error::InvalidData(
+                "free space map has no pages".to_string(),
+            ));
+        }
+        Ok(fsm)
+    }
+
+    /// Returns the id of the map's first page, which is all that is needed to reopen it.
+    pub fn first_page_id(&self) -> PageId {
+        self.page_ids[0]
+    }
+
+    /// Returns the ids of the pages storing the map.
+    pub fn page_ids(&self) -> &[PageId] {
+        &self.page_ids
+    }
+
+    /// Records that the heap page `page_id` has `free_bytes` of free space, starting to track the
+    /// page if it isn't already. The map is only written to when the page's category changes.
+    pub fn update(&mut self, page_id: PageId, free_bytes: usize) -> Result<()> {
+        let category = (free_bytes / FSM_CATEGORY_SIZE).min(u8::MAX as usize) as u8;
+        let position = match self.positions.get(&page_id) {
+            Some(&position) if self.entries[position].1 == category => return Ok(()),
+            Some(&position) => {
+                self.entries[position].1 = category;
+                position
+            }
+            None => {
+                let position = self.entries.len();
+                if position == self.page_ids.len() * FSM_ENTRIES_PER_PAGE {
+                    self.append_page()?;
+                }
+                self.entries.push((page_id, category));
+                self.positions.insert(page_id, position);
+                position
+            }
+        };
+        self.write_entry(position)
+    }
+
+    /// Returns the first heap page known to have room for a payload of `payload_size` bytes.
+    pub fn find_page(&self, payload_size: usize) -> Option<PageId> {
+        let needed = payload_size.max(MIN_TUPLE_ALLOCATION);
+        self.entries
+            .iter()
+            .find(|(_, category)| *category as usize * FSM_CATEGORY_SIZE >= needed)
+            .map(|(page_id, _)| *page_id)
+    }
+
+    /// Returns a lower bound on the free space of the heap page `page_id`, or `None` if the page
+    /// isn't tracked by the map.
+    pub fn free_space(&self, page_id: &PageId) -> Option<usize> {
+        let position = self.positions.get(page_id)?;
+        Some(self.entries[*position].1 as usize * FSM_CATEGORY_SIZE)
+    }
+
+    /// Empties the map. Its first page is kept, so that the map can still be found from there,
+    /// and the ids of the other pages are returned for the caller to delete.
+    pub fn clear(&mut self) -> Result<Vec<PageId>> {
+        let freed = self.page_ids.split_off(1);
+        self.entries.clear();
+        self.positions.clear();
+
+        let page_id = self.first_page_id();
+        let mut bpm = self
+            .buffer_pool_manager
+            .write()
+            .expect(COULD_NOT_UNWRAP_BPM_MSG);
+        let page = bpm.fetch_page(&page_id).ok_or_else(|| {
+            Error::InvalidData(format!("free space map page {page_id} is missing"))
+        })?;
+        {
+            let mut page_guard = page.write()?;
+            page_guard.update_tuple_in_place_unchecked(
+                TupleMetadata::new(false),
+                Tuple::from(empty_entries()),
+                &RecordId::new(page_id, 0),
+            )?;
+            page_guard.set_next_page_id(INVALID_PID);
+        }
+        bpm.unpin_page(&page_id, true);
+        Ok(freed)
+    }
+
+    /// Writes the entry at `position` through to the page storing it.
+    fn write_entry(&self, position: usize) -> Result<()> {
+        let page_id = self.page_ids[position / FSM_ENTRIES_PER_PAGE];
+        let offset = (position % FSM_ENTRIES_PER_PAGE) * FSM_ENTRY_SIZE;
+        let (heap_page_id, category) = self.entries[position];
+
+        let mut bpm = self
+            .buffer_pool_manager
+            .write()
+            .expect(COULD_NOT_UNWRAP_BPM_MSG);
+        let page = bpm.fetch_page(&page_id).ok_or_else(|| {
+            Error::InvalidData(format!("free space map page {page_id} is missing"))
+        })?;
+        {
+            let rid = RecordId::new(page_id, 0);
+            let mut page_guard = page.write()?;
+            let mut data = page_guard.get_tuple(&rid)?.data;
+            data[offset..(offset + 4)].copy_from_slice(&heap_page_id.to_le_bytes());
+            data[offset + 4] = category;
+            page_guard.update_tuple_in_place_unchecked(
+                TupleMetadata::new(false),
+                Tuple::from(data),
+                &rid,
+            )?;
+        }
+        bpm.unpin_page(&page_id, true);
+        Ok(())
+    }
+
+    /// Allocates an empty page for the map and links it to the end of the chain.
+    fn append_page(&mut self) -> Result<()> {
+        let mut bpm = self
+            .buffer_pool_manager
+            .write()
+            .expect(COULD_NOT_UNWRAP_BPM_MSG);
+
+        let page_id = bpm.new_page().ok_or(Error::CreationError)?;
+        let page = bpm.fetch_page(&page_id).ok_or(Error::CreationError)?;
+        page.write()?
+            .insert_tuple(TupleMetadata::new(false), Tuple::from(empty_entries()))
+            .expect(TUPLE_DOESNT_FIT_MSG);
+        // Release the pins taken by both `new_page` and `fetch_page`.
+        bpm.unpin_page(&page_id, true);
+        bpm.unpin_page(&page_id, true);
+
+        if let Some(last_page_id) = self.page_ids.last() {
+            let last_page = bpm.fetch_page(last_page_id).ok_or(Error::CreationError)?;
+            last_page.write()?.set_next_page_id(page_id);
+            bpm.unpin_page(last_page_id, true);
+        }
+        self.page_ids.push(page_id);
+        Ok(())
+    }
+}
+
+/// Returns the contents of a map page without any entries.
+fn empty_entries() -> Vec<u8> {
+    [INVALID_PID.to_le_bytes().as_slice(), &[0]]
+        .concat()
+        .repeat(FSM_ENTRIES_PER_PAGE)
+}
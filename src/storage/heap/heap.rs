@@ -1,13 +1,18 @@
 use crate::common::constants::{
-    COULD_NOT_UNWRAP_BPM_MSG, INVALID_PID, NEW_PAGE_ERR_MSG, TUPLE_DOESNT_FIT_MSG,
+    COULD_NOT_UNWRAP_BPM_MSG, DELETED_TUPLE_MSG, INVALID_PID, NEW_PAGE_ERR_MSG,
+    TUPLE_DOESNT_FIT_MSG,
 };
 use crate::common::{Error, Result};
+use crate::config::config::MAX_INLINE_TUPLE_SIZE_BYTES;
 use crate::storage::buffer::buffer_pool_manager::BufferPoolManager;
 use crate::storage::disk::disk_manager::PageId;
+use crate::storage::heap::free_space_map::FreeSpaceMap;
+use crate::storage::heap::overflow::{OverflowPointer, OVERFLOW_CHUNK_SIZE};
 use crate::storage::page::{Page, RecordId, TablePage, TablePageHandle, TablePageIterator};
 use crate::storage::tuple::{Tuple, TupleMetadata};
 use crate::types::Table;
-use std::sync::{Arc, RwLock};
+use std::collections::HashSet;
+use std::sync::{Arc, Mutex, RwLock};
 
 /// Represents a table stored on disk.
 #[derive(Debug)]
@@ -18,20 +23,77 @@ pub struct TableHeap {
     pub(crate) buffer_pool_manager: Arc<RwLock<BufferPoolManager>>,
     pub(crate) first_page_id: PageId,
     pub(crate) last_page_id: PageId,
+    pub(crate) free_space_map: FreeSpaceMap,
+    /// The heap pages the heap holds a pin on. Heap pages stay pinned once they're fetched, but
+    /// the heap only holds one pin per page, which is released when the page is deleted.
+    pinned: Mutex<HashSet<PageId>>,
 }
 
 impl TableHeap {
     pub fn new(schema: Table, bpm: &Arc<RwLock<BufferPoolManager>>) -> TableHeap {
         let bpm = Arc::clone(&bpm);
         let first_page_id = bpm.write().unwrap().new_page().unwrap().clone();
+        let free_space_map = FreeSpaceMap::new(&bpm).expect(NEW_PAGE_ERR_MSG);
 
-        TableHeap {
+        let mut heap = TableHeap {
             page_cnt: 1,
             schema,
             buffer_pool_manager: bpm,
             first_page_id,
             last_page_id: first_page_id,
+            free_space_map,
+            // The pin taken by `new_page` is kept.
+            pinned: Mutex::new(HashSet::from([first_page_id])),
+        };
+        heap.refresh_free_space(&first_page_id)
+            .expect(NEW_PAGE_ERR_MSG);
+        heap
+    }
+
+    /// Attaches to a heap that was previously created with [`TableHeap::new`], e.g. before the
+    /// database was restarted. The heap's pages are found by following the `next_page_id` links
+    /// from `first_page_id`, and its free space map is loaded from `fsm_page_id`, see
+    /// [`TableHeap::fsm_page_id`].
+    pub fn open(
+        schema: Table,
+        first_page_id: PageId,
+        fsm_page_id: PageId,
+        bpm: &Arc<RwLock<BufferPoolManager>>,
+    ) -> Result<TableHeap> {
+        let bpm = Arc::clone(bpm);
+        let mut visited = HashSet::new();
+        let mut last_page_id = first_page_id;
+        let mut page_id = first_page_id;
+        while page_id != INVALID_PID {
+            let page = bpm
+                .write()
+                .expect(COULD_NOT_UNWRAP_BPM_MSG)
+                .fetch_page(&page_id)
+                .ok_or(Error::CreationError)?;
+            let page_guard = page.read()?;
+            // A page that was never written reads back as zeroes, i.e. with the wrong page id.
+            if *page_guard.page_id() != page_id || visited.contains(&page_id) {
+                return Err(Error::InvalidData(format!(
+                    "page {page_id} is not part of a table heap"
+                )));
+            }
+            visited.insert(page_id);
+            last_page_id = page_id;
+            page_id = page_guard.get_next_page_id();
         }
+
+        let free_space_map = FreeSpaceMap::open(fsm_page_id, &bpm)?;
+
+        Ok(TableHeap {
+            page_cnt: visited.len() as u32,
+            schema,
+            buffer_pool_manager: bpm,
+            first_page_id,
+            last_page_id,
+            free_space_map,
+            // The pins taken while following the links are kept.
+            pinned: Mutex::new(visited),
+        })
     }
 
     pub fn schema(&self) -> Table {
@@ -42,6 +104,12 @@ impl TableHeap {
         self.page_cnt
     }
 
+    /// Returns the first page of the heap's free space map, which has to be recorded alongside
+    /// `first_page_id` to reopen the heap. It doesn't change for the lifetime of the heap.
+    pub fn fsm_page_id(&self) -> PageId {
+        self.free_space_map.first_page_id()
+    }
+
     /// creates a new page and updates corresponding heap metadata.
     pub fn create_new_page(&mut self) -> Result<PageId> {
         let binding = Arc::clone(&self.buffer_pool_manager);
@@ -52,66 +120,147 @@ impl TableHeap {
             None => return Err(Error::CreationError),
         };
 
+        self.pinned.lock()?.insert(new_page_id);
         if let Some(page_handle) = bpm.fetch_page(&self.last_page_id) {
             page_handle.write().unwrap().set_next_page_id(new_page_id);
+            // The heap already holds a pin on its last page.
+            bpm.unpin_page(&self.last_page_id, false);
             self.last_page_id = new_page_id;
             self.page_cnt += 1;
-            Ok(new_page_id)
         } else {
-            Err(Error::CreationError)
+            return Err(Error::CreationError);
         }
-    }
+        drop(bpm);
 
-    /// Fetches the tuple payload corresponding to the given record ID from the table heap.
-    pub fn delete_tuple(&self, rid: &RecordId) -> Result<()> {
-        let page = self.fetch_page_handle(&rid.page_id());
-        let mut page_guard = page.write()?;
+        self.refresh_free_space(&new_page_id)?;
+        Ok(new_page_id)
+    }
 
-        page_guard.update_tuple_metadata(&TupleMetadata::deleted_payload_metadata(), rid)
+    /// Deletes the tuple corresponding to the given record ID from the table heap. Does nothing if
+    /// it was already deleted.
+    pub fn delete_tuple(&mut self, rid: &RecordId) -> Result<()> {
+        let (chain, metadata, stored) = self.locate(rid)?;
+        if metadata.is_deleted() {
+            return Ok(());
+        }
+        chain.iter().try_for_each(|hop| self.mark_deleted(hop))?;
+        self.free_if_overflow(metadata, &stored)
     }
 
     pub fn get_tuple(&self, rid: &RecordId) -> Result<Tuple> {
-        let page = self.fetch_page_handle(&rid.page_id());
-        let page_guard = page.read()?;
-        page_guard.get_tuple(rid)
+        let (_, metadata, stored) = self.locate(rid)?;
+        if metadata.is_deleted() {
+            return Err(Error::InvalidInput(DELETED_TUPLE_MSG.to_string()));
+        }
+        self.resolve_payload(metadata, stored)
+    }
+
+    /// Returns true if the tuple corresponding to the given record ID was deleted.
+    pub(crate) fn is_deleted(&self, rid: &RecordId) -> Result<bool> {
+        Ok(self.locate(rid)?.1.is_deleted())
     }
 
     pub fn insert_tuple(&mut self, tuple: Tuple) -> Result<RecordId> {
-        let _ = self.get_page_slot(&tuple).unwrap_or_else(|| {
-            // tuple payload won't fit in the existing page, make a new page
-            self.create_new_page().expect(NEW_PAGE_ERR_MSG);
-            self.get_page_slot(&tuple).expect(TUPLE_DOESNT_FIT_MSG)
-        });
+        let (metadata, tuple) = self.prepare_payload(tuple)?;
+        self.insert_payload(metadata, tuple)
+    }
 
-        let page = self.fetch_page_handle(&self.last_page_id);
-        let mut page_guard = page.write().unwrap();
-        let metadata = TupleMetadata::new(false);
+    /// Replaces the tuple corresponding to `rid` with `payload`.
+    ///
+    /// `rid` stays valid regardless of the size of the new payload: if the tuple no longer fits on
+    /// its page, it is moved to another page and its original slot forwards to the new location.
+    pub fn update_tuple(&mut self, rid: &RecordId, payload: Tuple) -> Result<()> {
+        let (chain, existing_metadata, existing) = self.locate(rid)?;
+        if existing_metadata.is_deleted() {
+            return Err(Error::InvalidInput(DELETED_TUPLE_MSG.to_string()));
+        }
+        let (mut metadata, payload) = self.prepare_payload(payload)?;
+        metadata.set_relocated(existing_metadata.is_relocated());
 
-        let slot_id = page_guard
-            .insert_tuple(metadata, tuple)
-            .expect(TUPLE_DOESNT_FIT_MSG);
-        Ok(RecordId::new(self.last_page_id, slot_id))
+        let current = chain
+            .last()
+            .expect("a tuple's location includes its own slot");
+        if !self.write_slot(current, metadata, payload.clone())? {
+            // The tuple outgrew its page, so move it to one with enough free space.
+            metadata.set_relocated(true);
+            let new_rid = self.insert_payload(metadata, payload)?;
+            self.forward(rid, &new_rid)?;
+            chain
+                .iter()
+                .skip(1)
+                .try_for_each(|hop| self.mark_deleted(hop))?;
+        }
+        self.free_if_overflow(existing_metadata, &existing)
     }
 
-    pub fn update_tuple(&self, rid: &RecordId, payload: Tuple) -> Result<()> {
-        let page_id = rid.page_id();
+    /// Collapses forwarding pointers left behind by updates. A tuple that was moved off its home
+    /// page is moved back if the page has room for it again. Otherwise, its home slot is pointed
+    /// directly at the tuple's current location, so that a lookup takes at most one hop.
+    ///
+    /// # Returns
+    /// The number of tuples that were moved back to their home slot.
+    pub fn vacuum(&mut self) -> Result<usize> {
+        let mut restored = 0;
+        for page_id in self.page_ids() {
+            let page = self.fetch_page_handle(&page_id);
+            let forwarded: Vec<RecordId> = page
+                .read()?
+                .tuple_info
+                .iter()
+                .enumerate()
+                .filter(|(_, info)| info.metadata.is_forwarded())
+                .map(|(slot, _)| RecordId::new(page_id, slot as u16))
+                .collect();
 
-        let page = self.fetch_page_handle(&page_id);
-        let mut page_guard = page.write().unwrap();
-        let metadata = page_guard.get_tuple_metadata(rid)?;
-
-        // If the tuple has a variable length field and the size of the updated tuple is different
-        // from the existing tuple, delete the existing tuple and insert the new tuple.
-        let existing_size = page_guard.get_tuple(rid)?.data.len();
-        match existing_size == payload.data.len() {
-            true => page_guard.update_tuple_in_place_unchecked(metadata, payload, rid),
-            false => {
-                page_guard
-                    .update_tuple_metadata(&TupleMetadata::deleted_payload_metadata(), rid)?;
-                page_guard.insert_tuple(TupleMetadata::new(false), payload);
-                Ok(())
+            for home in forwarded {
+                let (chain, mut metadata, stored) = self.locate(&home)?;
+                // An earlier collapse on this page may have already removed this hop.
+                if chain.len() == 1 {
+                    continue;
+                }
+                metadata.set_relocated(false);
+                if self.write_slot(&home, metadata, stored)? {
+                    chain
+                        .iter()
+                        .skip(1)
+                        .try_for_each(|hop| self.mark_deleted(hop))?;
+                    restored += 1;
+                } else if chain.len() > 2 {
+                    self.forward(&home, &chain[chain.len() - 1])?;
+                    chain[1..chain.len() - 1]
+                        .iter()
+                        .try_for_each(|hop| self.mark_deleted(hop))?;
+                }
             }
         }
+        Ok(restored)
+    }
+
+    /// Deletes the heap's pages, the pages of its free space map and the overflow pages of its
+    /// tuples, so that the disk manager can reuse them.
+    pub fn delete(self) -> Result<()> {
+        self.free_overflow_chains()?;
+        let mut page_ids = self.page_ids();
+        page_ids.extend_from_slice(self.free_space_map.page_ids());
+        self.free_pages(page_ids)
+    }
+
+    /// Deletes every tuple of the heap. The first pages of the heap and of its free space map are
+    /// kept and emptied, since that's where the heap is found from, and the other pages are
+    /// deleted like in [`TableHeap::delete`].
+    pub fn truncate(&mut self) -> Result<()> {
+        self.free_overflow_chains()?;
+        let mut page_ids = self.page_ids().split_off(1);
+        page_ids.extend(self.free_space_map.clear()?);
+        self.free_pages(page_ids)?;
+
+        let first_page_id = self.first_page_id;
+        let mut first_page = TablePage::builder().page_id(first_page_id).build();
+        first_page.set_is_dirty(true);
+        *self.fetch_page_handle(&first_page_id).write()? = first_page;
+        self.last_page_id = first_page_id;
+        self.page_cnt = 1;
+        self.refresh_free_space(&first_page_id)
     }
 
     pub fn iter(&self) -> TableHeapIterator {
@@ -122,6 +271,26 @@ impl TableHeap {
             heap_file: self,
             current_page_id,
             current_page_iterator,
+            page_ids: None,
+        }
+    }
+
+    /// Returns an iterator over the tuples of the given pages of the heap, which are visited in
+    /// the given order instead of the order they're linked in.
+    pub(crate) fn iter_pages(&self, page_ids: Vec<PageId>) -> TableHeapIterator<'_> {
+        let mut page_ids = page_ids.into_iter();
+        let (current_page_id, current_page_iterator) = match page_ids.next() {
+            Some(page_id) => (page_id, TablePage::iter(self.fetch_page_handle(&page_id))),
+            None => (
+                INVALID_PID,
+                TablePage::iter(Arc::new(RwLock::new(TablePage::create_invalid_page()))),
+            ),
+        };
+        TableHeapIterator {
+            heap_file: self,
+            current_page_id,
+            current_page_iterator,
+            page_ids: Some(page_ids),
         }
     }
 
@@ -130,13 +299,268 @@ impl TableHeap {
             .buffer_pool_manager
             .write()
             .expect(COULD_NOT_UNWRAP_BPM_MSG);
-        bpm.fetch_page(page_id).unwrap()
+        let page = bpm.fetch_page(page_id).unwrap();
+        // Only the first fetch of a page keeps its pin, see `TableHeap::pinned`.
+        if !self.pinned.lock().unwrap().insert(*page_id) {
+            bpm.unpin_page(page_id, false);
+        }
+        page
+    }
+
+    /// Releases the heap's pins on the given pages and deletes them from the buffer pool.
+    fn free_pages(&self, page_ids: Vec<PageId>) -> Result<()> {
+        let mut bpm = self
+            .buffer_pool_manager
+            .write()
+            .expect(COULD_NOT_UNWRAP_BPM_MSG);
+        let mut pinned = self.pinned.lock()?;
+        for page_id in page_ids {
+            if pinned.remove(&page_id) {
+                bpm.unpin_page(&page_id, false);
+            }
+            free_page(&mut bpm, page_id)?;
+        }
+        Ok(())
+    }
+
+    /// Returns the ids of the heap's pages, in the order they are linked.
+    pub(crate) fn page_ids(&self) -> Vec<PageId> {
+        let mut page_ids = Vec::with_capacity(self.page_cnt as usize);
+        let mut page_id = self.first_page_id;
+        while page_id != INVALID_PID {
+            page_ids.push(page_id);
+            page_id = self
+                .fetch_page_handle(&page_id)
+                .read()
+                .unwrap()
+                .get_next_page_id();
+        }
+        page_ids
+    }
+
+    /// Stores an already prepared payload in the first page the free space map finds room on,
+    /// appending a new page if none of the existing ones has enough.
+    fn insert_payload(&mut self, metadata: TupleMetadata, tuple: Tuple) -> Result<RecordId> {
+        let page_id = match self.free_space_map.find_page(tuple.data.len()) {
+            Some(page_id) => page_id,
+            // tuple payload won't fit in any existing page, make a new page
+            None => self.create_new_page()?,
+        };
+
+        let slot_id = self
+            .fetch_page_handle(&page_id)
+            .write()?
+            .insert_tuple(metadata, tuple)
+            .expect(TUPLE_DOESNT_FIT_MSG);
+        self.refresh_free_space(&page_id)?;
+        Ok(RecordId::new(page_id, slot_id))
+    }
+
+    /// Records the current free space of the heap page `page_id` in the free space map.
+    fn refresh_free_space(&mut self, page_id: &PageId) -> Result<()> {
+        let free_space = self.fetch_page_handle(page_id).read()?.free_space();
+        self.free_space_map.update(*page_id, free_space)
+    }
+
+    /// Follows forwarding pointers from `rid` to the slot that stores the tuple's payload.
+    ///
+    /// # Returns
+    /// The slots visited, starting at `rid` and ending at the slot storing the payload, along with
+    /// that slot's metadata and payload.
+    fn locate(&self, rid: &RecordId) -> Result<(Vec<RecordId>, TupleMetadata, Tuple)> {
+        let mut chain = vec![rid.clone()];
+        loop {
+            let current = chain.last().expect("the chain starts at `rid`");
+            let page = self.fetch_page_handle(&current.page_id());
+            let page_guard = page.read()?;
+            let metadata = page_guard.get_tuple_metadata(current)?;
+            let stored = page_guard.get_tuple(current)?;
+            if !metadata.is_forwarded() {
+                return Ok((chain, metadata, stored));
+            }
+
+            let next = RecordId::from_bytes(&stored.data)?;
+            if chain.contains(&next) {
+                return Err(Error::InvalidData(format!(
+                    "forwarding pointers from {} form a cycle",
+                    rid.to_string()
+                )));
+            }
+            chain.push(next);
+        }
+    }
+
+    /// Overwrites the slot `rid` with `payload`, see [`TablePage::update_tuple`].
+    fn write_slot(
+        &mut self,
+        rid: &RecordId,
+        metadata: TupleMetadata,
+        payload: Tuple,
+    ) -> Result<bool> {
+        let written = self
+            .fetch_page_handle(&rid.page_id())
+            .write()?
+            .update_tuple(metadata, payload, rid)?;
+        self.refresh_free_space(&rid.page_id())?;
+        Ok(written)
+    }
+
+    /// Turns the slot `rid` into a forwarding pointer to `target`.
+    fn forward(&mut self, rid: &RecordId, target: &RecordId) -> Result<()> {
+        let pointer = Tuple::from(target.to_bytes()?);
+        match self.write_slot(rid, TupleMetadata::forwarding_payload_metadata(), pointer)? {
+            true => Ok(()),
+            // Every slot reserves room for a forwarding pointer.
+            false => Err(Error::InvalidData(RecordId::invalid_rid_message(rid))),
+        }
+    }
+
+    fn mark_deleted(&mut self, rid: &RecordId) -> Result<()> {
+        self.fetch_page_handle(&rid.page_id())
+            .write()?
+            .update_tuple_metadata(&TupleMetadata::deleted_payload_metadata(), rid)?;
+        self.refresh_free_space(&rid.page_id())
+    }
+
+    /// Returns the metadata and payload to store in a heap page for `tuple`. Tuples larger than
+    /// [`MAX_INLINE_TUPLE_SIZE_BYTES`] are written to a chain of overflow pages, and the payload
+    /// stored in the heap page is an [`OverflowPointer`] to the chain.
+    fn prepare_payload(&self, tuple: Tuple) -> Result<(TupleMetadata, Tuple)> {
+        if tuple.data.len() <= MAX_INLINE_TUPLE_SIZE_BYTES {
+            return Ok((TupleMetadata::new(false), tuple));
+        }
+        let pointer = self.write_overflow_chain(&tuple.data)?;
+        Ok((
+            TupleMetadata::overflow_payload_metadata(),
+            Tuple::from(pointer.to_bytes()),
+        ))
+    }
+
+    /// Returns the tuple for a payload stored in a heap page, reassembling it from its overflow
+    /// pages if it is stored out of line.
+    pub(crate) fn resolve_payload(&self, metadata: TupleMetadata, stored: Tuple) -> Result<Tuple> {
+        match metadata.is_overflow() {
+            true => self.read_overflow_chain(&OverflowPointer::from_bytes(&stored.data)?),
+            false => Ok(stored),
+        }
+    }
+
+    fn free_if_overflow(&self, metadata: TupleMetadata, stored: &Tuple) -> Result<()> {
+        match metadata.is_overflow() {
+            true => self.free_overflow_chain(&OverflowPointer::from_bytes(&stored.data)?),
+            false => Ok(()),
+        }
+    }
+
+    /// Splits `data` across newly allocated overflow pages and returns a pointer to the first one.
+    ///
+    /// Unlike heap pages, overflow pages are only pinned while they are being read or written, so
+    /// large payloads don't tie up the buffer pool.
+    pub(crate) fn write_overflow_chain(&self, data: &[u8]) -> Result<OverflowPointer> {
+        let mut bpm = self
+            .buffer_pool_manager
+            .write()
+            .expect(COULD_NOT_UNWRAP_BPM_MSG);
+
+        // Chunks are written back to front, so that each page can be linked to its successor.
+        let mut next_page_id = INVALID_PID;
+        for chunk in data.chunks(OVERFLOW_CHUNK_SIZE).rev() {
+            let page_id = bpm.new_page().ok_or(Error::CreationError)?;
+            let page = bpm.fetch_page(&page_id).ok_or(Error::CreationError)?;
+            {
+                let mut page_guard = page.write()?;
+                page_guard.set_next_page_id(next_page_id);
+                page_guard
+                    .insert_tuple(TupleMetadata::new(false), Tuple::from(chunk))
+                    .expect(TUPLE_DOESNT_FIT_MSG);
+            }
+            // Release the pins taken by both `new_page` and `fetch_page`.
+            bpm.unpin_page(&page_id, true);
+            bpm.unpin_page(&page_id, true);
+            next_page_id = page_id;
+        }
+        Ok(OverflowPointer::new(next_page_id, data.len() as u32))
     }
 
-    pub(crate) fn get_page_slot(&self, payload: &Tuple) -> Option<u16> {
-        let page = self.fetch_page_handle(&self.last_page_id);
-        let offset = page.read().unwrap().get_next_tuple_offset(payload);
-        offset
+    /// Reassembles a payload from the chain of overflow pages that `pointer` refers to.
+    pub(crate) fn read_overflow_chain(&self, pointer: &OverflowPointer) -> Result<Tuple> {
+        let mut bpm = self
+            .buffer_pool_manager
+            .write()
+            .expect(COULD_NOT_UNWRAP_BPM_MSG);
+
+        let mut data = Vec::with_capacity(pointer.len as usize);
+        let mut page_id = pointer.first_page_id;
+        while page_id != INVALID_PID {
+            let page = bpm
+                .fetch_page(&page_id)
+                .ok_or_else(|| Error::InvalidData(format!("overflow page {page_id} is missing")))?;
+            let next_page_id = {
+                let page_guard = page.read()?;
+                data.extend(page_guard.get_tuple(&RecordId::new(page_id, 0))?.data);
+                page_guard.get_next_page_id()
+            };
+            bpm.unpin_page(&page_id, false);
+            page_id = next_page_id;
+        }
+
+        if data.len() != pointer.len as usize {
+            return Err(Error::InvalidData(format!(
+                "overflow chain holds {} bytes, expected {}",
+                data.len(),
+                pointer.len
+            )));
+        }
+        Ok(Tuple::from(data))
+    }
+
+    /// Deletes the overflow pages of every tuple in the heap.
+    fn free_overflow_chains(&self) -> Result<()> {
+        for page_id in self.page_ids() {
+            let mut tuples = TablePage::iter(self.fetch_page_handle(&page_id));
+            while let Some((_, metadata, stored)) = tuples.next_with_metadata() {
+                self.free_if_overflow(metadata, &stored)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Deletes every page in the overflow chain that `pointer` refers to.
+    pub(crate) fn free_overflow_chain(&self, pointer: &OverflowPointer) -> Result<()> {
+        let mut bpm = self
+            .buffer_pool_manager
+            .write()
+            .expect(COULD_NOT_UNWRAP_BPM_MSG);
+
+        let mut page_id = pointer.first_page_id;
+        while page_id != INVALID_PID {
+            let page = bpm
+                .fetch_page(&page_id)
+                .ok_or_else(|| Error::InvalidData(format!("overflow page {page_id} is missing")))?;
+            let next_page_id = page.read()?.get_next_page_id();
+            bpm.unpin_page(&page_id, false);
+            bpm.delete_page(page_id);
+            page_id = next_page_id;
+        }
+        Ok(())
+    }
+}
+
+/// Evicts a page from the buffer pool and deletes it. Fails if the page is still pinned by
+/// someone else, e.g. another heap opened on the same pages.
+fn free_page(bpm: &mut BufferPoolManager, page_id: PageId) -> Result<()> {
+    // Only pages in the buffer pool can be deleted.
+    if bpm.fetch_page(&page_id).is_none() {
+        return Err(Error::InvalidData(format!(
+            "page {page_id} could not be found"
+        )));
+    }
+    bpm.unpin_page(&page_id, false);
+    match bpm.delete_page(page_id) {
+        true => Ok(()),
+        false => Err(Error::InvalidData(format!(
+            "page {page_id} is still pinned and could not be deleted"
+        ))),
     }
 }
 
@@ -146,20 +570,35 @@ pub struct TableHeapIterator<'a> {
     heap_file: &'a TableHeap,
     current_page_id: PageId,
     current_page_iterator: TablePageIterator,
+    /// The pages left to visit, if they aren't visited in the order they're linked in.
+    page_ids: Option<std::vec::IntoIter<PageId>>,
 }
 
 impl Iterator for TableHeapIterator<'_> {
-    type Item = (RecordId, Tuple);
+    type Item = Result<(RecordId, Tuple)>;
 
     /// Returns `Some(tuple)` if a tuple exists at the iterator's current slot in the page, and
-    /// `None` if the iterator is at the end of the page and there aren't anymore tuples.
+    /// `None` if the iterator is at the end of the page and there aren't anymore tuples. A tuple
+    /// that can't be read from its stored location is returned as an error.
     fn next(&mut self) -> Option<Self::Item> {
-        while self.current_page_id <= self.heap_file.last_page_id {
+        // Pages are reused once deleted, so the page ids of a heap aren't necessarily ascending.
+        while self.current_page_id != INVALID_PID {
             // our page iterator produced a valid tuple!
-            if let Some(item) = self.current_page_iterator.next() {
-                return Some(item);
+            if let Some((rid, metadata, stored)) = self.current_page_iterator.next_with_metadata() {
+                // Relocated tuples are returned at their home slot, which forwards to them.
+                if metadata.is_relocated() {
+                    continue;
+                }
+                let tuple = match metadata.is_forwarded() {
+                    true => self.heap_file.get_tuple(&rid),
+                    false => self.heap_file.resolve_payload(metadata, stored),
+                };
+                return Some(tuple.map(|tuple| (rid, tuple)));
             }
-            let next_page_id = self.current_page_iterator.next_page_id();
+            let next_page_id = match &mut self.page_ids {
+                Some(page_ids) => page_ids.next().unwrap_or(INVALID_PID),
+                None => self.current_page_iterator.next_page_id(),
+            };
             match next_page_id {
                 // that was the last page in the heap file
                 INVALID_PID => break,
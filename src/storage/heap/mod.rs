@@ -1,5 +1,9 @@
+mod free_space_map;
 mod heap;
+mod overflow;
 #[cfg(test)]
 mod tests;
 
+pub use free_space_map::{FreeSpaceMap, FSM_ENTRIES_PER_PAGE};
 pub use heap::{TableHeap, TableHeapIterator};
+pub use overflow::{OverflowPointer, OVERFLOW_CHUNK_SIZE};
@@ -0,0 +1,55 @@
+use crate::common::{Error, Result};
+use crate::config::config::RUSTY_DB_PAGE_SIZE_BYTES;
+use crate::storage::disk::disk_manager::PageId;
+use crate::storage::page::{TABLE_PAGE_HEADER_SIZE, TUPLE_INFO_SIZE};
+
+/// The number of payload bytes stored on a single overflow page. An overflow page is an ordinary
+/// [`crate::storage::page::TablePage`] holding exactly one chunk of the payload in slot 0, chained
+/// to the page holding the next chunk through its `next_page_id`.
+pub const OVERFLOW_CHUNK_SIZE: usize =
+    RUSTY_DB_PAGE_SIZE_BYTES - TABLE_PAGE_HEADER_SIZE - TUPLE_INFO_SIZE;
+
+/// Stored in a heap page's slot in place of a tuple that is too large to be stored inline. It
+/// locates the first page of the overflow chain that holds the actual payload.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct OverflowPointer {
+    pub first_page_id: PageId,
+    /// The length in bytes of the whole payload.
+    pub len: u32,
+}
+
+impl OverflowPointer {
+    /// The size in bytes of a serialized overflow pointer.
+    pub const SIZE: usize = 8;
+
+    pub fn new(first_page_id: PageId, len: u32) -> Self {
+        Self { first_page_id, len }
+    }
+
+    /// Serializes the pointer as | first_page_id (u32) | len (u32) |.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(Self::SIZE);
+        bytes.extend_from_slice(&self.first_page_id.to_le_bytes());
+        bytes.extend_from_slice(&self.len.to_le_bytes());
+        bytes
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self> {
+        if bytes.len() != Self::SIZE {
+            return Err(Error::InvalidData(format!(
+                "overflow pointer must be {} bytes, found {}",
+                Self::SIZE,
+                bytes.len()
+            )));
+        }
+        Ok(Self {
+            first_page_id: u32::from_le_bytes(bytes[0..4].try_into()?),
+            len: u32::from_le_bytes(bytes[4..8].try_into()?),
+        })
+    }
+
+    /// The number of overflow pages needed to hold the payload.
+    pub fn page_count(&self) -> usize {
+        (self.len as usize).div_ceil(OVERFLOW_CHUNK_SIZE)
+    }
+}
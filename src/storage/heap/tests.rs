@@ -1,13 +1,15 @@
 use crate::common::constants::NEW_PAGE_ERR_MSG;
 use crate::common::{utility, Error, Result};
+use crate::config::config::RUST_DB_DATA_DIR;
 use crate::storage::buffer::buffer_pool_manager::BufferPoolManager;
 use crate::storage::disk::disk_manager::DiskManager;
-use crate::storage::heap::TableHeap;
+use crate::storage::heap::{FreeSpaceMap, OverflowPointer, TableHeap};
 use crate::storage::page::{Page, RecordId, TablePage, TablePageHandle};
-use crate::storage::tuple::Row;
+use crate::storage::tuple::{Row, Tuple, TupleMetadata};
 use crate::types::Table;
 use rand::Rng;
 use std::sync::{Arc, RwLock, RwLockReadGuard};
+use tempfile::NamedTempFile;
 
 #[test]
 fn test_heap_file_initialization() {
@@ -156,7 +158,7 @@ fn test_iter() {
     // Iterator should output tuples in sequential order...
     rows.iter().for_each(|(_rid, row)| {
         assert_eq!(
-            Row::from_tuple(it.next().unwrap().1, &table_schema).unwrap(),
+            Row::from_tuple(it.next().unwrap().unwrap().1, &table_schema).unwrap(),
             *row
         )
     });
@@ -165,6 +167,475 @@ fn test_iter() {
     assert!(it.next().is_none());
 }
 
+#[test]
+fn test_insert_overflow_tuple() {
+    let mut heap_file = create_random_heap_file();
+
+    // Spans three overflow pages.
+    let tuple = create_large_tuple(10_000);
+    let rid = heap_file.insert_tuple(tuple.clone()).unwrap();
+    assert_eq!(tuple, heap_file.get_tuple(&rid).unwrap());
+
+    // The heap page only stores a pointer to the overflow chain.
+    let page = heap_file.fetch_page_handle(&rid.page_id());
+    let page_guard = page.read().unwrap();
+    assert!(page_guard.get_tuple_metadata(&rid).unwrap().is_overflow());
+    let pointer = OverflowPointer::from_bytes(&page_guard.get_tuple(&rid).unwrap().data).unwrap();
+    assert_eq!(10_000, pointer.len);
+    assert_eq!(3, pointer.page_count());
+
+    // Overflow pages are not part of the heap's page chain.
+    assert_eq!(1, heap_file.num_pages());
+}
+
+#[test]
+fn test_iter_returns_unreadable_tuple_as_error() {
+    let mut heap_file = create_random_heap_file();
+    let rid = heap_file.insert_tuple(create_large_tuple(10_000)).unwrap();
+
+    // Claim a longer payload than the overflow chain holds.
+    let mut pointer = get_overflow_pointer(&heap_file, &rid);
+    pointer.len += 1;
+    let page = heap_file.fetch_page_handle(&rid.page_id());
+    let mut page_guard = page.write().unwrap();
+    let metadata = page_guard.get_tuple_metadata(&rid).unwrap();
+    page_guard
+        .update_tuple_in_place_unchecked(metadata, Tuple::from(pointer.to_bytes()), &rid)
+        .unwrap();
+    drop(page_guard);
+
+    let mut it = heap_file.iter();
+    assert!(matches!(it.next(), Some(Err(Error::InvalidData(_)))));
+    assert!(it.next().is_none());
+}
+
+#[test]
+fn test_iter_overflow_tuples() {
+    let mut heap_file = create_random_heap_file();
+
+    let tuples: Vec<Tuple> = (0..20)
+        .map(|i| match i % 3 {
+            0 => create_large_tuple(5_000 + i * 100),
+            _ => create_large_tuple(100 + i),
+        })
+        .collect();
+    let rids: Vec<RecordId> = tuples
+        .iter()
+        .map(|tuple| heap_file.insert_tuple(tuple.clone()).unwrap())
+        .collect();
+
+    let scanned: Vec<(RecordId, Tuple)> = heap_file.iter().collect::<Result<_>>().unwrap();
+    assert_eq!(
+        rids,
+        scanned
+            .iter()
+            .map(|(rid, _)| rid.clone())
+            .collect::<Vec<_>>()
+    );
+    assert_eq!(
+        tuples,
+        scanned.into_iter().map(|(_, t)| t).collect::<Vec<_>>()
+    );
+}
+
+#[test]
+fn test_update_and_delete_overflow_tuple() {
+    let mut heap_file = create_random_heap_file();
+
+    let rid = heap_file.insert_tuple(create_large_tuple(9_000)).unwrap();
+    let first_pointer = get_overflow_pointer(&heap_file, &rid);
+
+    // Replacing a large tuple with another large tuple keeps the record id.
+    let updated = create_large_tuple(6_000);
+    heap_file.update_tuple(&rid, updated.clone()).unwrap();
+    assert_eq!(updated, heap_file.get_tuple(&rid).unwrap());
+
+    // ...and releases the pages of the replaced payload.
+    assert!(!overflow_chain_in_buffer_pool(&heap_file, &first_pointer));
+
+    let second_pointer = get_overflow_pointer(&heap_file, &rid);
+    assert!(overflow_chain_in_buffer_pool(&heap_file, &second_pointer));
+    heap_file.delete_tuple(&rid).unwrap();
+    assert!(!overflow_chain_in_buffer_pool(&heap_file, &second_pointer));
+    assert!(heap_file.iter().next().is_none());
+}
+
+/// Overflow pages are unpinned once written, so a heap can hold more large tuples than the buffer
+/// pool has frames.
+#[test]
+fn test_overflow_tuples_exceed_buffer_pool() {
+    let mut heap_file = create_random_heap_file();
+    let capacity = get_bpm_page_capacity(&heap_file);
+
+    let rows: Vec<(RecordId, Tuple)> = (0..capacity)
+        .map(|i| {
+            let tuple = create_large_tuple(2 * OVERFLOW_TEST_CHUNK + i);
+            (heap_file.insert_tuple(tuple.clone()).unwrap(), tuple)
+        })
+        .collect();
+    rows.iter()
+        .for_each(|(rid, tuple)| assert_eq!(*tuple, heap_file.get_tuple(rid).unwrap()));
+}
+
+#[test]
+fn test_update_tuple_grows_within_page() {
+    let mut heap_file = create_random_heap_file();
+
+    let rid = heap_file.insert_tuple(create_large_tuple(20)).unwrap();
+    let neighbor = create_large_tuple(30);
+    let neighbor_rid = heap_file.insert_tuple(neighbor.clone()).unwrap();
+
+    let updated = create_large_tuple(200);
+    heap_file.update_tuple(&rid, updated.clone()).unwrap();
+    assert_eq!(updated, heap_file.get_tuple(&rid).unwrap());
+    assert_eq!(neighbor, heap_file.get_tuple(&neighbor_rid).unwrap());
+
+    // The tuple was rewritten on the same page, and the slot still holds the tuple itself.
+    let page = heap_file.fetch_page_handle(&rid.page_id());
+    assert_eq!(2, page.read().unwrap().tuple_count());
+    assert!(!page
+        .read()
+        .unwrap()
+        .get_tuple_metadata(&rid)
+        .unwrap()
+        .is_forwarded());
+}
+
+#[test]
+fn test_update_tuple_forwards_to_another_page() {
+    let mut heap_file = create_random_heap_file();
+    let mut rows = fill_first_page(&mut heap_file, 200);
+
+    // Grow a tuple on the full first page, so that it has to move to the second page.
+    let (rid, _) = rows[0].clone();
+    let updated = create_large_tuple(600);
+    heap_file.update_tuple(&rid, updated.clone()).unwrap();
+    rows[0].1 = updated.clone();
+    assert_eq!(updated, heap_file.get_tuple(&rid).unwrap());
+
+    let page = heap_file.fetch_page_handle(&rid.page_id());
+    assert!(page
+        .read()
+        .unwrap()
+        .get_tuple_metadata(&rid)
+        .unwrap()
+        .is_forwarded());
+
+    // Scans return the moved tuple once, under its original record id.
+    assert_eq!(rows, heap_file.iter().collect::<Result<Vec<_>>>().unwrap());
+
+    // Updating the tuple again keeps following the same forwarding pointer.
+    let updated = create_large_tuple(900);
+    heap_file.update_tuple(&rid, updated.clone()).unwrap();
+    rows[0].1 = updated.clone();
+    assert_eq!(updated, heap_file.get_tuple(&rid).unwrap());
+    assert_eq!(rows, heap_file.iter().collect::<Result<Vec<_>>>().unwrap());
+
+    // Deleting through the original record id deletes the moved tuple too.
+    heap_file.delete_tuple(&rid).unwrap();
+    rows.remove(0);
+    assert_eq!(rows, heap_file.iter().collect::<Result<Vec<_>>>().unwrap());
+}
+
+#[test]
+fn test_update_deleted_tuple() {
+    let mut heap_file = create_random_heap_file();
+    let rid = heap_file.insert_tuple(create_large_tuple(20)).unwrap();
+    heap_file.delete_tuple(&rid).unwrap();
+    assert!(heap_file
+        .update_tuple(&rid, create_large_tuple(20))
+        .is_err());
+}
+
+#[test]
+fn test_vacuum_moves_tuple_home() {
+    let mut heap_file = create_random_heap_file();
+    let rows: Vec<(RecordId, Tuple)> = (0..11)
+        .map(|_| {
+            let tuple = create_large_tuple(340);
+            (heap_file.insert_tuple(tuple.clone()).unwrap(), tuple)
+        })
+        .collect();
+
+    // Outgrow the free space on the first page, then shrink again.
+    let (rid, _) = &rows[0];
+    heap_file
+        .update_tuple(rid, create_large_tuple(900))
+        .unwrap();
+    let moved_to = get_forwarding_target(&heap_file, rid).unwrap();
+    assert_ne!(rid.page_id(), moved_to.page_id());
+
+    let shrunk = create_large_tuple(40);
+    heap_file.update_tuple(rid, shrunk.clone()).unwrap();
+
+    assert_eq!(1, heap_file.vacuum().unwrap());
+    assert!(get_forwarding_target(&heap_file, rid).is_none());
+    assert_eq!(shrunk, heap_file.get_tuple(rid).unwrap());
+
+    // The relocated copy is gone.
+    let page = heap_file.fetch_page_handle(&moved_to.page_id());
+    assert!(page
+        .read()
+        .unwrap()
+        .get_tuple_metadata(&moved_to)
+        .unwrap()
+        .is_deleted());
+    assert_eq!(11, heap_file.iter().count());
+}
+
+#[test]
+fn test_vacuum_collapses_forwarding_chain() {
+    let mut heap_file = create_random_heap_file();
+    let mut rows = fill_first_page(&mut heap_file, 200);
+
+    // Build a chain home -> hop -> target by hand.
+    let (home, _) = rows[0].clone();
+    let tuple = create_large_tuple(500);
+    let target = heap_file.insert_tuple(tuple.clone()).unwrap();
+    let hop = heap_file.insert_tuple(create_large_tuple(10)).unwrap();
+    set_forwarding_target(&heap_file, &hop, &target, true);
+    set_forwarding_target(&heap_file, &home, &hop, false);
+    set_relocated(&heap_file, &target);
+    rows[0].1 = tuple.clone();
+    assert_eq!(tuple, heap_file.get_tuple(&home).unwrap());
+
+    // The first page is full, so the tuple can't move home, but the chain is shortened.
+    assert_eq!(0, heap_file.vacuum().unwrap());
+    assert_eq!(Some(target), get_forwarding_target(&heap_file, &home));
+    assert_eq!(tuple, heap_file.get_tuple(&home).unwrap());
+    assert_eq!(rows, heap_file.iter().collect::<Result<Vec<_>>>().unwrap());
+}
+
+#[test]
+fn test_insert_reuses_freed_space() {
+    let mut heap_file = create_random_heap_file();
+    let mut rows = fill_first_page(&mut heap_file, 200);
+    let last_page_id = heap_file.last_page_id;
+
+    // Free up two tuples' worth of space on the first page.
+    let (first, _) = rows.remove(0);
+    let (second, _) = rows.remove(0);
+    heap_file.delete_tuple(&first).unwrap();
+    heap_file.delete_tuple(&second).unwrap();
+
+    let tuple = create_large_tuple(350);
+    let rid = heap_file.insert_tuple(tuple.clone()).unwrap();
+    assert_eq!(first.page_id(), rid.page_id());
+    assert_ne!(first, rid);
+    assert_eq!(last_page_id, heap_file.last_page_id);
+    assert_eq!(tuple, heap_file.get_tuple(&rid).unwrap());
+    for (rid, tuple) in rows {
+        assert_eq!(tuple, heap_file.get_tuple(&rid).unwrap());
+    }
+}
+
+#[test]
+fn test_free_space_map_persists() {
+    let mut heap_file = create_random_heap_file();
+    let rows = fill_first_page(&mut heap_file, 200);
+    heap_file.delete_tuple(&rows[3].0).unwrap();
+
+    let fsm_page_id = heap_file.fsm_page_id();
+    let reopened = FreeSpaceMap::open(fsm_page_id, &heap_file.buffer_pool_manager).unwrap();
+    for page_id in heap_file.page_ids() {
+        let free_space = reopened.free_space(&page_id).unwrap();
+        assert_eq!(
+            heap_file.free_space_map.free_space(&page_id),
+            Some(free_space)
+        );
+    }
+    assert_eq!(
+        heap_file.free_space_map.find_page(200),
+        reopened.find_page(200)
+    );
+    assert_eq!(Some(heap_file.first_page_id), reopened.find_page(200));
+}
+
+#[test]
+fn test_open_heap_after_restart() {
+    let db_file = NamedTempFile::new_in(RUST_DB_DATA_DIR).unwrap();
+    let schema = utility::create_table_definition(5, "test");
+    let (first_page_id, fsm_page_id, last_page_id, num_pages, rows) = {
+        let bpm = open_buffer_pool(&db_file);
+        let mut heap_file = TableHeap::new(schema.clone(), &bpm);
+        for _ in 0..40 {
+            heap_file.insert_tuple(create_large_tuple(300)).unwrap();
+        }
+        heap_file
+            .insert_tuple(create_large_tuple(2 * OVERFLOW_TEST_CHUNK))
+            .unwrap();
+        assert!(heap_file.num_pages() > 1);
+
+        let rows: Vec<_> = heap_file.iter().collect::<Result<_>>().unwrap();
+        bpm.write().unwrap().flush_all_pages();
+        (
+            heap_file.first_page_id,
+            heap_file.fsm_page_id(),
+            heap_file.last_page_id,
+            heap_file.num_pages(),
+            rows,
+        )
+    };
+
+    let bpm = open_buffer_pool(&db_file);
+    let heap_file = TableHeap::open(schema, first_page_id, fsm_page_id, &bpm).unwrap();
+    assert_eq!(num_pages, heap_file.num_pages());
+    assert_eq!(last_page_id, heap_file.last_page_id);
+    assert_eq!(rows, heap_file.iter().collect::<Result<Vec<_>>>().unwrap());
+    for (rid, tuple) in rows {
+        assert_eq!(tuple, heap_file.get_tuple(&rid).unwrap());
+    }
+}
+
+#[test]
+fn test_insert_after_reopening_heap() {
+    let db_file = NamedTempFile::new_in(RUST_DB_DATA_DIR).unwrap();
+    let schema = utility::create_table_definition(5, "test");
+    let (first_page_id, fsm_page_id, freed, mut rows) = {
+        let bpm = open_buffer_pool(&db_file);
+        let mut heap_file = TableHeap::new(schema.clone(), &bpm);
+        let mut rows = fill_first_page(&mut heap_file, 200);
+        let (freed, _) = rows.remove(5);
+        heap_file.delete_tuple(&freed).unwrap();
+        bpm.write().unwrap().flush_all_pages();
+        (
+            heap_file.first_page_id,
+            heap_file.fsm_page_id(),
+            freed,
+            rows,
+        )
+    };
+
+    let bpm = open_buffer_pool(&db_file);
+    let mut heap_file = TableHeap::open(schema, first_page_id, fsm_page_id, &bpm).unwrap();
+
+    // The reloaded free space map still knows about the freed space.
+    let tuple = create_large_tuple(200);
+    let rid = heap_file.insert_tuple(tuple.clone()).unwrap();
+    assert_eq!(freed.page_id(), rid.page_id());
+    assert_ne!(freed, rid);
+    rows.push((rid, tuple));
+
+    // New pages don't overwrite any of the pages written before the restart.
+    for _ in 0..20 {
+        let tuple = create_large_tuple(300);
+        rows.push((heap_file.insert_tuple(tuple.clone()).unwrap(), tuple));
+    }
+    assert_eq!(rows, heap_file.iter().collect::<Result<Vec<_>>>().unwrap());
+}
+
+#[test]
+fn test_delete_heap_frees_pages() {
+    let mut heap_file = create_random_heap_file();
+    for _ in 0..40 {
+        heap_file.insert_tuple(create_large_tuple(300)).unwrap();
+    }
+    let rid = heap_file
+        .insert_tuple(create_large_tuple(2 * OVERFLOW_TEST_CHUNK))
+        .unwrap();
+    let pointer = get_overflow_pointer(&heap_file, &rid);
+    let mut page_ids = heap_file.page_ids();
+    page_ids.extend_from_slice(heap_file.free_space_map.page_ids());
+    assert!(page_ids.len() > 3);
+
+    let bpm = Arc::clone(&heap_file.buffer_pool_manager);
+    heap_file.delete().unwrap();
+    assert!(page_ids
+        .iter()
+        .chain([&pointer.first_page_id])
+        .all(|page_id| !bpm.read().unwrap().page_table.contains_key(page_id)));
+
+    // A new heap is built from the freed pages, instead of growing the file.
+    let size = bpm.read().unwrap().disk_manager.read().unwrap().size();
+    let mut heap_file = TableHeap::new(utility::create_table_definition(5, "test"), &bpm);
+    while heap_file.num_pages() < 3 {
+        heap_file.insert_tuple(create_large_tuple(300)).unwrap();
+    }
+    let size_after = bpm.read().unwrap().disk_manager.read().unwrap().size();
+    assert_eq!(size, size_after);
+}
+
+#[test]
+fn test_delete_heap_pinned_elsewhere() {
+    let mut heap_file = create_random_heap_file();
+    for _ in 0..40 {
+        heap_file.insert_tuple(create_large_tuple(300)).unwrap();
+    }
+    // The heap holds a single pin on each of its pages, however often they're fetched.
+    let page_ids = heap_file.page_ids();
+    heap_file.iter().for_each(drop);
+    let bpm = Arc::clone(&heap_file.buffer_pool_manager);
+    assert!(page_ids
+        .iter()
+        .all(|page_id| bpm.read().unwrap().get_pin_count(page_id) == Some(1)));
+
+    // A page pinned by someone else isn't deleted from under them.
+    bpm.write().unwrap().fetch_page(&page_ids[1]).unwrap();
+    assert!(matches!(heap_file.delete(), Err(Error::InvalidData(_))));
+    assert_eq!(Some(1), bpm.read().unwrap().get_pin_count(&page_ids[1]));
+}
+
+#[test]
+fn test_truncate_heap() {
+    let mut heap_file = create_random_heap_file();
+    for _ in 0..40 {
+        heap_file.insert_tuple(create_large_tuple(300)).unwrap();
+    }
+    let rid = heap_file
+        .insert_tuple(create_large_tuple(2 * OVERFLOW_TEST_CHUNK))
+        .unwrap();
+    let pointer = get_overflow_pointer(&heap_file, &rid);
+    let first_page_id = heap_file.first_page_id;
+    let fsm_page_id = heap_file.fsm_page_id();
+    assert!(heap_file.num_pages() > 1);
+
+    heap_file.truncate().unwrap();
+    assert!(!overflow_chain_in_buffer_pool(&heap_file, &pointer));
+    assert_eq!(first_page_id, heap_file.first_page_id);
+    assert_eq!(first_page_id, heap_file.last_page_id);
+    assert_eq!(1, heap_file.num_pages());
+    assert!(heap_file.iter().next().is_none());
+
+    let tuple = create_large_tuple(300);
+    let rid = heap_file.insert_tuple(tuple.clone()).unwrap();
+    assert_eq!(first_page_id, rid.page_id());
+
+    // The first page of the free space map is kept too, so the heap can be reopened from the
+    // page ids it was created with.
+    assert_eq!(fsm_page_id, heap_file.fsm_page_id());
+    let reopened = TableHeap::open(
+        heap_file.schema(),
+        first_page_id,
+        fsm_page_id,
+        &heap_file.buffer_pool_manager,
+    )
+    .unwrap();
+    assert_eq!(
+        vec![(rid, tuple)],
+        reopened.iter().collect::<Result<Vec<_>>>().unwrap()
+    );
+    assert_eq!(
+        heap_file.free_space_map.find_page(300),
+        reopened.free_space_map.find_page(300)
+    );
+}
+
+#[test]
+fn test_open_heap_at_unwritten_page() {
+    let db_file = NamedTempFile::new_in(RUST_DB_DATA_DIR).unwrap();
+    let bpm = open_buffer_pool(&db_file);
+    let schema = utility::create_table_definition(5, "test");
+    let heap_file = TableHeap::new(schema.clone(), &bpm);
+
+    let result = TableHeap::open(
+        schema,
+        heap_file.last_page_id + 10,
+        heap_file.fsm_page_id(),
+        &bpm,
+    );
+    assert!(matches!(result, Err(Error::InvalidData(_))));
+}
+
 pub fn create_random_heap_file() -> TableHeap {
     let disk_manager = new_disk_manager();
     let bpm = Arc::new(RwLock::new(BufferPoolManager::new(50, 5, disk_manager)));
@@ -178,6 +649,81 @@ fn new_disk_manager() -> Arc<RwLock<DiskManager>> {
     DiskManager::new_with_handle_for_test()
 }
 
+/// Opens a buffer pool over `db_file`, which must live in the data directory.
+fn open_buffer_pool(db_file: &NamedTempFile) -> Arc<RwLock<BufferPoolManager>> {
+    let file_name = db_file.path().file_name().unwrap().to_str().unwrap();
+    let disk_manager = DiskManager::new_with_handle(file_name);
+    Arc::new(RwLock::new(BufferPoolManager::new(50, 5, disk_manager)))
+}
+
+/// Roughly the size of a single overflow page.
+const OVERFLOW_TEST_CHUNK: usize = 4_000;
+
+fn create_large_tuple(len: usize) -> Tuple {
+    let mut rng = rand::thread_rng();
+    Tuple::from((0..len).map(|_| rng.gen::<u8>()).collect::<Vec<u8>>())
+}
+
+/// Inserts tuples of `len` bytes until the first page of the heap is full, and returns those on the
+/// first page.
+fn fill_first_page(heap_file: &mut TableHeap, len: usize) -> Vec<(RecordId, Tuple)> {
+    let mut rows = Vec::new();
+    loop {
+        let tuple = create_large_tuple(len);
+        let rid = heap_file.insert_tuple(tuple.clone()).unwrap();
+        if rid.page_id() != heap_file.first_page_id {
+            heap_file.delete_tuple(&rid).unwrap();
+            return rows;
+        }
+        rows.push((rid, tuple));
+    }
+}
+
+fn get_forwarding_target(heap_file: &TableHeap, rid: &RecordId) -> Option<RecordId> {
+    let page = heap_file.fetch_page_handle(&rid.page_id());
+    let page_guard = page.read().unwrap();
+    match page_guard.get_tuple_metadata(rid).unwrap().is_forwarded() {
+        true => Some(RecordId::from_bytes(&page_guard.get_tuple(rid).unwrap().data).unwrap()),
+        false => None,
+    }
+}
+
+fn set_forwarding_target(
+    heap_file: &TableHeap,
+    rid: &RecordId,
+    target: &RecordId,
+    relocated: bool,
+) {
+    let mut metadata = TupleMetadata::forwarding_payload_metadata();
+    metadata.set_relocated(relocated);
+    let page = heap_file.fetch_page_handle(&rid.page_id());
+    let pointer = Tuple::from(target.to_bytes().unwrap());
+    assert!(page
+        .write()
+        .unwrap()
+        .update_tuple(metadata, pointer, rid)
+        .unwrap());
+}
+
+fn set_relocated(heap_file: &TableHeap, rid: &RecordId) {
+    let page = heap_file.fetch_page_handle(&rid.page_id());
+    let mut page_guard = page.write().unwrap();
+    let mut metadata = page_guard.get_tuple_metadata(rid).unwrap();
+    metadata.set_relocated(true);
+    page_guard.update_tuple_metadata(&metadata, rid).unwrap();
+}
+
+fn get_overflow_pointer(heap_file: &TableHeap, rid: &RecordId) -> OverflowPointer {
+    let page = heap_file.fetch_page_handle(&rid.page_id());
+    let stored = page.read().unwrap().get_tuple(rid).unwrap();
+    OverflowPointer::from_bytes(&stored.data).unwrap()
+}
+
+fn overflow_chain_in_buffer_pool(heap_file: &TableHeap, pointer: &OverflowPointer) -> bool {
+    let bpm = heap_file.buffer_pool_manager.read().unwrap();
+    bpm.page_table.contains_key(&pointer.first_page_id)
+}
+
 pub fn create_row(table_schema: &Arc<Table>) -> Row {
     utility::create_random_row(table_schema, None)
 }
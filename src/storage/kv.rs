@@ -0,0 +1,537 @@
+use crate::common::{Error, Result};
+use crate::storage::engine::{
+    encode_key_range, is_empty_range, ScanBounds, ScanRange, Status, TableStatus,
+};
+use crate::storage::memory::{to_id, to_record_id};
+use crate::storage::page::RecordId;
+use crate::storage::tables::{check_unique, primary_key};
+use crate::storage::tuple::Tuple;
+use crate::storage::{engine, Engine, Key};
+use crate::types::field::Field;
+use crate::types::{SchemaChange, Table};
+use crate::{errdata, errinput};
+use std::collections::{btree_map, BTreeMap, HashMap};
+use std::ops::{Bound, RangeBounds};
+
+/// Prefixes the key of a table's definition, which is followed by the table's name.
+const TABLE_PREFIX: u8 = 0x00;
+/// Prefixes the key of a tuple, which is followed by the table's id and the tuple's record id.
+const TUPLE_PREFIX: u8 = 0x01;
+
+/// An ordered key/value store of byte strings.
+///
+/// Unlike [`Engine`], which addresses tuples by table and record id, a store has no notion of
+/// tables, and can be used as a general key/value store. [`KvEngine`] layers the table API on
+/// top of any store.
+pub trait Store: Send {
+    /// The iterator returned by scan_range() and scan_prefix().
+    type ScanIterator<'a>: Iterator<Item = Result<(Vec<u8>, Vec<u8>)>> + 'a
+    where
+        Self: 'a;
+
+    /// The name of the engine, as reported in its status.
+    const NAME: &'static str;
+
+    /// Gets the value of a key, if it exists.
+    fn get(&mut self, key: &[u8]) -> Result<Option<Vec<u8>>>;
+
+    /// Sets the value of a key, replacing any existing value.
+    fn set(&mut self, key: &[u8], value: &[u8]) -> Result<()>;
+
+    /// Deletes a key, if it exists.
+    fn delete(&mut self, key: &[u8]) -> Result<()>;
+
+    /// Returns the keys and values in the given range of keys, in key order.
+    fn scan_range(&mut self, range: impl RangeBounds<Vec<u8>>) -> Self::ScanIterator<'_>;
+
+    /// Returns the keys and values whose keys start with the given prefix, in key order.
+    fn scan_prefix(&mut self, prefix: &[u8]) -> Self::ScanIterator<'_> {
+        self.scan_range(prefix_range(prefix))
+    }
+
+    /// Returns the size in bytes of the store's files.
+    fn disk_size(&mut self) -> Result<u64>;
+}
+
+/// Returns the range of keys that start with the given prefix.
+pub fn prefix_range(prefix: &[u8]) -> (Bound<Vec<u8>>, Bound<Vec<u8>>) {
+    // The first key past the range increments the last byte that isn't 0xff.
+    let end = match prefix.iter().rposition(|&b| b != 0xff) {
+        Some(i) => Bound::Excluded([&prefix[..i], &[prefix[i] + 1]].concat()),
+        None => Bound::Unbounded,
+    };
+    (Bound::Included(prefix.to_vec()), end)
+}
+
+/// Returns the bounds of a range. A range that can't contain any keys, e.g. one whose start is
+/// after its end, is replaced by an empty range that [`BTreeMap::range`] doesn't panic on.
+pub(crate) fn range_bounds(range: impl RangeBounds<Vec<u8>>) -> (Bound<Vec<u8>>, Bound<Vec<u8>>) {
+    match is_empty_range(&range) {
+        true => (Bound::Unbounded, Bound::Excluded(Vec::new())),
+        false => (range.start_bound().cloned(), range.end_bound().cloned()),
+    }
+}
+
+/// An in-memory [`Store`], which keeps the keys and values in a BTreeMap. Nothing is persisted.
+#[derive(Debug, Default)]
+pub struct MemoryStore {
+    data: BTreeMap<Vec<u8>, Vec<u8>>,
+}
+
+impl MemoryStore {
+    /// Creates a new, empty in-memory store.
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl Store for MemoryStore {
+    type ScanIterator<'a> = MemoryScanIterator<'a>;
+
+    const NAME: &'static str = "memory-kv";
+
+    fn get(&mut self, key: &[u8]) -> Result<Option<Vec<u8>>> {
+        Ok(self.data.get(key).cloned())
+    }
+
+    fn set(&mut self, key: &[u8], value: &[u8]) -> Result<()> {
+        self.data.insert(key.to_vec(), value.to_vec());
+        Ok(())
+    }
+
+    fn delete(&mut self, key: &[u8]) -> Result<()> {
+        self.data.remove(key);
+        Ok(())
+    }
+
+    fn scan_range(&mut self, range: impl RangeBounds<Vec<u8>>) -> Self::ScanIterator<'_> {
+        MemoryScanIterator {
+            inner: self.data.range(range_bounds(range)),
+        }
+    }
+
+    fn disk_size(&mut self) -> Result<u64> {
+        Ok(0)
+    }
+}
+
+pub struct MemoryScanIterator<'a> {
+    inner: btree_map::Range<'a, Vec<u8>, Vec<u8>>,
+}
+
+impl Iterator for MemoryScanIterator<'_> {
+    type Item = Result<(Vec<u8>, Vec<u8>)>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.inner
+            .next()
+            .map(|(key, value)| Ok((key.clone(), value.clone())))
+    }
+}
+
+/// A storage engine that stores tables in a key/value [`Store`].
+///
+/// Each table definition is stored under its name, along with an id that the keys of its tuples
+/// start with, so that renaming a table doesn't rewrite its tuples. Record ids are synthetic, like
+/// [`crate::storage::Memory`]'s, and tuple keys sort in insertion order. The primary key
+/// directory of each table is kept in memory, and rebuilt when the engine is opened.
+pub struct KvEngine<S: Store> {
+    store: S,
+    tables: HashMap<String, KvTable>,
+    next_table_id: u32,
+    /// Removes the store's directory when the engine is dropped, e.g. see
+    /// [`crate::storage::Bitcask::new_for_test`].
+    #[cfg(test)]
+    pub(crate) temp_dir: Option<tempfile::TempDir>,
+}
+
+struct KvTable {
+    id: u32,
+    schema: Table,
+    next_id: u64,
+    /// Maps encoded primary keys to record ids, see [`crate::storage::KeyDirectory`].
+    keys: BTreeMap<Vec<u8>, RecordId>,
+}
+
+impl<S: Store> KvEngine<S> {
+    /// Creates an engine over the given store, loading the tables stored in it.
+    pub fn new(mut store: S) -> Result<Self> {
+        let definitions = store
+            .scan_prefix(&[TABLE_PREFIX])
+            .collect::<Result<Vec<_>>>()?;
+
+        let mut tables = HashMap::new();
+        let mut next_table_id = 0;
+        for (_, definition) in definitions {
+            let (id, schema): (u32, Table) = bincode::deserialize(&definition)?;
+            let mut table = KvTable {
+                id,
+                schema,
+                next_id: 0,
+                keys: BTreeMap::new(),
+            };
+            for item in store.scan_prefix(&tuple_prefix(id)) {
+                let (key, value) = item?;
+                let record_id = decode_tuple_key(&key)?;
+                table.next_id = table.next_id.max(to_id(&record_id) + 1);
+                if let Some(primary_key) = primary_key(&table.schema, &Tuple::from(value))? {
+                    table.keys.insert(primary_key, record_id);
+                }
+            }
+            next_table_id = next_table_id.max(id + 1);
+            tables.insert(table.schema.name().to_string(), table);
+        }
+        Ok(Self {
+            store,
+            tables,
+            next_table_id,
+            #[cfg(test)]
+            temp_dir: None,
+        })
+    }
+
+    /// Returns the underlying store.
+    pub fn store(&mut self) -> &mut S {
+        &mut self.store
+    }
+
+    fn must_get(&mut self, table_name: &str) -> Result<&mut KvTable> {
+        self.tables
+            .get_mut(table_name)
+            .ok_or_else(|| Error::InvalidInput(format!("table {table_name} does not exist")))
+    }
+
+    /// Writes a table's definition under its name.
+    fn write_table(&mut self, id: u32, schema: &Table) -> Result<()> {
+        let definition = bincode::serialize(&(id, schema))?;
+        self.store.set(&table_key(schema.name()), &definition)
+    }
+}
+
+impl<S: Store> Engine for KvEngine<S> {
+    type ScanIterator<'a>
+        = ScanIterator<'a, S>
+    where
+        S: 'a;
+
+    fn create_table(&mut self, table: Table) -> Result<()> {
+        if self.tables.contains_key(table.name()) {
+            return errinput!("table {} already exists", table.name());
+        }
+        self.write_table(self.next_table_id, &table)?;
+        let name = table.name().to_string();
+        let table = KvTable {
+            id: self.next_table_id,
+            schema: table,
+            next_id: 0,
+            keys: BTreeMap::new(),
+        };
+        self.next_table_id += 1;
+        self.tables.insert(name, table);
+        Ok(())
+    }
+
+    fn delete_table(&mut self, table_name: &str) -> Result<bool> {
+        let Some(table_id) = self.tables.get(table_name).map(|table| table.id) else {
+            return Ok(false);
+        };
+        // The rows go first: on reopen, table ids are only reserved by surviving definitions, so a
+        // definition deleted before its rows could let a new table inherit them.
+        let keys = self
+            .store
+            .scan_prefix(&tuple_prefix(table_id))
+            .map(|item| item.map(|(key, _)| key))
+            .collect::<Result<Vec<_>>>()?;
+        for key in keys {
+            self.store.delete(&key)?;
+        }
+        self.store.delete(&table_key(table_name))?;
+        self.tables.remove(table_name);
+        Ok(true)
+    }
+
+    fn get_table(&mut self, table_name: &str) -> Result<Option<Table>> {
+        Ok(self
+            .tables
+            .get(table_name)
+            .map(|table| table.schema.clone()))
+    }
+
+    fn alter_table(&mut self, table_name: &str, change: SchemaChange) -> Result<Table> {
+        if let SchemaChange::RenameTable(new_name) = &change {
+            if self.tables.contains_key(new_name) {
+                return errinput!("table {new_name} already exists");
+            }
+        }
+        // The table is altered in memory only once its new definition has been written.
+        let table = self.must_get(table_name)?;
+        let (id, mut schema) = (table.id, table.schema.clone());
+        schema.alter(change)?;
+        self.write_table(id, &schema)?;
+        let mut table = self.tables.remove(table_name).expect("the table exists");
+        table.schema = schema.clone();
+        self.tables.insert(schema.name().to_string(), table);
+        if schema.name() != table_name {
+            self.store.delete(&table_key(table_name))?;
+        }
+        Ok(schema)
+    }
+
+    fn delete(&mut self, key: Key) -> Result<()> {
+        let table = self.must_get(key.table_name)?;
+        let tuple_key = tuple_key(table.id, key.record_id);
+        let Some(value) = self.store.get(&tuple_key)? else {
+            return Ok(());
+        };
+        let table = self.must_get(key.table_name)?;
+        if let Some(primary_key) = primary_key(&table.schema, &Tuple::from(value))? {
+            table.keys.remove(&primary_key);
+        }
+        self.store.delete(&tuple_key)
+    }
+
+    fn get(&mut self, key: Key) -> Result<Tuple> {
+        let table_id = self.must_get(key.table_name)?.id;
+        match self.store.get(&tuple_key(table_id, key.record_id))? {
+            Some(value) => Ok(Tuple::from(value)),
+            None => errinput!(
+                "no tuple with record id {} in table {}",
+                key.record_id.to_string(),
+                key.table_name
+            ),
+        }
+    }
+
+    fn insert(&mut self, table_name: &str, value: Tuple) -> Result<RecordId> {
+        let table = self.must_get(table_name)?;
+        let primary_key = primary_key(&table.schema, &value)?;
+        if let Some(primary_key) = &primary_key {
+            check_unique(&table.keys, &table.schema, primary_key)?;
+        }
+        let record_id = to_record_id(table.next_id);
+        let tuple_key = tuple_key(table.id, &record_id);
+        self.store.set(&tuple_key, &value.data)?;
+
+        let table = self.must_get(table_name)?;
+        table.next_id += 1;
+        if let Some(primary_key) = primary_key {
+            table.keys.insert(primary_key, record_id.clone());
+        }
+        Ok(record_id)
+    }
+
+    fn scan(&mut self, table_name: &str) -> Self::ScanIterator<'_> {
+        let table = self
+            .tables
+            .get(table_name)
+            .unwrap_or_else(|| panic!("Could not access table {table_name}"));
+        ScanIterator {
+            inner: self.store.scan_prefix(&tuple_prefix(table.id)),
+        }
+    }
+
+    fn scan_dyn(&mut self, table_name: &str) -> Result<Box<dyn engine::ScanIterator + '_>> {
+        self.must_get(table_name)?;
+        Ok(Box::new(self.scan(table_name)))
+    }
+
+    fn scan_range(
+        &mut self,
+        table_name: &str,
+        range: ScanRange,
+    ) -> Result<Box<dyn engine::ScanIterator + '_>> {
+        let table = self
+            .tables
+            .get(table_name)
+            .ok_or_else(|| Error::InvalidInput(format!("table {table_name} does not exist")))?;
+        let table_id = table.id;
+        match range.bounds {
+            ScanBounds::RecordIds(start, end) => {
+                let prefix = tuple_prefix(table_id);
+                let start = match start {
+                    Bound::Included(record_id) => Bound::Included(tuple_key(table_id, &record_id)),
+                    Bound::Excluded(record_id) => Bound::Excluded(tuple_key(table_id, &record_id)),
+                    Bound::Unbounded => Bound::Included(prefix.clone()),
+                };
+                let end = match end {
+                    Bound::Included(record_id) => Bound::Included(tuple_key(table_id, &record_id)),
+                    Bound::Excluded(record_id) => Bound::Excluded(tuple_key(table_id, &record_id)),
+                    Bound::Unbounded => prefix_range(&prefix).1,
+                };
+                let rows = ScanIterator::<S> {
+                    inner: self.store.scan_range((start, end)),
+                };
+                // Stores only scan forward, so reverse scans are buffered.
+                Ok(match range.reverse {
+                    true => Box::new(rows.collect::<Vec<_>>().into_iter().rev()),
+                    false => Box::new(rows),
+                })
+            }
+            ScanBounds::PrimaryKeys(start, end) => {
+                let keys = encode_key_range(&table.schema, &start, &end)?;
+                if is_empty_range(&keys) {
+                    return Ok(Box::new(std::iter::empty()));
+                }
+                let mut record_ids: Vec<RecordId> = table
+                    .keys
+                    .range(keys)
+                    .map(|(_, record_id)| record_id.clone())
+                    .collect();
+                if range.reverse {
+                    record_ids.reverse();
+                }
+                let store = &mut self.store;
+                Ok(Box::new(record_ids.into_iter().map(move |record_id| {
+                    match store.get(&tuple_key(table_id, &record_id))? {
+                        Some(value) => Ok((record_id, Tuple::from(value))),
+                        None => errdata!("no tuple with record id {}", record_id.to_string()),
+                    }
+                })))
+            }
+        }
+    }
+
+    fn update(&mut self, key: Key, value: Tuple) -> Result<()> {
+        let old = self.get(Key::new(key.table_name, key.record_id))?;
+        let table = self.must_get(key.table_name)?;
+        let old_key = primary_key(&table.schema, &old)?;
+        let new_key = primary_key(&table.schema, &value)?;
+        if let Some(new_key) = &new_key {
+            if old_key.as_ref() != Some(new_key) {
+                check_unique(&table.keys, &table.schema, new_key)?;
+            }
+        }
+        let tuple_key = tuple_key(table.id, key.record_id);
+        self.store.set(&tuple_key, &value.data)?;
+
+        let table = self.must_get(key.table_name)?;
+        if let Some(old_key) = old_key {
+            table.keys.remove(&old_key);
+        }
+        if let Some(new_key) = new_key {
+            table.keys.insert(new_key, key.record_id.clone());
+        }
+        Ok(())
+    }
+
+    fn lookup(&mut self, table_name: &str, key: &[Field]) -> Result<Option<RecordId>> {
+        let table = self.must_get(table_name)?;
+        let key = table.schema.encode_primary_key(key)?;
+        Ok(table.keys.get(&key).cloned())
+    }
+
+    fn status(&mut self) -> Result<Status> {
+        let mut tables = BTreeMap::new();
+        for (name, table) in &self.tables {
+            let mut status = TableStatus::default();
+            for item in self.store.scan_prefix(&tuple_prefix(table.id)) {
+                let (_, value) = item?;
+                status.keys += 1;
+                status.size += value.len() as u64;
+            }
+            tables.insert(name.clone(), status);
+        }
+        Ok(Status {
+            name: S::NAME.to_string(),
+            keys: tables.values().map(|table: &TableStatus| table.keys).sum(),
+            size: tables.values().map(|table: &TableStatus| table.size).sum(),
+            tables,
+            pages: 0,
+            disk_size: self.store.disk_size()?,
+            buffer_pool: None,
+        })
+    }
+}
+
+pub struct ScanIterator<'a, S: Store + 'a> {
+    inner: S::ScanIterator<'a>,
+}
+
+impl<'a, S: Store + 'a> Iterator for ScanIterator<'a, S> {
+    type Item = Result<(RecordId, Tuple)>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let item = self.inner.next()?;
+        Some(item.and_then(|(key, value)| Ok((decode_tuple_key(&key)?, Tuple::from(value)))))
+    }
+}
+
+fn table_key(table_name: &str) -> Vec<u8> {
+    [&[TABLE_PREFIX], table_name.as_bytes()].concat()
+}
+
+fn tuple_prefix(table_id: u32) -> Vec<u8> {
+    [&[TUPLE_PREFIX][..], &table_id.to_be_bytes()].concat()
+}
+
+/// Encodes a tuple's key. Record ids are stored as their synthetic ids, so keys sort in
+/// insertion order.
+fn tuple_key(table_id: u32, record_id: &RecordId) -> Vec<u8> {
+    [
+        tuple_prefix(table_id),
+        to_id(record_id).to_be_bytes().to_vec(),
+    ]
+    .concat()
+}
+
+fn decode_tuple_key(key: &[u8]) -> Result<RecordId> {
+    match key {
+        [TUPLE_PREFIX, _, _, _, _, id @ ..] if id.len() == 8 => {
+            Ok(to_record_id(u64::from_be_bytes(id.try_into()?)))
+        }
+        _ => errdata!("invalid tuple key {key:?}"),
+    }
+}
+
+/// A conformance test for [`Store`] implementations, like [`crate::storage::engine::tests`].
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+
+    crate::storage::engine::tests::test_engine!(KvEngine::new(MemoryStore::new()).unwrap());
+
+    #[test]
+    fn store_memory() {
+        store(MemoryStore::new())
+    }
+
+    fn scan(iter: impl Iterator<Item = Result<(Vec<u8>, Vec<u8>)>>) -> Vec<Vec<u8>> {
+        iter.map(|item| item.map(|(key, _)| key))
+            .collect::<Result<_>>()
+            .unwrap()
+    }
+
+    /// Checks point operations and scans of a store, which must be empty.
+    pub(crate) fn store(mut store: impl Store) {
+        let keys: [&[u8]; 6] = [b"a", b"b", b"ba", b"bb\xff", b"b\xff", b"c"];
+        for key in keys {
+            store.set(key, key).unwrap();
+        }
+        store.set(b"a", b"new").unwrap();
+        store.delete(b"c").unwrap();
+        store.delete(b"missing").unwrap();
+        assert_eq!(store.get(b"a").unwrap(), Some(b"new".to_vec()));
+        assert_eq!(store.get(b"c").unwrap(), None);
+
+        let all = keys[..5].iter().map(|key| key.to_vec()).collect::<Vec<_>>();
+        assert_eq!(scan(store.scan_range(..)), all);
+        assert_eq!(scan(store.scan_prefix(b"")), all);
+        assert_eq!(scan(store.scan_prefix(b"b")), all[1..5]);
+        assert_eq!(scan(store.scan_prefix(b"b\xff")), all[4..5]);
+        assert_eq!(
+            scan(store.scan_range(b"b".to_vec()..b"bb\xff".to_vec())),
+            all[1..3]
+        );
+        assert_eq!(
+            scan(store.scan_range((
+                Bound::Excluded(b"b".to_vec()),
+                Bound::Included(b"bb\xff".to_vec())
+            ))),
+            all[2..4]
+        );
+        assert_eq!(
+            scan(store.scan_range(b"z".to_vec()..)),
+            Vec::<Vec<u8>>::new()
+        );
+    }
+}
@@ -0,0 +1,72 @@
+use crate::common::Result;
+use crate::errdata;
+
+/// A bloom filter over the keys of an SSTable, which tells whether a key may be in the table, so
+/// that lookups of missing keys rarely have to read a block.
+///
+/// Keys are hashed with [`hash`], and the two halves of the hash are combined into `k` bit
+/// positions (double hashing). The hash is stable, since the filters are stored on disk.
+#[derive(Clone, Debug, PartialEq)]
+pub(super) struct BloomFilter {
+    bits: Vec<u8>,
+    k: u8,
+}
+
+impl BloomFilter {
+    /// Builds a filter over the given key hashes, with about `bits_per_key` bits per key.
+    pub(super) fn new(hashes: &[u64], bits_per_key: usize) -> Self {
+        let num_bits = (hashes.len() * bits_per_key).max(64);
+        // ln(2) * bits per key minimizes the false positive rate.
+        let k = ((bits_per_key as f64 * 0.69) as u8).clamp(1, 30);
+        let mut filter = Self {
+            bits: vec![0; num_bits.div_ceil(8)],
+            k,
+        };
+        for &hash in hashes {
+            for bit in filter.positions(hash) {
+                filter.bits[bit / 8] |= 1 << (bit % 8);
+            }
+        }
+        filter
+    }
+
+    /// Returns false if the key with the given hash is definitely not in the filter.
+    pub(super) fn may_contain(&self, hash: u64) -> bool {
+        self.positions(hash)
+            .all(|bit| self.bits[bit / 8] & (1 << (bit % 8)) != 0)
+    }
+
+    fn positions(&self, hash: u64) -> impl Iterator<Item = usize> {
+        let num_bits = self.bits.len() as u64 * 8;
+        let (h1, h2) = (hash & 0xffff_ffff, hash >> 32);
+        (0..self.k as u64).map(move |i| (h1.wrapping_add(i.wrapping_mul(h2)) % num_bits) as usize)
+    }
+
+    /// Encodes the filter as `k` followed by the bits.
+    pub(super) fn encode(&self) -> Vec<u8> {
+        [&[self.k][..], &self.bits].concat()
+    }
+
+    pub(super) fn decode(bytes: &[u8]) -> Result<Self> {
+        match bytes {
+            [k @ 1..=30, bits @ ..] if !bits.is_empty() => Ok(Self {
+                bits: bits.to_vec(),
+                k: *k,
+            }),
+            _ => errdata!("invalid bloom filter"),
+        }
+    }
+}
+
+/// Hashes a key with 64-bit FNV-1a, followed by MurmurHash3's finalizer, since FNV-1a alone mixes
+/// the last bytes of a key poorly into the upper bits.
+pub(super) fn hash(key: &[u8]) -> u64 {
+    let mut hash = key.iter().fold(0xcbf2_9ce4_8422_2325, |hash, &byte| {
+        (hash ^ byte as u64).wrapping_mul(0x0100_0000_01b3)
+    });
+    hash ^= hash >> 33;
+    hash = hash.wrapping_mul(0xff51_afd7_ed55_8ccd);
+    hash ^= hash >> 33;
+    hash = hash.wrapping_mul(0xc4ce_b9fe_1a85_ec53);
+    hash ^ (hash >> 33)
+}
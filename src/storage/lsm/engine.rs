@@ -0,0 +1,65 @@
+use super::tree::{self, LsmOptions, LsmTree};
+use crate::common::Result;
+use crate::storage::kv::{KvEngine, Store};
+use std::ops::RangeBounds;
+use std::path::Path;
+
+/// A storage engine that stores tables in an [`LsmTree`]. Writes are buffered in memory and
+/// written out in bulk, which suits write-heavy ingest.
+pub type Lsm = KvEngine<LsmTree>;
+
+impl Lsm {
+    /// Opens the engine stored in the given directory, creating it if needed.
+    pub fn open(dir: impl AsRef<Path>) -> Result<Self> {
+        Self::open_with(dir, LsmOptions::default())
+    }
+
+    /// Opens the engine stored in the given directory with the given tree options.
+    pub fn open_with(dir: impl AsRef<Path>, options: LsmOptions) -> Result<Self> {
+        KvEngine::new(LsmTree::open_with(dir, options)?)
+    }
+
+    #[cfg(test)]
+    /// Creates an engine in a temporary directory, which is removed when the engine is dropped.
+    /// The memtable is kept small, so that tests go through SSTables and compactions.
+    pub fn new_for_test() -> Self {
+        let temp_dir = tempfile::tempdir().expect("Unable to create temp dir");
+        let options = LsmOptions {
+            memtable_size: 256,
+            block_size: 64,
+            level0_compaction_trigger: 2,
+            level_base_size: 1 << 10,
+            table_size: 512,
+            ..LsmOptions::default()
+        };
+        let mut lsm = Self::open_with(temp_dir.path(), options).expect("Unable to open lsm");
+        lsm.temp_dir = Some(temp_dir);
+        lsm
+    }
+}
+
+impl Store for LsmTree {
+    type ScanIterator<'a> = tree::ScanIterator<'a>;
+
+    const NAME: &'static str = "lsm";
+
+    fn get(&mut self, key: &[u8]) -> Result<Option<Vec<u8>>> {
+        self.get(key)
+    }
+
+    fn set(&mut self, key: &[u8], value: &[u8]) -> Result<()> {
+        self.set(key, value)
+    }
+
+    fn delete(&mut self, key: &[u8]) -> Result<()> {
+        self.delete(key)
+    }
+
+    fn scan_range(&mut self, range: impl RangeBounds<Vec<u8>>) -> Self::ScanIterator<'_> {
+        self.scan(range)
+    }
+
+    fn disk_size(&mut self) -> Result<u64> {
+        Ok(self.status()?.disk_size)
+    }
+}
@@ -0,0 +1,104 @@
+use super::sstable::{entry_size, read_entry, write_entry};
+use crate::common::Result;
+use std::collections::{btree_map, BTreeMap};
+use std::fs::{File, OpenOptions};
+use std::io::{BufReader, BufWriter, Seek, Write};
+use std::ops::Bound;
+use std::path::Path;
+
+/// The writes that haven't been flushed to an SSTable yet, in key order. A `None` value is a
+/// tombstone, which shadows the key's value in older SSTables.
+///
+/// Every write is first appended to a write-ahead log, which is replayed when the tree is opened
+/// so that writes survive a restart, and emptied once the memtable is flushed.
+pub(super) struct Memtable {
+    entries: BTreeMap<Vec<u8>, Option<Vec<u8>>>,
+    /// The size of the entries as they'd be written to the log.
+    size: u64,
+    wal: BufWriter<File>,
+}
+
+impl Memtable {
+    /// Opens the memtable with the write-ahead log at the given path, replaying its writes. An
+    /// entry that was only partly written is truncated.
+    pub(super) fn open(wal_path: &Path) -> Result<Self> {
+        let file = OpenOptions::new()
+            .create(true)
+            .truncate(false)
+            .read(true)
+            .write(true)
+            .open(wal_path)?;
+        let file_len = file.metadata()?.len();
+        let mut memtable = Self {
+            entries: BTreeMap::new(),
+            size: 0,
+            wal: BufWriter::new(file.try_clone()?),
+        };
+        let mut reader = BufReader::new(&file);
+        let mut pos = 0;
+        while let Some(((key, value), len)) = read_entry(&mut reader, file_len - pos)? {
+            memtable.apply(key, value);
+            pos += len;
+        }
+        if pos < file_len {
+            file.set_len(pos)?;
+        }
+        memtable.wal.seek(std::io::SeekFrom::Start(pos))?;
+        Ok(memtable)
+    }
+
+    /// Gets the value of a key: `None` if the memtable doesn't have the key, and `Some(None)` if
+    /// it was deleted.
+    pub(super) fn get(&self, key: &[u8]) -> Option<Option<&[u8]>> {
+        self.entries.get(key).map(Option::as_deref)
+    }
+
+    /// Writes a value, or a tombstone if `None`, to the log and the memtable.
+    pub(super) fn write(&mut self, key: &[u8], value: Option<&[u8]>) -> Result<()> {
+        write_entry(&mut self.wal, key, value)?;
+        self.wal.flush()?;
+        self.apply(key.to_vec(), value.map(<[u8]>::to_vec));
+        Ok(())
+    }
+
+    fn apply(&mut self, key: Vec<u8>, value: Option<Vec<u8>>) {
+        self.size += entry_size(&key, value.as_deref());
+        self.entries.insert(key, value);
+    }
+
+    /// Returns the entries in the given range of keys, including tombstones.
+    pub(super) fn range(
+        &self,
+        range: (Bound<Vec<u8>>, Bound<Vec<u8>>),
+    ) -> btree_map::Range<'_, Vec<u8>, Option<Vec<u8>>> {
+        self.entries.range(range)
+    }
+
+    pub(super) fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Returns the number of bytes written to the log since it was last emptied.
+    pub(super) fn size(&self) -> u64 {
+        self.size
+    }
+
+    /// Empties the memtable and its log, once its entries are stored in an SSTable.
+    pub(super) fn clear(&mut self) -> Result<()> {
+        self.wal.flush()?;
+        let file = self.wal.get_mut();
+        file.set_len(0)?;
+        file.rewind()?;
+        file.sync_all()?;
+        self.entries.clear();
+        self.size = 0;
+        Ok(())
+    }
+
+    /// Flushes the log to disk.
+    pub(super) fn sync(&mut self) -> Result<()> {
+        self.wal.flush()?;
+        self.wal.get_ref().sync_all()?;
+        Ok(())
+    }
+}
@@ -0,0 +1,15 @@
+//! A log-structured merge tree storage engine.
+//!
+//! [`LsmTree`] buffers writes in a memtable, flushes them to sorted, immutable SSTables, and
+//! compacts the SSTables level by level. [`Lsm`] stores tables on top of it.
+
+mod bloom;
+mod engine;
+mod memtable;
+mod sstable;
+#[cfg(test)]
+mod tests;
+mod tree;
+
+pub use engine::Lsm;
+pub use tree::{LevelStatus, LsmOptions, LsmStatus, LsmTree, ScanIterator};
@@ -0,0 +1,410 @@
+use super::bloom::{self, BloomFilter};
+use crate::common::Result;
+use crate::errdata;
+use std::fs::File;
+use std::io::{BufWriter, Read, Seek, SeekFrom, Write};
+use std::ops::Bound;
+use std::path::{Path, PathBuf};
+
+/// The size of an entry's header: the length of the key as a u32, and the length of the value as
+/// an i32, both big-endian. A value length of [`TOMBSTONE`] marks a deleted key.
+const ENTRY_HEADER_SIZE: u64 = 8;
+
+/// The value length of a tombstone, which has no value.
+const TOMBSTONE: i32 = -1;
+
+/// The size of an SSTable's footer: the offsets of the bloom filter and of the index as u64s,
+/// followed by [`MAGIC`].
+const FOOTER_SIZE: u64 = 20;
+
+/// Ends every SSTable, to catch files that aren't SSTables or were only partly written.
+const MAGIC: &[u8; 4] = b"RSST";
+
+/// An entry: a key and its value, or `None` for a tombstone.
+pub(super) type Entry = (Vec<u8>, Option<Vec<u8>>);
+
+/// The location of a data block, and the last key in it.
+#[derive(Debug)]
+struct BlockHandle {
+    last_key: Vec<u8>,
+    offset: u64,
+    len: u32,
+}
+
+/// An immutable, sorted file of entries. The file is laid out as
+///
+/// | data blocks | bloom filter | index | footer |
+///
+/// Data blocks hold entries in key order, in the same format as the write-ahead log, and are cut
+/// once they reach [`super::LsmOptions::block_size`]. The index holds the table's first key, and
+/// the [`BlockHandle`] of each block. The index and the bloom filter are read when the table is
+/// opened, so that a lookup reads a single block, and none if the bloom filter rules the key out.
+#[derive(Debug)]
+pub(super) struct SsTable {
+    id: u64,
+    file: File,
+    first_key: Vec<u8>,
+    index: Vec<BlockHandle>,
+    bloom: BloomFilter,
+    size: u64,
+}
+
+impl SsTable {
+    /// Opens the SSTable with the given id in the given directory.
+    pub(super) fn open(dir: &Path, id: u64) -> Result<Self> {
+        let mut file = File::open(path(dir, id))?;
+        let size = file.metadata()?.len();
+        if size < FOOTER_SIZE {
+            return errdata!("SSTable {id} is truncated");
+        }
+        let mut footer = [0; FOOTER_SIZE as usize];
+        file.seek(SeekFrom::Start(size - FOOTER_SIZE))?;
+        file.read_exact(&mut footer)?;
+        if &footer[16..20] != MAGIC {
+            return errdata!("SSTable {id} has an invalid footer");
+        }
+        let bloom_offset = u64::from_be_bytes(footer[0..8].try_into()?);
+        let index_offset = u64::from_be_bytes(footer[8..16].try_into()?);
+        if bloom_offset > index_offset || index_offset > size - FOOTER_SIZE {
+            return errdata!("SSTable {id} has an invalid footer");
+        }
+
+        let mut bloom = vec![0; (index_offset - bloom_offset) as usize];
+        file.seek(SeekFrom::Start(bloom_offset))?;
+        file.read_exact(&mut bloom)?;
+        let mut index = vec![0; (size - FOOTER_SIZE - index_offset) as usize];
+        file.read_exact(&mut index)?;
+
+        let mut index = index.as_slice();
+        let first_key = read_bytes(&mut index)?;
+        let mut handles = Vec::new();
+        while !index.is_empty() {
+            let last_key = read_bytes(&mut index)?;
+            let mut location = [0; 12];
+            index.read_exact(&mut location)?;
+            handles.push(BlockHandle {
+                last_key,
+                offset: u64::from_be_bytes(location[0..8].try_into()?),
+                len: u32::from_be_bytes(location[8..12].try_into()?),
+            });
+        }
+        if handles.is_empty() {
+            return errdata!("SSTable {id} has no blocks");
+        }
+        Ok(Self {
+            id,
+            file,
+            first_key,
+            index: handles,
+            bloom: BloomFilter::decode(&bloom)?,
+            size,
+        })
+    }
+
+    pub(super) fn id(&self) -> u64 {
+        self.id
+    }
+
+    /// Returns the size of the table's file in bytes.
+    pub(super) fn size(&self) -> u64 {
+        self.size
+    }
+
+    pub(super) fn first_key(&self) -> &[u8] {
+        &self.first_key
+    }
+
+    pub(super) fn last_key(&self) -> &[u8] {
+        &self.index.last().expect("SSTables have blocks").last_key
+    }
+
+    /// Gets the value of a key: `None` if the table doesn't have the key, and `Some(None)` if it
+    /// has a tombstone for it.
+    pub(super) fn get(&self, key: &[u8]) -> Result<Option<Option<Vec<u8>>>> {
+        if key < self.first_key() || key > self.last_key() {
+            return Ok(None);
+        }
+        if !self.bloom.may_contain(bloom::hash(key)) {
+            return Ok(None);
+        }
+        let block = self
+            .index
+            .partition_point(|handle| handle.last_key.as_slice() < key);
+        let entries = self.read_block(block)?;
+        Ok(entries
+            .into_iter()
+            .find(|(k, _)| k == key)
+            .map(|(_, value)| value))
+    }
+
+    /// Returns whether the table may have keys in the given range.
+    pub(super) fn overlaps(&self, range: &(Bound<Vec<u8>>, Bound<Vec<u8>>)) -> bool {
+        let after_start = match &range.0 {
+            Bound::Included(start) => self.last_key() >= start.as_slice(),
+            Bound::Excluded(start) => self.last_key() > start.as_slice(),
+            Bound::Unbounded => true,
+        };
+        after_start && !past_end(&self.first_key, &range.1)
+    }
+
+    /// Returns the entries in the given range of keys, including tombstones.
+    pub(super) fn scan(&self, range: (Bound<Vec<u8>>, Bound<Vec<u8>>)) -> SsTableIterator<'_> {
+        let next_block = match &range.0 {
+            Bound::Included(start) | Bound::Excluded(start) => self
+                .index
+                .partition_point(|handle| handle.last_key < *start),
+            Bound::Unbounded => 0,
+        };
+        SsTableIterator {
+            table: self,
+            range,
+            next_block,
+            entries: Vec::new().into_iter(),
+            done: false,
+        }
+    }
+
+    /// Reads and decodes a data block.
+    fn read_block(&self, block: usize) -> Result<Vec<Entry>> {
+        let handle = &self.index[block];
+        let mut data = vec![0; handle.len as usize];
+        let mut file = &self.file;
+        file.seek(SeekFrom::Start(handle.offset))?;
+        file.read_exact(&mut data)?;
+
+        let mut entries = Vec::new();
+        let mut data = data.as_slice();
+        while !data.is_empty() {
+            match read_entry(&mut data, u64::MAX)? {
+                Some((entry, _)) => entries.push(entry),
+                None => return errdata!("SSTable {} has a truncated block", self.id),
+            }
+        }
+        Ok(entries)
+    }
+}
+
+/// An iterator over the entries of an SSTable in a range of keys, which reads a block at a time.
+pub(super) struct SsTableIterator<'a> {
+    table: &'a SsTable,
+    range: (Bound<Vec<u8>>, Bound<Vec<u8>>),
+    next_block: usize,
+    entries: std::vec::IntoIter<Entry>,
+    done: bool,
+}
+
+impl Iterator for SsTableIterator<'_> {
+    type Item = Result<Entry>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while !self.done {
+            for (key, value) in self.entries.by_ref() {
+                let before_start = match &self.range.0 {
+                    Bound::Included(start) => key < *start,
+                    Bound::Excluded(start) => key <= *start,
+                    Bound::Unbounded => false,
+                };
+                if before_start {
+                    continue;
+                }
+                if past_end(&key, &self.range.1) {
+                    self.done = true;
+                    return None;
+                }
+                return Some(Ok((key, value)));
+            }
+            if self.next_block >= self.table.index.len() {
+                self.done = true;
+                return None;
+            }
+            match self.table.read_block(self.next_block) {
+                Ok(entries) => self.entries = entries.into_iter(),
+                Err(err) => {
+                    self.done = true;
+                    return Some(Err(err));
+                }
+            }
+            self.next_block += 1;
+        }
+        None
+    }
+}
+
+/// Writes an SSTable from entries given in key order.
+pub(super) struct SsTableWriter {
+    id: u64,
+    dir: PathBuf,
+    file: BufWriter<File>,
+    block_size: u64,
+    bits_per_key: usize,
+    block: Vec<u8>,
+    offset: u64,
+    first_key: Option<Vec<u8>>,
+    last_key: Vec<u8>,
+    index: Vec<BlockHandle>,
+    hashes: Vec<u64>,
+}
+
+impl SsTableWriter {
+    /// Creates the file of the SSTable with the given id in the given directory.
+    pub(super) fn new(dir: &Path, id: u64, block_size: u64, bits_per_key: usize) -> Result<Self> {
+        Ok(Self {
+            id,
+            dir: dir.to_path_buf(),
+            file: BufWriter::new(File::create(path(dir, id))?),
+            block_size,
+            bits_per_key,
+            block: Vec::new(),
+            offset: 0,
+            first_key: None,
+            last_key: Vec::new(),
+            index: Vec::new(),
+            hashes: Vec::new(),
+        })
+    }
+
+    /// Adds an entry, whose key must come after the keys already added.
+    pub(super) fn add(&mut self, key: &[u8], value: Option<&[u8]>) -> Result<()> {
+        debug_assert!(self.first_key.is_none() || key > self.last_key.as_slice());
+        self.first_key.get_or_insert_with(|| key.to_vec());
+        write_entry(&mut self.block, key, value)?;
+        self.hashes.push(bloom::hash(key));
+        key.clone_into(&mut self.last_key);
+        if self.block.len() as u64 >= self.block_size {
+            self.finish_block()?;
+        }
+        Ok(())
+    }
+
+    /// Returns the size of the entries added so far.
+    pub(super) fn size(&self) -> u64 {
+        self.offset + self.block.len() as u64
+    }
+
+    fn finish_block(&mut self) -> Result<()> {
+        if self.block.is_empty() {
+            return Ok(());
+        }
+        self.file.write_all(&self.block)?;
+        self.index.push(BlockHandle {
+            last_key: self.last_key.clone(),
+            offset: self.offset,
+            len: self.block.len() as u32,
+        });
+        self.offset += self.block.len() as u64;
+        self.block.clear();
+        Ok(())
+    }
+
+    /// Writes the bloom filter, the index and the footer, syncs the file to disk, and opens the
+    /// table. At least one entry must have been added.
+    pub(super) fn finish(mut self) -> Result<SsTable> {
+        self.finish_block()?;
+        let Some(first_key) = self.first_key.take() else {
+            return errdata!("SSTable {} has no entries", self.id);
+        };
+        let bloom_offset = self.offset;
+        let bloom = BloomFilter::new(&self.hashes, self.bits_per_key).encode();
+        self.file.write_all(&bloom)?;
+
+        let index_offset = bloom_offset + bloom.len() as u64;
+        write_bytes(&mut self.file, &first_key)?;
+        for handle in &self.index {
+            write_bytes(&mut self.file, &handle.last_key)?;
+            self.file.write_all(&handle.offset.to_be_bytes())?;
+            self.file.write_all(&handle.len.to_be_bytes())?;
+        }
+        self.file.write_all(&bloom_offset.to_be_bytes())?;
+        self.file.write_all(&index_offset.to_be_bytes())?;
+        self.file.write_all(MAGIC)?;
+        self.file
+            .into_inner()
+            .map_err(|err| err.into_error())?
+            .sync_all()?;
+        SsTable::open(&self.dir, self.id)
+    }
+}
+
+/// Returns whether a key comes after the end of a range.
+fn past_end(key: &[u8], end: &Bound<Vec<u8>>) -> bool {
+    match end {
+        Bound::Included(end) => key > end.as_slice(),
+        Bound::Excluded(end) => key >= end.as_slice(),
+        Bound::Unbounded => false,
+    }
+}
+
+/// Returns the path of the SSTable with the given id.
+pub(super) fn path(dir: &Path, id: u64) -> PathBuf {
+    dir.join(format!("{id:08}.sst"))
+}
+
+/// Returns the size of an entry.
+pub(super) fn entry_size(key: &[u8], value: Option<&[u8]>) -> u64 {
+    ENTRY_HEADER_SIZE + key.len() as u64 + value.map_or(0, |value| value.len() as u64)
+}
+
+/// Writes an entry. A `None` value writes a tombstone.
+pub(super) fn write_entry(writer: &mut impl Write, key: &[u8], value: Option<&[u8]>) -> Result<()> {
+    let value_len = match value {
+        Some(value) if value.len() > i32::MAX as usize => {
+            return errdata!("value of {} bytes is too large", value.len())
+        }
+        Some(value) => value.len() as i32,
+        None => TOMBSTONE,
+    };
+    writer.write_all(&(key.len() as u32).to_be_bytes())?;
+    writer.write_all(&value_len.to_be_bytes())?;
+    writer.write_all(key)?;
+    writer.write_all(value.unwrap_or_default())?;
+    Ok(())
+}
+
+/// Reads an entry, along with its size, given the number of bytes left in the reader. Returns
+/// `None` if there isn't a whole entry left, e.g. at the end of a log that was cut short.
+pub(super) fn read_entry(reader: &mut impl Read, remaining: u64) -> Result<Option<(Entry, u64)>> {
+    if remaining < ENTRY_HEADER_SIZE {
+        return Ok(None);
+    }
+    let mut header = [0; ENTRY_HEADER_SIZE as usize];
+    reader.read_exact(&mut header)?;
+    let key_len = u32::from_be_bytes(header[0..4].try_into()?) as u64;
+    let value_len = match i32::from_be_bytes(header[4..8].try_into()?) {
+        TOMBSTONE => None,
+        len if len >= 0 => Some(len as u64),
+        len => return errdata!("invalid value length {len}"),
+    };
+    let size = ENTRY_HEADER_SIZE + key_len + value_len.unwrap_or(0);
+    if size > remaining {
+        return Ok(None);
+    }
+    let mut key = vec![0; key_len as usize];
+    reader.read_exact(&mut key)?;
+    let value = match value_len {
+        Some(len) => {
+            let mut value = vec![0; len as usize];
+            reader.read_exact(&mut value)?;
+            Some(value)
+        }
+        None => None,
+    };
+    Ok(Some(((key, value), size)))
+}
+
+fn write_bytes(writer: &mut impl Write, bytes: &[u8]) -> Result<()> {
+    writer.write_all(&(bytes.len() as u32).to_be_bytes())?;
+    writer.write_all(bytes)?;
+    Ok(())
+}
+
+fn read_bytes(reader: &mut &[u8]) -> Result<Vec<u8>> {
+    let mut len = [0; 4];
+    reader.read_exact(&mut len)?;
+    let len = u32::from_be_bytes(len) as usize;
+    if reader.len() < len {
+        return errdata!("truncated SSTable index");
+    }
+    let (bytes, rest) = reader.split_at(len);
+    *reader = rest;
+    Ok(bytes.to_vec())
+}
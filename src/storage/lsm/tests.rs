@@ -0,0 +1,197 @@
+use super::bloom::{self, BloomFilter};
+use super::*;
+use crate::common::Result;
+use crate::storage::tuple::Row;
+use crate::storage::{Engine, Key};
+use crate::types::field::Field;
+use crate::types::{DataType, Table};
+use std::collections::BTreeMap;
+use std::fs::{self, OpenOptions};
+use std::ops::Bound;
+
+crate::storage::engine::tests::test_engine!(Lsm::new_for_test());
+
+/// Options that make the tree flush and compact after a few writes.
+fn small_options() -> LsmOptions {
+    LsmOptions {
+        memtable_size: 256,
+        block_size: 64,
+        level0_compaction_trigger: 3,
+        level_base_size: 1 << 10,
+        level_size_multiplier: 4,
+        table_size: 512,
+        ..LsmOptions::default()
+    }
+}
+
+fn scan(tree: &mut LsmTree, range: (Bound<Vec<u8>>, Bound<Vec<u8>>)) -> Vec<(Vec<u8>, Vec<u8>)> {
+    tree.scan(range).collect::<Result<_>>().unwrap()
+}
+
+#[test]
+fn test_bloom_filter() {
+    let hashes: Vec<u64> = (0..1000u32)
+        .map(|i| bloom::hash(&i.to_be_bytes()))
+        .collect();
+    let filter = BloomFilter::decode(&BloomFilter::new(&hashes, 10).encode()).unwrap();
+    assert!(hashes.iter().all(|&hash| filter.may_contain(hash)));
+    let false_positives = (1000..11000u32)
+        .filter(|i| filter.may_contain(bloom::hash(&i.to_be_bytes())))
+        .count();
+    assert!(false_positives < 300, "{false_positives} false positives");
+}
+
+#[test]
+fn test_lsm_tree_matches_btreemap() {
+    let dir = tempfile::tempdir().unwrap();
+    let mut tree = LsmTree::open_with(dir.path(), small_options()).unwrap();
+    let mut expected = BTreeMap::new();
+    for i in 0..2000u32 {
+        let key = (i * 7919 % 500).to_be_bytes().to_vec();
+        if i % 5 == 0 {
+            tree.delete(&key).unwrap();
+            expected.remove(&key);
+        } else {
+            tree.set(&key, &i.to_be_bytes()).unwrap();
+            expected.insert(key, i.to_be_bytes().to_vec());
+        }
+    }
+    let status = tree.status().unwrap();
+    assert!(status.levels.len() > 2, "{status:?}");
+    assert!(status.levels[0].tables < 3, "{status:?}");
+
+    let check = |tree: &mut LsmTree| {
+        for i in 0..500u32 {
+            let key = i.to_be_bytes().to_vec();
+            assert_eq!(tree.get(&key).unwrap(), expected.get(&key).cloned());
+        }
+        let all = (Bound::Unbounded, Bound::Unbounded);
+        assert_eq!(
+            scan(tree, all),
+            expected.clone().into_iter().collect::<Vec<_>>()
+        );
+        let (from, to) = (100u32.to_be_bytes().to_vec(), 200u32.to_be_bytes().to_vec());
+        assert_eq!(
+            scan(
+                tree,
+                (Bound::Excluded(from.clone()), Bound::Included(to.clone()))
+            ),
+            expected
+                .range((Bound::Excluded(from), Bound::Included(to)))
+                .map(|(key, value)| (key.clone(), value.clone()))
+                .collect::<Vec<_>>()
+        );
+    };
+    check(&mut tree);
+    drop(tree);
+
+    let mut tree = LsmTree::open_with(dir.path(), small_options()).unwrap();
+    check(&mut tree);
+
+    // A full compaction leaves a single level without tombstones.
+    let before = tree.status().unwrap().disk_size;
+    tree.compact().unwrap();
+    let status = tree.status().unwrap();
+    assert!(status.disk_size < before);
+    assert!(status.levels[..status.levels.len() - 1]
+        .iter()
+        .all(|level| level.tables == 0));
+    check(&mut tree);
+}
+
+#[test]
+fn test_lsm_tree_store() {
+    let dir = tempfile::tempdir().unwrap();
+    crate::storage::kv::tests::store(LsmTree::open_with(dir.path(), small_options()).unwrap());
+}
+
+#[test]
+fn test_lsm_tree_scan_prefix() {
+    let dir = tempfile::tempdir().unwrap();
+    let mut tree = LsmTree::open_with(dir.path(), small_options()).unwrap();
+    for key in [&b"a"[..], b"b", b"ba", b"bb\xff", b"b\xff", b"c"] {
+        tree.set(key, key).unwrap();
+    }
+    tree.flush().unwrap();
+    tree.set(b"bc", b"new").unwrap();
+    tree.delete(b"ba").unwrap();
+    let keys: Vec<Vec<u8>> = tree
+        .scan_prefix(b"b")
+        .map(|item| item.map(|(key, _)| key))
+        .collect::<Result<_>>()
+        .unwrap();
+    assert_eq!(
+        keys,
+        vec![
+            b"b".to_vec(),
+            b"bb\xff".to_vec(),
+            b"bc".to_vec(),
+            b"b\xff".to_vec()
+        ]
+    );
+}
+
+#[test]
+fn test_lsm_tree_recovery() {
+    let dir = tempfile::tempdir().unwrap();
+    let mut tree = LsmTree::open_with(dir.path(), small_options()).unwrap();
+    tree.set(b"flushed", b"1").unwrap();
+    tree.flush().unwrap();
+    tree.set(b"logged", b"2").unwrap();
+    tree.set(b"torn", b"3").unwrap();
+    drop(tree);
+
+    // Cut the last write short, as if the process crashed while writing it, and leave behind
+    // a table that a flush didn't commit.
+    let wal = dir.path().join("wal");
+    let file = OpenOptions::new().write(true).open(&wal).unwrap();
+    file.set_len(fs::metadata(&wal).unwrap().len() - 1).unwrap();
+    drop(file);
+    fs::write(dir.path().join("00000099.sst"), b"partial").unwrap();
+
+    let mut tree = LsmTree::open_with(dir.path(), small_options()).unwrap();
+    assert!(!dir.path().join("00000099.sst").exists());
+    assert_eq!(tree.get(b"flushed").unwrap(), Some(b"1".to_vec()));
+    assert_eq!(tree.get(b"logged").unwrap(), Some(b"2".to_vec()));
+    assert_eq!(tree.get(b"torn").unwrap(), None);
+    tree.set(b"after", b"4").unwrap();
+    drop(tree);
+
+    let mut tree = LsmTree::open_with(dir.path(), small_options()).unwrap();
+    assert_eq!(tree.get(b"after").unwrap(), Some(b"4".to_vec()));
+    assert_eq!(tree.scan(..).count(), 3);
+}
+
+#[test]
+fn test_lsm_survives_restart() {
+    let dir = tempfile::tempdir().unwrap();
+    let mut engine = Lsm::open_with(dir.path(), small_options()).unwrap();
+    let table = Table::builder()
+        .name("people")
+        .column("id", DataType::Int, false, None, None)
+        .column("name", DataType::Text, false, None, Some(5))
+        .primary_key(&["id"])
+        .build();
+    engine.create_table(table.clone()).unwrap();
+    let mut rids = Vec::new();
+    for id in 0..100 {
+        let row = Row::from(vec![Field::from(id), Field::from("ann")]);
+        rids.push(engine.insert_row("people", row).unwrap());
+    }
+    for rid in rids.iter().step_by(2) {
+        engine.delete(Key::new("people", rid)).unwrap();
+    }
+    drop(engine);
+
+    let mut engine = Lsm::open_with(dir.path(), small_options()).unwrap();
+    assert_eq!(engine.get_table("people").unwrap(), Some(table));
+    assert_eq!(engine.scan("people").count(), 50);
+    assert_eq!(engine.lookup("people", &[Field::from(2)]).unwrap(), None);
+    assert_eq!(
+        engine.lookup("people", &[Field::from(3)]).unwrap(),
+        Some(rids[3].clone())
+    );
+    let status = engine.status().unwrap();
+    assert_eq!(status.name, "lsm");
+    assert_eq!(status.keys, 50);
+}
@@ -0,0 +1,467 @@
+use super::memtable::Memtable;
+use super::sstable::{self, Entry, SsTable, SsTableWriter};
+use crate::common::Result;
+use crate::storage::kv;
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::fs::{self, File};
+use std::io::Write;
+use std::mem;
+use std::ops::{Bound, RangeBounds};
+use std::path::{Path, PathBuf};
+
+/// Lists the SSTables of each level. It's replaced by renaming a new one over it, which commits
+/// flushes and compactions.
+const MANIFEST_FILE: &str = "MANIFEST";
+/// The write-ahead log of the memtable.
+const WAL_FILE: &str = "wal";
+
+/// Options for an [`LsmTree`].
+#[derive(Clone, Debug)]
+pub struct LsmOptions {
+    /// The size in bytes at which the memtable is flushed to an SSTable.
+    pub memtable_size: u64,
+    /// The size in bytes at which SSTable data blocks are cut.
+    pub block_size: u64,
+    /// The number of bloom filter bits per key. 10 bits give about 1% false positives.
+    pub bloom_bits_per_key: usize,
+    /// The number of SSTables in level 0 at which they're compacted into level 1.
+    pub level0_compaction_trigger: usize,
+    /// The size in bytes at which level 1 is compacted into level 2.
+    pub level_base_size: u64,
+    /// How many times larger each level after level 1 may grow than the level before it.
+    pub level_size_multiplier: u64,
+    /// The size in bytes at which compaction starts a new SSTable.
+    pub table_size: u64,
+}
+
+impl Default for LsmOptions {
+    fn default() -> Self {
+        Self {
+            memtable_size: 4 << 20,
+            block_size: 4 << 10,
+            bloom_bits_per_key: 10,
+            level0_compaction_trigger: 4,
+            level_base_size: 16 << 20,
+            level_size_multiplier: 10,
+            table_size: 2 << 20,
+        }
+    }
+}
+
+/// LSM tree status.
+#[derive(Clone, Debug, PartialEq)]
+pub struct LsmStatus {
+    /// The size in bytes of the writes in the memtable.
+    pub memtable_size: u64,
+    /// The SSTables of each level, starting with level 0.
+    pub levels: Vec<LevelStatus>,
+    /// The size in bytes of the files in the tree's directory.
+    pub disk_size: u64,
+}
+
+/// The SSTables of a level.
+#[derive(Clone, Debug, PartialEq)]
+pub struct LevelStatus {
+    pub tables: u64,
+    pub size: u64,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct Manifest {
+    next_table_id: u64,
+    levels: Vec<Vec<u64>>,
+}
+
+/// A log-structured merge tree, a key/value store tuned for writes.
+///
+/// Writes go to the [`Memtable`], and are flushed to an immutable [`SsTable`] in level 0 once
+/// it's full. Since flushed tables overlap, lookups try them newest first, after the memtable.
+/// Once level 0 has [`LsmOptions::level0_compaction_trigger`] tables, they're merged with level
+/// 1 into new tables, which don't overlap, so that a lookup reads at most one table per level
+/// after level 0. Likewise, each level past its size limit is merged into the next one. Deleted
+/// keys are written as tombstones, which are dropped once they reach the last level.
+///
+/// Scans merge the memtable and the tables that overlap the range, the newest value of a key
+/// winning. Flushes and compactions commit by replacing the manifest, so a crash leaves the
+/// tree as it was before they started.
+pub struct LsmTree {
+    dir: PathBuf,
+    options: LsmOptions,
+    memtable: Memtable,
+    /// Level 0 holds the newest table first. The tables of the other levels are sorted by key.
+    levels: Vec<Vec<SsTable>>,
+    next_table_id: u64,
+}
+
+impl LsmTree {
+    /// Opens the tree in the given directory with the default options, creating it if needed.
+    pub fn open(dir: impl AsRef<Path>) -> Result<Self> {
+        Self::open_with(dir, LsmOptions::default())
+    }
+
+    /// Opens the tree in the given directory, creating it if needed.
+    pub fn open_with(dir: impl AsRef<Path>, options: LsmOptions) -> Result<Self> {
+        let dir = dir.as_ref().to_path_buf();
+        fs::create_dir_all(&dir)?;
+        let manifest_path = dir.join(MANIFEST_FILE);
+        let manifest: Manifest = match manifest_path.exists() {
+            true => bincode::deserialize(&fs::read(&manifest_path)?)?,
+            false => Manifest::default(),
+        };
+
+        // Remove the tables of flushes and compactions that didn't commit, and the tables that
+        // compactions replaced but didn't get to remove.
+        let live: HashSet<u64> = manifest.levels.iter().flatten().copied().collect();
+        for entry in fs::read_dir(&dir)? {
+            let path = entry?.path();
+            if path.extension().and_then(|ext| ext.to_str()) != Some("sst") {
+                continue;
+            }
+            let id = path.file_stem().and_then(|s| s.to_str()).map(str::parse);
+            if !matches!(id, Some(Ok(id)) if live.contains(&id)) {
+                fs::remove_file(path)?;
+            }
+        }
+
+        let levels = manifest
+            .levels
+            .iter()
+            .map(|ids| ids.iter().map(|&id| SsTable::open(&dir, id)).collect())
+            .collect::<Result<_>>()?;
+        Ok(Self {
+            memtable: Memtable::open(&dir.join(WAL_FILE))?,
+            dir,
+            options,
+            levels,
+            next_table_id: manifest.next_table_id,
+        })
+    }
+
+    /// Gets the value of a key, if it exists.
+    pub fn get(&mut self, key: &[u8]) -> Result<Option<Vec<u8>>> {
+        if let Some(value) = self.memtable.get(key) {
+            return Ok(value.map(<[u8]>::to_vec));
+        }
+        for (level, tables) in self.levels.iter().enumerate() {
+            let tables = match level {
+                0 => tables.as_slice(),
+                _ => {
+                    let i = tables.partition_point(|table| table.last_key() < key);
+                    &tables[i..tables.len().min(i + 1)]
+                }
+            };
+            for table in tables {
+                if let Some(value) = table.get(key)? {
+                    return Ok(value);
+                }
+            }
+        }
+        Ok(None)
+    }
+
+    /// Sets the value of a key, replacing any existing value.
+    pub fn set(&mut self, key: &[u8], value: &[u8]) -> Result<()> {
+        self.memtable.write(key, Some(value))?;
+        self.flush_if_full()
+    }
+
+    /// Deletes a key, if it exists.
+    pub fn delete(&mut self, key: &[u8]) -> Result<()> {
+        self.memtable.write(key, None)?;
+        self.flush_if_full()
+    }
+
+    /// Returns the keys and values in the given range of keys, in key order.
+    pub fn scan(&mut self, range: impl RangeBounds<Vec<u8>>) -> ScanIterator<'_> {
+        let range = kv::range_bounds(range);
+        let mut sources: Vec<Box<dyn Iterator<Item = Result<Entry>> + '_>> = vec![Box::new(
+            self.memtable
+                .range(range.clone())
+                .map(|(key, value)| Ok((key.clone(), value.clone()))),
+        )];
+        for (level, tables) in self.levels.iter().enumerate() {
+            let tables: Vec<&SsTable> = tables
+                .iter()
+                .filter(|table| table.overlaps(&range))
+                .collect();
+            match level {
+                0 => sources.extend(tables.into_iter().map(|table| {
+                    Box::new(table.scan(range.clone())) as Box<dyn Iterator<Item = _>>
+                })),
+                _ => {
+                    let range = range.clone();
+                    sources.push(Box::new(
+                        tables
+                            .into_iter()
+                            .flat_map(move |table| table.scan(range.clone())),
+                    ))
+                }
+            }
+        }
+        ScanIterator {
+            inner: MergeIterator::new(sources),
+        }
+    }
+
+    /// Returns the keys and values whose keys start with the given prefix, in key order.
+    pub fn scan_prefix(&mut self, prefix: &[u8]) -> ScanIterator<'_> {
+        self.scan(kv::prefix_range(prefix))
+    }
+
+    /// Flushes the memtable to a new SSTable in level 0, and runs the compactions that are due.
+    pub fn flush(&mut self) -> Result<()> {
+        if self.memtable.is_empty() {
+            return Ok(());
+        }
+        let mut writer = self.new_table()?;
+        for (key, value) in self.memtable.range((Bound::Unbounded, Bound::Unbounded)) {
+            writer.add(key, value.as_deref())?;
+        }
+        let table = writer.finish()?;
+        if self.levels.is_empty() {
+            self.levels.push(Vec::new());
+        }
+        self.levels[0].insert(0, table);
+        self.write_manifest()?;
+        self.memtable.clear()?;
+        self.compact_due()
+    }
+
+    /// Flushes the memtable, and merges all levels into the last one, dropping stale values and
+    /// tombstones.
+    pub fn compact(&mut self) -> Result<()> {
+        self.flush()?;
+        while self.levels.len() < 2 {
+            self.levels.push(Vec::new());
+        }
+        for level in 0..self.levels.len() - 1 {
+            if !self.levels[level].is_empty() {
+                self.compact_level(level)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Returns the tree's status.
+    pub fn status(&mut self) -> Result<LsmStatus> {
+        let mut disk_size = 0;
+        for entry in fs::read_dir(&self.dir)? {
+            disk_size += entry?.metadata()?.len();
+        }
+        Ok(LsmStatus {
+            memtable_size: self.memtable.size(),
+            levels: self
+                .levels
+                .iter()
+                .map(|tables| LevelStatus {
+                    tables: tables.len() as u64,
+                    size: tables.iter().map(SsTable::size).sum(),
+                })
+                .collect(),
+            disk_size,
+        })
+    }
+
+    fn flush_if_full(&mut self) -> Result<()> {
+        match self.memtable.size() >= self.options.memtable_size {
+            true => self.flush(),
+            false => Ok(()),
+        }
+    }
+
+    /// Compacts level 0 once it has too many tables, and each other level once it's too large.
+    fn compact_due(&mut self) -> Result<()> {
+        loop {
+            if self.levels[0].len() >= self.options.level0_compaction_trigger {
+                self.compact_level(0)?;
+                continue;
+            }
+            let mut max_size = self.options.level_base_size;
+            let mut full = None;
+            for (level, tables) in self.levels.iter().enumerate().skip(1) {
+                if tables.iter().map(SsTable::size).sum::<u64>() > max_size {
+                    full = Some(level);
+                    break;
+                }
+                max_size = max_size.saturating_mul(self.options.level_size_multiplier);
+            }
+            match full {
+                Some(level) => self.compact_level(level)?,
+                None => return Ok(()),
+            }
+        }
+    }
+
+    /// Merges a level into the next one, replacing the tables of both.
+    fn compact_level(&mut self, level: usize) -> Result<()> {
+        if self.levels.len() == level + 1 {
+            self.levels.push(Vec::new());
+        }
+        // Tombstones can go once nothing older could be shadowed by them.
+        let last = self.levels[level + 2..].iter().all(Vec::is_empty);
+
+        let mut sources: Vec<Box<dyn Iterator<Item = Result<Entry>>>> = Vec::new();
+        for table in &self.levels[level] {
+            sources.push(Box::new(table.scan((Bound::Unbounded, Bound::Unbounded))));
+        }
+        sources.push(Box::new(
+            self.levels[level + 1]
+                .iter()
+                .flat_map(|table| table.scan((Bound::Unbounded, Bound::Unbounded))),
+        ));
+
+        let mut tables = Vec::new();
+        let mut writer: Option<SsTableWriter> = None;
+        for entry in MergeIterator::new(sources) {
+            let (key, value) = entry?;
+            if value.is_none() && last {
+                continue;
+            }
+            let output = match &mut writer {
+                Some(writer) => writer,
+                None => writer.insert(SsTableWriter::new(
+                    &self.dir,
+                    self.next_table_id,
+                    self.options.block_size,
+                    self.options.bloom_bits_per_key,
+                )?),
+            };
+            output.add(&key, value.as_deref())?;
+            if output.size() >= self.options.table_size {
+                tables.push(writer.take().expect("writer is set").finish()?);
+                self.next_table_id += 1;
+            }
+        }
+        if let Some(writer) = writer {
+            tables.push(writer.finish()?);
+            self.next_table_id += 1;
+        }
+
+        let mut replaced = mem::take(&mut self.levels[level]);
+        replaced.extend(mem::replace(&mut self.levels[level + 1], tables));
+        self.write_manifest()?;
+        for table in replaced {
+            fs::remove_file(sstable::path(&self.dir, table.id()))?;
+        }
+        Ok(())
+    }
+
+    fn new_table(&mut self) -> Result<SsTableWriter> {
+        let id = self.next_table_id;
+        self.next_table_id += 1;
+        SsTableWriter::new(
+            &self.dir,
+            id,
+            self.options.block_size,
+            self.options.bloom_bits_per_key,
+        )
+    }
+
+    /// Writes the manifest to a temporary file, and renames it over the current one.
+    fn write_manifest(&mut self) -> Result<()> {
+        let manifest = Manifest {
+            next_table_id: self.next_table_id,
+            levels: self
+                .levels
+                .iter()
+                .map(|tables| tables.iter().map(SsTable::id).collect())
+                .collect(),
+        };
+        let path = self.dir.join(MANIFEST_FILE);
+        let tmp_path = path.with_extension("tmp");
+        let mut file = File::create(&tmp_path)?;
+        file.write_all(&bincode::serialize(&manifest)?)?;
+        file.sync_all()?;
+        fs::rename(tmp_path, path)?;
+        Ok(())
+    }
+}
+
+impl Drop for LsmTree {
+    /// Flushes the write-ahead log to disk. The memtable is replayed from it when the tree is
+    /// next opened.
+    fn drop(&mut self) {
+        let _ = self.memtable.sync();
+    }
+}
+
+/// Merges sorted sources of entries, which are given newest first. Where several sources have a
+/// key, the entry of the newest one is returned and the others are skipped.
+struct MergeIterator<'a> {
+    sources: Vec<Box<dyn Iterator<Item = Result<Entry>> + 'a>>,
+    /// The next entry of each source.
+    heads: Vec<Option<Entry>>,
+    started: bool,
+}
+
+impl<'a> MergeIterator<'a> {
+    fn new(sources: Vec<Box<dyn Iterator<Item = Result<Entry>> + 'a>>) -> Self {
+        Self {
+            heads: vec![None; sources.len()],
+            sources,
+            started: false,
+        }
+    }
+
+    fn advance(&mut self, source: usize) -> Result<()> {
+        self.heads[source] = None;
+        self.heads[source] = self.sources[source].next().transpose()?;
+        Ok(())
+    }
+
+    fn try_next(&mut self) -> Result<Option<Entry>> {
+        if !self.started {
+            for source in 0..self.sources.len() {
+                self.advance(source)?;
+            }
+            self.started = true;
+        }
+        let mut newest: Option<usize> = None;
+        for (source, head) in self.heads.iter().enumerate() {
+            let Some((key, _)) = head else { continue };
+            match newest.and_then(|newest| self.heads[newest].as_ref()) {
+                Some((newest_key, _)) if newest_key <= key => {}
+                _ => newest = Some(source),
+            }
+        }
+        let Some(newest) = newest else {
+            return Ok(None);
+        };
+        let entry = self.heads[newest].take().expect("head is set");
+        for source in 0..self.sources.len() {
+            if source == newest || matches!(&self.heads[source], Some((key, _)) if *key == entry.0)
+            {
+                self.advance(source)?;
+            }
+        }
+        Ok(Some(entry))
+    }
+}
+
+impl Iterator for MergeIterator<'_> {
+    type Item = Result<Entry>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.try_next().transpose()
+    }
+}
+
+/// An iterator over the live keys and values in a range of keys.
+pub struct ScanIterator<'a> {
+    inner: MergeIterator<'a>,
+}
+
+impl Iterator for ScanIterator<'_> {
+    type Item = Result<(Vec<u8>, Vec<u8>)>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            match self.inner.next()? {
+                Ok((key, Some(value))) => return Some(Ok((key, value))),
+                Ok((_, None)) => continue,
+                Err(err) => return Some(Err(err)),
+            }
+        }
+    }
+}
@@ -0,0 +1,267 @@
+use crate::common::{Error, Result};
+use crate::errinput;
+use crate::storage::disk::disk_manager::PageId;
+use crate::storage::engine::{
+    encode_key_range, is_empty_range, ScanBounds, ScanRange, Status, TableStatus,
+};
+use crate::storage::page::RecordId;
+use crate::storage::tables::{check_unique, primary_key};
+use crate::storage::tuple::Tuple;
+use crate::storage::{engine, Engine, Key};
+use crate::types::field::Field;
+use crate::types::{SchemaChange, Table};
+use std::collections::{btree_map, BTreeMap, HashMap};
+
+/// An in-memory storage engine, which keeps each table's tuples in a BTreeMap. Nothing is
+/// persisted, so it's mostly useful as a reference to test other engines against.
+///
+/// Record ids are synthetic: the tuples of a table are numbered in insertion order, and tuple `n`
+/// gets the record id with page `n / 2^16` and slot `n % 2^16`. Scans return the tuples in
+/// insertion order, like a heap that was never vacuumed.
+#[derive(Debug, Default)]
+pub struct Memory {
+    tables: HashMap<String, MemoryTable>,
+}
+
+#[derive(Debug)]
+struct MemoryTable {
+    schema: Table,
+    tuples: BTreeMap<u64, Tuple>,
+    /// Maps encoded primary keys to record ids, see [`crate::storage::KeyDirectory`].
+    keys: BTreeMap<Vec<u8>, RecordId>,
+    next_id: u64,
+}
+
+impl Memory {
+    /// Creates a new, empty in-memory engine.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn must_get(&mut self, table_name: &str) -> Result<&mut MemoryTable> {
+        self.tables
+            .get_mut(table_name)
+            .ok_or_else(|| Error::InvalidInput(format!("table {table_name} does not exist")))
+    }
+}
+
+impl MemoryTable {
+    fn must_get_tuple(&self, record_id: &RecordId) -> Result<&Tuple> {
+        match self.tuples.get(&to_id(record_id)) {
+            Some(tuple) => Ok(tuple),
+            None => errinput!(
+                "no tuple with record id {} in table {}",
+                record_id.to_string(),
+                self.schema.name()
+            ),
+        }
+    }
+}
+
+pub(super) fn to_id(record_id: &RecordId) -> u64 {
+    (record_id.page_id() as u64) << 16 | record_id.slot_id() as u64
+}
+
+pub(super) fn to_record_id(id: u64) -> RecordId {
+    RecordId::new((id >> 16) as PageId, id as u16)
+}
+
+impl Engine for Memory {
+    type ScanIterator<'a> = ScanIterator<'a>;
+
+    fn create_table(&mut self, table: Table) -> Result<()> {
+        if self.tables.contains_key(table.name()) {
+            return errinput!("table {} already exists", table.name());
+        }
+        let table = MemoryTable {
+            schema: table,
+            tuples: BTreeMap::new(),
+            keys: BTreeMap::new(),
+            next_id: 0,
+        };
+        self.tables.insert(table.schema.name().to_string(), table);
+        Ok(())
+    }
+
+    fn delete_table(&mut self, table_name: &str) -> Result<bool> {
+        Ok(self.tables.remove(table_name).is_some())
+    }
+
+    fn truncate_table(&mut self, table_name: &str) -> Result<()> {
+        let table = self.must_get(table_name)?;
+        table.tuples.clear();
+        table.keys.clear();
+        Ok(())
+    }
+
+    fn get_table(&mut self, table_name: &str) -> Result<Option<Table>> {
+        Ok(self
+            .tables
+            .get(table_name)
+            .map(|table| table.schema.clone()))
+    }
+
+    fn alter_table(&mut self, table_name: &str, change: SchemaChange) -> Result<Table> {
+        if let SchemaChange::RenameTable(new_name) = &change {
+            if self.tables.contains_key(new_name) {
+                return errinput!("table {new_name} already exists");
+            }
+        }
+        let table = self.must_get(table_name)?;
+        table.schema.alter(change)?;
+        let schema = table.schema.clone();
+        if schema.name() != table_name {
+            let table = self.tables.remove(table_name).expect("the table exists");
+            self.tables.insert(schema.name().to_string(), table);
+        }
+        Ok(schema)
+    }
+
+    fn delete(&mut self, key: Key) -> Result<()> {
+        let table = self.must_get(key.table_name)?;
+        if let Some(tuple) = table.tuples.remove(&to_id(key.record_id)) {
+            if let Some(primary_key) = primary_key(&table.schema, &tuple)? {
+                table.keys.remove(&primary_key);
+            }
+        }
+        Ok(())
+    }
+
+    fn get(&mut self, key: Key) -> Result<Tuple> {
+        let table = self.must_get(key.table_name)?;
+        table.must_get_tuple(key.record_id).cloned()
+    }
+
+    fn insert(&mut self, table_name: &str, value: Tuple) -> Result<RecordId> {
+        let table = self.must_get(table_name)?;
+        let primary_key = primary_key(&table.schema, &value)?;
+        if let Some(primary_key) = &primary_key {
+            check_unique(&table.keys, &table.schema, primary_key)?;
+        }
+        let id = table.next_id;
+        table.next_id += 1;
+        table.tuples.insert(id, value);
+        if let Some(primary_key) = primary_key {
+            table.keys.insert(primary_key, to_record_id(id));
+        }
+        Ok(to_record_id(id))
+    }
+
+    fn scan(&mut self, table_name: &str) -> Self::ScanIterator<'_> {
+        let table = self
+            .tables
+            .get(table_name)
+            .unwrap_or_else(|| panic!("Could not access table {table_name}"));
+        ScanIterator {
+            inner: table.tuples.iter(),
+        }
+    }
+
+    fn scan_dyn(&mut self, table_name: &str) -> Result<Box<dyn engine::ScanIterator + '_>> {
+        self.must_get(table_name)?;
+        Ok(Box::new(self.scan(table_name)))
+    }
+
+    fn scan_range(
+        &mut self,
+        table_name: &str,
+        range: ScanRange,
+    ) -> Result<Box<dyn engine::ScanIterator + '_>> {
+        let table = self.must_get(table_name)?;
+        let rows: Box<dyn DoubleEndedIterator<Item = (u64, &Tuple)>> = match range.bounds {
+            ScanBounds::RecordIds(start, end) => {
+                let ids = (start.as_ref().map(to_id), end.as_ref().map(to_id));
+                if is_empty_range(&ids) {
+                    return Ok(Box::new(std::iter::empty()));
+                }
+                Box::new(table.tuples.range(ids).map(|(id, tuple)| (*id, tuple)))
+            }
+            ScanBounds::PrimaryKeys(start, end) => {
+                let keys = encode_key_range(&table.schema, &start, &end)?;
+                if is_empty_range(&keys) {
+                    return Ok(Box::new(std::iter::empty()));
+                }
+                let tuples = &table.tuples;
+                Box::new(table.keys.range(keys).map(|(_, record_id)| {
+                    let id = to_id(record_id);
+                    (id, &tuples[&id])
+                }))
+            }
+        };
+        let rows = rows.map(|(id, tuple)| Ok((to_record_id(id), tuple.clone())));
+        Ok(match range.reverse {
+            true => Box::new(rows.rev()),
+            false => Box::new(rows),
+        })
+    }
+
+    fn update(&mut self, key: Key, value: Tuple) -> Result<()> {
+        let table = self.must_get(key.table_name)?;
+        let old_key = primary_key(&table.schema, table.must_get_tuple(key.record_id)?)?;
+        let new_key = primary_key(&table.schema, &value)?;
+        if let Some(new_key) = &new_key {
+            if old_key.as_ref() != Some(new_key) {
+                check_unique(&table.keys, &table.schema, new_key)?;
+            }
+        }
+        table.tuples.insert(to_id(key.record_id), value);
+        if let Some(old_key) = old_key {
+            table.keys.remove(&old_key);
+        }
+        if let Some(new_key) = new_key {
+            table.keys.insert(new_key, key.record_id.clone());
+        }
+        Ok(())
+    }
+
+    fn lookup(&mut self, table_name: &str, key: &[Field]) -> Result<Option<RecordId>> {
+        let table = self.must_get(table_name)?;
+        let key = table.schema.encode_primary_key(key)?;
+        Ok(table.keys.get(&key).cloned())
+    }
+
+    fn status(&mut self) -> Result<Status> {
+        let tables: BTreeMap<String, TableStatus> = self
+            .tables
+            .iter()
+            .map(|(name, table)| {
+                let status = TableStatus {
+                    keys: table.tuples.len() as u64,
+                    size: table.tuples.values().map(|t| t.data.len() as u64).sum(),
+                    pages: 0,
+                };
+                (name.clone(), status)
+            })
+            .collect();
+        Ok(Status {
+            name: "memory".to_string(),
+            keys: tables.values().map(|table| table.keys).sum(),
+            size: tables.values().map(|table| table.size).sum(),
+            tables,
+            pages: 0,
+            disk_size: 0,
+            buffer_pool: None,
+        })
+    }
+}
+
+pub struct ScanIterator<'a> {
+    inner: btree_map::Iter<'a, u64, Tuple>,
+}
+
+impl Iterator for ScanIterator<'_> {
+    type Item = Result<(RecordId, Tuple)>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.inner
+            .next()
+            .map(|(id, tuple)| Ok((to_record_id(*id), tuple.clone())))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    crate::storage::engine::tests::test_engine!(Memory::new());
+}
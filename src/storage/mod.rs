@@ -1,12 +1,20 @@
+pub mod bitcask;
 pub mod buffer;
+mod catalog;
 pub mod disk;
 pub mod engine;
 pub mod heap;
 pub mod index;
+pub mod kv;
+pub mod lsm;
+mod memory;
 pub mod page;
 pub mod simple;
 mod tables;
 pub mod tuple;
 
+pub use bitcask::Bitcask;
 pub use engine::{Engine, Key, ScanIterator};
+pub use lsm::Lsm;
+pub use memory::Memory;
 pub use tables::{HeapTableManager, KeyDirectory};
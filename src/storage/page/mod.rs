@@ -5,3 +5,4 @@ mod table_page;
 pub use page::Page;
 pub use record_id::{RecordId, INVALID_RID};
 pub use table_page::{TablePage, TablePageBuilder, TablePageHandle, TablePageIterator};
+pub(crate) use table_page::{MIN_TUPLE_ALLOCATION, TABLE_PAGE_HEADER_SIZE, TUPLE_INFO_SIZE};
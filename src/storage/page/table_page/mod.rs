@@ -3,3 +3,4 @@ mod table_page;
 mod tests;
 
 pub use table_page::{TablePage, TablePageBuilder, TablePageHandle, TablePageIterator};
+pub(crate) use table_page::{MIN_TUPLE_ALLOCATION, TABLE_PAGE_HEADER_SIZE, TUPLE_INFO_SIZE};
@@ -11,6 +11,24 @@ use std::sync::{Arc, RwLock, RwLockReadGuard};
 
 pub type TablePageHandle = Arc<RwLock<TablePage>>;
 
+/// Size of the fixed page header: page_id (4) + next_page_id (4) + tuple_cnt (2) +
+/// deleted_tuple_cnt (2).
+pub(crate) const TABLE_PAGE_HEADER_SIZE: usize = 12;
+/// Size of a serialized slot in the page header: offset (2) + size_bytes (2).
+pub(crate) const TUPLE_INFO_SIZE: usize = 4;
+
+/// Every slot reserves at least this many bytes, so that any tuple can be replaced in place by a
+/// forwarding pointer, i.e. a serialized [`RecordId`], when it is moved to another page.
+pub(crate) const MIN_TUPLE_ALLOCATION: usize = 6;
+
+/// Payloads never exceed a page, so the high bits of a serialized slot's `size_bytes` are free to
+/// carry the tuple's metadata flags.
+const SLOT_DELETED_FLAG: u16 = 0x8000;
+const SLOT_OVERFLOW_FLAG: u16 = 0x4000;
+const SLOT_FORWARDED_FLAG: u16 = 0x2000;
+const SLOT_RELOCATED_FLAG: u16 = 0x1000;
+const SLOT_SIZE_MASK: u16 = 0x0FFF;
+
 #[derive(Debug, PartialEq, Clone, Copy)]
 pub struct TupleInfo {
     pub(crate) offset: u16,
@@ -68,23 +86,71 @@ impl TablePage {
         self.tuple_cnt + self.deleted_tuple_cnt
     }
 
+    /// Returns the offset of the first byte of live tuple data on the page. Tuples are usually
+    /// stored in slot order, but a tuple that grows is moved to the front of the tuple data.
+    fn tuples_start(&self) -> usize {
+        self.tuple_info
+            .iter()
+            .filter(|info| !info.metadata.is_deleted())
+            .map(|info| info.offset as usize)
+            .min()
+            .unwrap_or(RUSTY_DB_PAGE_SIZE_BYTES)
+    }
+
+    /// Returns the number of bytes reserved by the live tuples on the page.
+    fn live_tuple_bytes(&self) -> usize {
+        self.tuple_info
+            .iter()
+            .filter(|info| !info.metadata.is_deleted())
+            .map(|info| (info.size_bytes as usize).max(MIN_TUPLE_ALLOCATION))
+            .sum()
+    }
+
+    /// Returns the size of the largest payload that can be inserted into the page, counting the
+    /// space of deleted tuples, which is reclaimed by [`Self::compact`] when needed. Their slots
+    /// are kept, so that record ids are never handed out twice.
+    pub fn free_space(&self) -> usize {
+        let header_size = TABLE_PAGE_HEADER_SIZE + (self.tuple_info.len() + 1) * TUPLE_INFO_SIZE;
+        RUSTY_DB_PAGE_SIZE_BYTES.saturating_sub(header_size + self.live_tuple_bytes())
+    }
+
+    /// Packs the live tuples against the end of the page, reclaiming the bytes of deleted tuples and
+    /// of tuples that have since been shrunk or moved. Slot ids are left unchanged.
+    pub fn compact(&mut self) {
+        let mut data = vec![0; RUSTY_DB_PAGE_SIZE_BYTES];
+        let mut tuples_start = RUSTY_DB_PAGE_SIZE_BYTES;
+        for info in self.tuple_info.iter_mut() {
+            if info.metadata.is_deleted() {
+                info.offset = RUSTY_DB_PAGE_SIZE_BYTES as u16;
+                info.size_bytes = 0;
+                continue;
+            }
+            let offset = info.offset as usize;
+            let size = info.size_bytes as usize;
+            tuples_start -= size.max(MIN_TUPLE_ALLOCATION);
+            data[tuples_start..(tuples_start + size)]
+                .copy_from_slice(&self.data[offset..(offset + size)]);
+            info.offset = tuples_start as u16;
+        }
+        self.data = data;
+        self.is_dirty = true;
+    }
+
     pub fn get_next_tuple_offset(&self, payload: &Tuple) -> Option<u16> {
-        let tuple_size_bytes = payload.data.len();
-        let tuples_end = match self.total_tuple_count() {
-            0 => RUSTY_DB_PAGE_SIZE_BYTES,
-            _ => self.tuple_info[(self.total_tuple_count() - 1) as usize].offset as usize,
-        };
+        let tuple_size_bytes = payload.data.len().max(MIN_TUPLE_ALLOCATION);
+        let tuples_end = self.tuples_start();
         if tuple_size_bytes > tuples_end {
             return None;
         }
         // tuples are positioned at the end of the page growing inward, with new tuples appended to
         // the front, e.g. | ... t_{n}, t_{n-1}, ... t_{0} |.
         let tuples_start = (tuples_end - tuple_size_bytes) as u16;
-        let header_size = 8 + (self.total_tuple_count() + 1) * 4;
+        let header_size =
+            TABLE_PAGE_HEADER_SIZE + (self.total_tuple_count() as usize + 1) * TUPLE_INFO_SIZE;
 
         // Recall that the header and tuples are positioned on opposite sides of the page, growing
         // inward toward each other, i.e. | header => free space <= tuples |.
-        Some(tuples_start).filter(|_| header_size < tuples_start)
+        Some(tuples_start).filter(|_| header_size <= tuples_start as usize)
     }
 
     pub fn update_tuple_in_place_unchecked(
@@ -115,6 +181,64 @@ impl TablePage {
         Ok(())
     }
 
+    /// Replaces the tuple in the slot `rid` with a payload of any size. A payload that fits in the
+    /// slot's current allocation is written in place; a larger one is written to the free space on
+    /// the page, and the slot is pointed at it. Either way, the tuple keeps its slot id.
+    ///
+    /// # Returns
+    /// - `Ok(true)`: If the tuple was updated.
+    /// - `Ok(false)`: If the payload doesn't fit on the page; the page is left unchanged.
+    pub fn update_tuple(
+        &mut self,
+        meta: TupleMetadata,
+        tuple: Tuple,
+        rid: &RecordId,
+    ) -> Result<bool> {
+        let slot = rid.slot_id() as usize;
+        if slot >= self.total_tuple_count() as usize {
+            return Err(Error::InvalidData("Slot ID out of bounds".into()));
+        }
+
+        let len = tuple.data.len();
+        let old_meta = self.tuple_info[slot].metadata;
+        let allocated = match old_meta.is_deleted() {
+            true => 0,
+            false => (self.tuple_info[slot].size_bytes as usize).max(MIN_TUPLE_ALLOCATION),
+        };
+        let offset = match len <= allocated {
+            true => self.tuple_info[slot].offset as usize,
+            false => {
+                let header_size =
+                    TABLE_PAGE_HEADER_SIZE + self.total_tuple_count() as usize * TUPLE_INFO_SIZE;
+                let contiguous = self.tuples_start().saturating_sub(header_size);
+                let reclaimable =
+                    RUSTY_DB_PAGE_SIZE_BYTES - header_size - (self.live_tuple_bytes() - allocated);
+                let needed = len.max(MIN_TUPLE_ALLOCATION);
+                if needed > reclaimable {
+                    return Ok(false);
+                }
+                if needed > contiguous {
+                    // Drop the slot's current payload before compacting, since it is replaced.
+                    self.tuple_info[slot].size_bytes = 0;
+                    self.tuple_info[slot].metadata.set_deleted(true);
+                    self.compact();
+                    self.tuple_info[slot].metadata = old_meta;
+                }
+                self.tuples_start() - needed
+            }
+        };
+
+        self.update_tuple_cnt(&old_meta.is_deleted(), &meta.is_deleted());
+        self.tuple_info[slot] = TupleInfo {
+            offset: offset as u16,
+            size_bytes: len as u16,
+            metadata: meta,
+        };
+        self.data[offset..(offset + len)].copy_from_slice(&tuple.data);
+        self.is_dirty = true;
+        Ok(true)
+    }
+
     pub fn update_tuple_cnt(&mut self, old_meta_delete: &bool, new_meta_delete: &bool) {
         match (old_meta_delete, new_meta_delete) {
             (true, false) => {
@@ -158,7 +282,8 @@ impl Page for TablePage {
             return Err(Error::InvalidData("slot id out of bounds".into()));
         }
         let tuple_info = &self.tuple_info[slot];
-        let tuple_data = &self.data[tuple_info.offset as usize..(tuple_info.offset + tuple_info.size_bytes) as usize];
+        let tuple_data = &self.data
+            [tuple_info.offset as usize..(tuple_info.offset + tuple_info.size_bytes) as usize];
         Ok(Tuple::from(tuple_data.to_vec()))
     }
 
@@ -167,13 +292,21 @@ impl Page for TablePage {
         meta: TupleMetadata,
         tuple: Tuple,
     ) -> Option<Self::InsertOutputType> {
-        let offset = self.get_next_tuple_offset(&tuple)?;
-        let slot_id = self.total_tuple_count();
-        self.tuple_info.push(TupleInfo {
+        let offset = match self.get_next_tuple_offset(&tuple) {
+            Some(offset) => offset,
+            None if tuple.data.len().max(MIN_TUPLE_ALLOCATION) <= self.free_space() => {
+                self.compact();
+                self.get_next_tuple_offset(&tuple)?
+            }
+            None => return None,
+        };
+        let tuple_info = TupleInfo {
             offset,
             size_bytes: tuple.data.len() as u16,
             metadata: meta,
-        });
+        };
+        self.tuple_info.push(tuple_info);
+        let slot_id = (self.tuple_info.len() - 1) as u16;
         let start = offset as usize;
         let end = start + tuple.data.len();
         self.data[start..end].copy_from_slice(&tuple.data);
@@ -254,22 +387,26 @@ impl Page for TablePage {
 
         // tuple_info: Vec<TupleInfo>
         self.tuple_info.iter().for_each(|info| {
-            match info.metadata.is_deleted() {
-                true => {
-                    // this slot is vacant
-                    result[cursor..(cursor + 4)].fill(0);
-                    cursor += 4;
-                }
-                false => {
-                    let offset_bytes = info.offset.to_le_bytes();
-                    result[cursor..(cursor + 2)].copy_from_slice(&offset_bytes);
-                    cursor += 2;
-
-                    let size_bytes = info.size_bytes.to_le_bytes();
-                    result[cursor..(cursor + 2)].copy_from_slice(&size_bytes);
-                    cursor += 2;
-                }
-            }
+            let offset_bytes = info.offset.to_le_bytes();
+            result[cursor..(cursor + 2)].copy_from_slice(&offset_bytes);
+            cursor += 2;
+
+            // Deleted slots keep their offset, since their bytes are still in use until the page
+            // is compacted.
+            let metadata = info.metadata;
+            let mut size = info.size_bytes;
+            [
+                (metadata.is_deleted(), SLOT_DELETED_FLAG),
+                (metadata.is_overflow(), SLOT_OVERFLOW_FLAG),
+                (metadata.is_forwarded(), SLOT_FORWARDED_FLAG),
+                (metadata.is_relocated(), SLOT_RELOCATED_FLAG),
+            ]
+            .iter()
+            .filter(|(is_set, _)| *is_set)
+            .for_each(|(_, flag)| size |= flag);
+            let size_bytes = size.to_le_bytes();
+            result[cursor..(cursor + 2)].copy_from_slice(&size_bytes);
+            cursor += 2;
         });
 
         result
@@ -314,15 +451,18 @@ impl Page for TablePage {
             let size = u16::from_le_bytes(size_bytes.try_into().unwrap());
             cursor += 2;
 
-            let mut deleted = false;
+            let mut deleted = size & SLOT_DELETED_FLAG != 0;
             if size == 0 && offset == 0 {
                 deleted = true;
             }
 
-            let meta = TupleMetadata::new(deleted);
+            let mut meta = TupleMetadata::new(deleted);
+            meta.set_overflow(size & SLOT_OVERFLOW_FLAG != 0);
+            meta.set_forwarded(size & SLOT_FORWARDED_FLAG != 0);
+            meta.set_relocated(size & SLOT_RELOCATED_FLAG != 0);
             let tuple_info = TupleInfo {
                 offset,
-                size_bytes: size,
+                size_bytes: size & SLOT_SIZE_MASK,
                 metadata: meta,
             };
             page.tuple_info.push(tuple_info);
@@ -351,11 +491,9 @@ impl TablePageIterator {
         &self,
         page_slot: u16,
         page_guard: &RwLockReadGuard<TablePage>,
-    ) -> Option<(RecordId, Tuple)> {
-        match page_guard.tuple_info[page_slot as usize]
-            .metadata
-            .is_deleted()
-        {
+    ) -> Option<(RecordId, TupleMetadata, Tuple)> {
+        let metadata = page_guard.tuple_info[page_slot as usize].metadata;
+        match metadata.is_deleted() {
             // tombstone tuple; no tuple to return.
             true => None,
             // tuple is not deleted; return it!
@@ -363,16 +501,14 @@ impl TablePageIterator {
                 let rid = RecordId::new(page_guard.page_id, page_slot);
                 page_guard
                     .get_tuple(&rid)
-                    .map_or_else(|_| None, |payload| Some((rid, payload)))
+                    .map_or_else(|_| None, |payload| Some((rid, metadata, payload)))
             }
         }
     }
-}
 
-impl Iterator for TablePageIterator {
-    type Item = (RecordId, Tuple);
-
-    fn next(&mut self) -> Option<Self::Item> {
+    /// Like [`Iterator::next`], but also returns the slot's metadata so that callers can tell
+    /// apart payloads that are stored out of line.
+    pub(crate) fn next_with_metadata(&mut self) -> Option<(RecordId, TupleMetadata, Tuple)> {
         let page_guard = self.page.read().unwrap();
 
         // Use a loop to skip deleted tuples and find the next valid one.
@@ -388,6 +524,15 @@ impl Iterator for TablePageIterator {
             }
         }
     }
+}
+
+impl Iterator for TablePageIterator {
+    type Item = (RecordId, Tuple);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.next_with_metadata()
+            .map(|(rid, _metadata, payload)| (rid, payload))
+    }
 
     fn nth(&mut self, n: usize) -> Option<Self::Item> {
         self.index.fetch_add(n as u16, Ordering::SeqCst);
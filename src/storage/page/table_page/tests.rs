@@ -35,8 +35,8 @@ pub fn test_overfull_page() {
         .build_with_handle();
 
     let mut page = TablePage::builder().page_id(0).build();
-    // cost of next_page_id (u32) + tuple_cnt (u16) + deleted_tuple_cnt (u16) = 8 bytes.
-    let mut page_size: usize = 8;
+    // cost of page_id, next_page_id, tuple_cnt, and deleted_tuple_cnt.
+    let mut page_size: usize = TABLE_PAGE_HEADER_SIZE;
 
     loop {
         let tuple = create_random_row(&schema, None).to_tuple(&schema).unwrap();
@@ -62,3 +62,136 @@ pub fn test_iterate_page() {
     let page_guard = page.read().unwrap();
     assert_eq!(iter.count(), page_guard.tuple_count() as usize);
 }
+
+#[test]
+pub fn test_serialize_overflow_flag() {
+    let mut page = TablePage::builder().page_id(3).build();
+    let inline_slot = page
+        .insert_tuple(TupleMetadata::new(false), Tuple::from(vec![7_u8; 10]))
+        .unwrap();
+    let overflow_slot = page
+        .insert_tuple(
+            TupleMetadata::overflow_payload_metadata(),
+            Tuple::from(vec![9_u8; 8]),
+        )
+        .unwrap();
+
+    let page = TablePage::deserialize(&page.serialize());
+    let inline_rid = RecordId::new(3, inline_slot);
+    let overflow_rid = RecordId::new(3, overflow_slot);
+    assert!(!page.get_tuple_metadata(&inline_rid).unwrap().is_overflow());
+    assert!(page
+        .get_tuple_metadata(&overflow_rid)
+        .unwrap()
+        .is_overflow());
+    assert_eq!(vec![7_u8; 10], page.get_tuple(&inline_rid).unwrap().data);
+    assert_eq!(vec![9_u8; 8], page.get_tuple(&overflow_rid).unwrap().data);
+}
+
+#[test]
+pub fn test_update_tuple_resizes_slot() {
+    let mut page = TablePage::builder().page_id(0).build();
+    let slot = page
+        .insert_tuple(TupleMetadata::new(false), Tuple::from(vec![1_u8; 100]))
+        .unwrap();
+    let other_slot = page
+        .insert_tuple(TupleMetadata::new(false), Tuple::from(vec![2_u8; 100]))
+        .unwrap();
+    let rid = RecordId::new(0, slot);
+    let other_rid = RecordId::new(0, other_slot);
+
+    // Shrinking and growing both keep the slot id.
+    let meta = TupleMetadata::new(false);
+    assert!(page
+        .update_tuple(meta, Tuple::from(vec![3_u8; 10]), &rid)
+        .unwrap());
+    assert_eq!(vec![3_u8; 10], page.get_tuple(&rid).unwrap().data);
+    assert!(page
+        .update_tuple(meta, Tuple::from(vec![4_u8; 1000]), &rid)
+        .unwrap());
+    assert_eq!(vec![4_u8; 1000], page.get_tuple(&rid).unwrap().data);
+    assert_eq!(vec![2_u8; 100], page.get_tuple(&other_rid).unwrap().data);
+
+    // New tuples don't overwrite the moved tuple.
+    let new_slot = page
+        .insert_tuple(TupleMetadata::new(false), Tuple::from(vec![5_u8; 100]))
+        .unwrap();
+    assert_eq!(vec![4_u8; 1000], page.get_tuple(&rid).unwrap().data);
+    assert_eq!(
+        vec![5_u8; 100],
+        page.get_tuple(&RecordId::new(0, new_slot)).unwrap().data
+    );
+
+    // A payload larger than the free space is rejected without modifying the page.
+    assert!(!page
+        .update_tuple(meta, Tuple::from(vec![6_u8; 4000]), &other_rid)
+        .unwrap());
+    assert_eq!(vec![2_u8; 100], page.get_tuple(&other_rid).unwrap().data);
+}
+
+#[test]
+pub fn test_serialize_deleted_slot() {
+    let mut page = TablePage::builder().page_id(0).build();
+    let first = RecordId::new(
+        0,
+        page.insert_tuple(TupleMetadata::new(false), Tuple::from(vec![1_u8; 50]))
+            .unwrap(),
+    );
+    let second = RecordId::new(
+        0,
+        page.insert_tuple(TupleMetadata::new(false), Tuple::from(vec![2_u8; 50]))
+            .unwrap(),
+    );
+    page.update_tuple_metadata(&TupleMetadata::deleted_payload_metadata(), &second)
+        .unwrap();
+
+    // The deleted slot survives a round trip, and isn't taken over by a new tuple, so the record
+    // id of the deleted tuple never refers to another one.
+    let mut page = TablePage::deserialize(&page.serialize());
+    assert!(page.get_tuple_metadata(&second).unwrap().is_deleted());
+    let third = RecordId::new(
+        0,
+        page.insert_tuple(TupleMetadata::new(false), Tuple::from(vec![3_u8; 50]))
+            .unwrap(),
+    );
+    assert_ne!(second, third);
+    assert!(page.get_tuple_metadata(&second).unwrap().is_deleted());
+    assert_eq!(vec![1_u8; 50], page.get_tuple(&first).unwrap().data);
+    assert_eq!(vec![3_u8; 50], page.get_tuple(&third).unwrap().data);
+}
+
+#[test]
+pub fn test_compact_reclaims_deleted_tuples() {
+    let mut page = TablePage::builder().page_id(0).build();
+    let rids: Vec<RecordId> = (0..4_u8)
+        .map(|i| {
+            let tuple = Tuple::from(vec![i; 1000]);
+            RecordId::new(
+                0,
+                page.insert_tuple(TupleMetadata::new(false), tuple).unwrap(),
+            )
+        })
+        .collect();
+    let too_large = Tuple::from(vec![9_u8; 1500]);
+    assert!(page.get_next_tuple_offset(&too_large).is_none());
+
+    // Deleting two tuples frees enough space, but not in one contiguous run.
+    page.update_tuple_metadata(&TupleMetadata::deleted_payload_metadata(), &rids[0])
+        .unwrap();
+    page.update_tuple_metadata(&TupleMetadata::deleted_payload_metadata(), &rids[2])
+        .unwrap();
+    assert!(page.get_next_tuple_offset(&too_large).is_none());
+    assert!(page.free_space() >= too_large.data.len());
+
+    // The tuple gets a new slot, while the deleted ones are kept as gravestones.
+    let slot = page
+        .insert_tuple(TupleMetadata::new(false), too_large.clone())
+        .unwrap();
+    assert_eq!(4, slot);
+    assert_eq!(too_large, page.get_tuple(&RecordId::new(0, slot)).unwrap());
+    assert!(page.get_tuple_metadata(&rids[0]).unwrap().is_deleted());
+    assert_eq!(vec![1_u8; 1000], page.get_tuple(&rids[1]).unwrap().data);
+    assert_eq!(vec![3_u8; 1000], page.get_tuple(&rids[3]).unwrap().data);
+    assert_eq!(3, page.tuple_count());
+    assert_eq!(2, page.deleted_tuple_count());
+}
@@ -1,10 +1,11 @@
 use crate::common::Result;
-use crate::storage::engine::Engine;
+use crate::storage::engine::{Engine, ScanBounds, ScanRange};
 use crate::storage::page::RecordId;
-use crate::storage::tuple::Tuple;
+use crate::storage::tuple::{Row, Tuple};
 use crate::storage::Key;
-use crate::types::Table;
+use crate::types::{SchemaChange, Table};
 use std::collections::VecDeque;
+use std::ops::Bound;
 use std::sync::{Arc, Mutex};
 
 /// A serial transactional key-value engine. It wraps an
@@ -59,6 +60,18 @@ impl<E: Engine> Transaction<E> {
         engine.delete_table(table_name)
     }
 
+    /// Deletes every row of a table, but keeps the table.
+    pub fn truncate_table(&self, table_name: &str) -> Result<()> {
+        let mut engine = self.engine.lock()?;
+        engine.truncate_table(table_name)
+    }
+
+    /// Changes the schema of a table.
+    pub fn alter_table(&self, table_name: &str, change: SchemaChange) -> Result<Table> {
+        let mut engine = self.engine.lock()?;
+        engine.alter_table(table_name, change)
+    }
+
     /// Fetches a table
     pub fn fetch_table(&self, table_name: &str) -> Result<Option<Table>> {
         let mut engine = self.engine.lock()?;
@@ -90,24 +103,47 @@ impl<E: Engine> Transaction<E> {
         engine.update(key, value)
     }
 
+    /// Fetches a key's value as a row of the table's schema.
+    pub fn get_row(&self, key: Key) -> Result<Row> {
+        let mut engine = self.engine.lock()?;
+        engine.get_row(key)
+    }
+
+    /// Validates a row against the table's schema and inserts it into the table with the given
+    /// `table_name`. Returns the record id corresponding to the inserted row.
+    pub fn insert_row(&self, table_name: &str, row: Row) -> Result<RecordId> {
+        let mut engine = self.engine.lock()?;
+        engine.insert_row(table_name, row)
+    }
+
+    /// Validates a row against the table's schema and replaces the key's value with it.
+    pub fn update_row(&self, key: Key, row: Row) -> Result<()> {
+        let mut engine = self.engine.lock()?;
+        engine.update_row(key, row)
+    }
+
     /// Returns an iterator over the key/value items of the table.
     pub fn scan(&self, table: &str) -> ScanIterator<E> {
-        ScanIterator::new(Arc::clone(&self.engine), table)
+        self.scan_range(table, ScanRange::all())
+    }
+
+    /// Returns an iterator over the key/value items of the table in a range of record ids or
+    /// primary keys, see [`Engine::scan_range`].
+    pub fn scan_range(&self, table: &str, range: ScanRange) -> ScanIterator<E> {
+        ScanIterator::new(Arc::clone(&self.engine), table, range)
     }
 }
 
-// todo(eyoon): buffer the scaniterator
-//
-// /// An iterator over the latest live and visible key/value pairs for the txn.
-// ///
-// /// The (single-threaded) engine is protected by a mutex, and holding the mutex
-// /// for the duration of the iteration can cause deadlocks (e.g. when the local
-// /// SQL engine pulls from two tables concurrently during a join). Instead, we
-// /// pull and buffer a batch of rows at a time, and release the mutex in between.
-// ///
-// /// This does not implement DoubleEndedIterator (reverse scans), since the SQL
-// /// layer doesn't currently need it.
-// #[allow(clippy::type_complexity)]
+/// An iterator over the latest live and visible key/value pairs for the txn.
+///
+/// The (single-threaded) engine is protected by a mutex, and holding the mutex
+/// for the duration of the iteration can cause deadlocks (e.g. when the local
+/// SQL engine pulls from two tables concurrently during a join). Instead, we
+/// pull and buffer a batch of rows at a time, and release the mutex in between.
+/// Each batch resumes the scan right after the last row of the previous one.
+///
+/// This does not implement DoubleEndedIterator, since the SQL layer doesn't
+/// currently need it. Reverse scans are done with [`ScanRange::reverse`].
 pub struct ScanIterator<E: Engine> {
     /// The engine.
     engine: Arc<Mutex<E>>,
@@ -115,8 +151,8 @@ pub struct ScanIterator<E: Engine> {
     buffer: VecDeque<(RecordId, Tuple)>,
     /// The name of the table this iterates over
     table: String,
-    /// The position of the current tuple in the iterator
-    i: usize,
+    /// The rows of the table that haven't been buffered yet, and their order
+    remainder: Option<ScanRange>,
 }
 
 /// Implement Clone manually. Deriving it requires Engine: Clone.
@@ -126,52 +162,80 @@ impl<E: Engine> Clone for ScanIterator<E> {
             engine: self.engine.clone(),
             buffer: self.buffer.clone(),
             table: self.table.clone(),
-            i: self.i,
+            remainder: self.remainder.clone(),
         }
     }
 }
-//
+
 impl<E: Engine> ScanIterator<E> {
     /// The number of live keys to pull from the engine at a time.
     #[cfg(not(test))]
     const BUFFER_SIZE: usize = 1000;
-    /// Pull only 2 keys in tests, to exercise this more often.
+    /// Pull only 4 keys in tests, to exercise this more often.
     #[cfg(test)]
     const BUFFER_SIZE: usize = 4;
 
     /// Creates a new scan iterator.
-    fn new(engine: Arc<Mutex<E>>, table: &str) -> Self {
+    fn new(engine: Arc<Mutex<E>>, table: &str, range: ScanRange) -> Self {
         let buffer = VecDeque::with_capacity(Self::BUFFER_SIZE);
         Self {
             engine,
             buffer,
             table: table.to_string(),
-            i: 0,
+            remainder: Some(range),
         }
     }
 
-    /// Fills the buffer, if there's any pending items.
+    /// Fills the buffer with the next batch of rows, if there's any pending items.
     fn fill_buffer(&mut self) -> Result<()> {
-        // Check if there's anything to buffer.
-        if self.buffer.len() >= Self::BUFFER_SIZE {
+        let Some(range) = self.remainder.take() else {
             return Ok(());
-        }
+        };
 
         let mut engine = self.engine.lock()?;
-        let mut iter = engine.scan(&self.table).peekable();
-        // Iterator is exhausted; no more tuples to insert into the buffer.
-        if iter.peek().into_iter().skip(self.i).next().is_none() {
-            return Ok(());
+        for item in engine
+            .scan_range(&self.table, range.clone())?
+            .take(Self::BUFFER_SIZE)
+        {
+            self.buffer.push_back(item?);
         }
-        // Skip to the current
-        while let Some((rid, tuple)) = iter.next().transpose()? {
-            self.buffer.push_back((rid, tuple));
-            self.i += 1;
+        // A short batch means the scan is exhausted. Otherwise, continue after the last row.
+        if self.buffer.len() < Self::BUFFER_SIZE {
+            return Ok(());
         }
+        let Some((record_id, tuple)) = self.buffer.back() else {
+            return Ok(());
+        };
+        let bounds = match range.bounds {
+            ScanBounds::RecordIds(start, end) => {
+                let (start, end) = resume(start, end, record_id.clone(), range.reverse);
+                ScanBounds::RecordIds(start, end)
+            }
+            ScanBounds::PrimaryKeys(start, end) => {
+                let table = engine.must_get_table(&self.table)?;
+                let row = Row::from_tuple(tuple.clone(), &table)?;
+                let key = table
+                    .primary_key()
+                    .iter()
+                    .map(|&index| row.get_field(index))
+                    .collect::<Result<Vec<_>>>()?;
+                let (start, end) = resume(start, end, key, range.reverse);
+                ScanBounds::PrimaryKeys(start, end)
+            }
+        };
+        self.remainder = Some(ScanRange { bounds, ..range });
         Ok(())
     }
 }
 
+/// Narrows the bounds of a scan to the rows after `last`, in the scan's order.
+fn resume<T>(start: Bound<T>, end: Bound<T>, last: T, reverse: bool) -> (Bound<T>, Bound<T>) {
+    match reverse {
+        false => (Bound::Excluded(last), end),
+        true => (start, Bound::Excluded(last)),
+    }
+}
+
 impl<E: Engine> Iterator for ScanIterator<E> {
     type Item = Result<(RecordId, Tuple)>;
     fn next(&mut self) -> Option<Self::Item> {
@@ -183,3 +247,66 @@ impl<E: Engine> Iterator for ScanIterator<E> {
         self.buffer.pop_front().map(Ok)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::Memory;
+    use crate::types::field::Field;
+    use crate::types::DataType;
+
+    fn people() -> Table {
+        Table::builder()
+            .name("people")
+            .column("id", DataType::Int, false, None, None)
+            .primary_key(&["id"])
+            .build()
+    }
+
+    fn person(id: i32) -> Row {
+        Row::from(vec![Field::from(id)])
+    }
+
+    fn rows(scan: ScanIterator<Memory>) -> Vec<Row> {
+        scan.map(|item| Row::from_tuple(item?.1, &people()))
+            .collect::<Result<_>>()
+            .unwrap()
+    }
+
+    #[test]
+    fn test_scan_batches() {
+        let simple = Simple::new(Memory::new());
+        let txn = simple.begin().unwrap();
+        txn.create_table(people()).unwrap();
+        // Insert the keys in descending order, so that record id and key order differ.
+        for id in (0..10).rev() {
+            txn.insert_row("people", person(id)).unwrap();
+        }
+
+        let mut scan = txn.scan("people");
+        assert_eq!(
+            scan.next().unwrap().unwrap().1,
+            person(9).to_tuple(&people()).unwrap()
+        );
+        // The engine isn't locked between batches, so it can be used while scanning.
+        txn.insert_row("people", person(10)).unwrap();
+        let expected: Vec<Row> = (0..9).rev().chain([10]).map(person).collect();
+        assert_eq!(rows(scan), expected);
+
+        let expected: Vec<Row> = [10].into_iter().chain(0..10).map(person).collect();
+        assert_eq!(
+            rows(txn.scan_range("people", ScanRange::all().reverse())),
+            expected
+        );
+        let keys = vec![Field::from(2)]..=vec![Field::from(8)];
+        assert_eq!(
+            rows(txn.scan_range("people", ScanRange::primary_keys(keys.clone()))),
+            (2..=8).map(person).collect::<Vec<_>>()
+        );
+        assert_eq!(
+            rows(txn.scan_range("people", ScanRange::primary_keys(keys).reverse())),
+            (2..=8).rev().map(person).collect::<Vec<_>>()
+        );
+        assert!(txn.scan("pets").next().unwrap().is_err());
+    }
+}
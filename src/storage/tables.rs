@@ -1,35 +1,98 @@
+use crate::common::constants::COULD_NOT_UNWRAP_BPM_MSG;
 use crate::common::{Error, Result};
+use crate::config::config::RUSTY_DB_PAGE_SIZE_BYTES;
+use crate::errinput;
 use crate::storage::buffer::buffer_pool_manager::BufferPoolManager;
-use crate::storage::engine::Status;
+use crate::storage::catalog::SystemCatalog;
+use crate::storage::engine::{
+    encode_key_range, is_empty_range, ScanBounds, ScanRange, Status, TableStatus,
+};
 use crate::storage::heap::{TableHeap, TableHeapIterator};
 use crate::storage::page::RecordId;
-use crate::storage::tuple::Tuple;
+use crate::storage::tuple::{Row, Tuple};
 use crate::storage::{engine, Engine, Key};
-use crate::types::Table;
+use crate::types::field::Field;
+use crate::types::{SchemaChange, Table};
 use std::collections::{BTreeMap, HashMap};
+use std::iter::Peekable;
+use std::ops::{Bound, RangeBounds};
 use std::sync::{Arc, RwLock};
 
 pub struct HeapTableManager {
     heaps: HashMap<String, TableHeap>,
     bpm: Arc<RwLock<BufferPoolManager>>,
     key_directory: KeyDirectory,
+    catalog: SystemCatalog,
 }
 
 impl HeapTableManager {
-    pub fn new(bpm: &Arc<RwLock<BufferPoolManager>>) -> Self {
-        Self {
-            heaps: HashMap::new(),
-            bpm: Arc::clone(bpm),
-            key_directory: HashMap::new(),
+    /// Opens the tables of the database that `bpm` reads from, as recorded in its system catalog.
+    /// A database without a catalog is given an empty one.
+    pub fn new(bpm: &Arc<RwLock<BufferPoolManager>>) -> Result<Self> {
+        let catalog = SystemCatalog::open(bpm)?;
+        let mut heaps = HashMap::new();
+        let mut key_directory = HashMap::new();
+        for entry in catalog.load()? {
+            let name = entry.table.name().to_string();
+            let heap = TableHeap::open(entry.table, entry.first_page_id, entry.fsm_page_id, bpm)?;
+            let mut keys = BTreeMap::new();
+            for row in heap.iter() {
+                let (rid, tuple) = row?;
+                if let Some(key) = primary_key(&heap.schema, &tuple)? {
+                    keys.insert(key, rid);
+                }
+            }
+            heaps.insert(name.clone(), heap);
+            key_directory.insert(name, keys);
         }
+        Ok(Self {
+            heaps,
+            bpm: Arc::clone(bpm),
+            key_directory,
+            catalog,
+        })
     }
 }
 
 /// Maps table name -> [ Map: bytestream key -> RecordId ]
+///
+/// The keys are the encoded primary keys of the table's rows, see [`Table::primary_key_of`], and
+/// are empty for tables without a primary key.
 pub type KeyDirectory = HashMap<String, BTreeMap<Vec<u8>, RecordId>>;
 
+/// Returns the encoded primary key of a tuple of the table, if the table has a primary key.
+pub(super) fn primary_key(schema: &Table, tuple: &Tuple) -> Result<Option<Vec<u8>>> {
+    if schema.primary_key().is_empty() {
+        return Ok(None);
+    }
+    let row = Row::from_tuple(tuple.clone(), schema)?;
+    schema.primary_key_of(&row.iter().cloned().collect::<Vec<_>>())
+}
+
+/// Fails if the table already has a row with the given primary key.
+pub(super) fn check_unique<V>(
+    keys: &BTreeMap<Vec<u8>, V>,
+    schema: &Table,
+    key: &[u8],
+) -> Result<()> {
+    if keys.contains_key(key) {
+        let columns = schema
+            .primary_key()
+            .iter()
+            .map(|&index| schema.get_column_name(index))
+            .collect::<Vec<_>>()
+            .join(", ");
+        return errinput!(
+            "duplicate primary key ({columns}) in table {}",
+            schema.name()
+        );
+    }
+    Ok(())
+}
+
 impl Engine for HeapTableManager {
-    type ScanIterator<'a> = ScanIterator<'a>
+    type ScanIterator<'a>
+        = ScanIterator<'a>
     where
         Self: Sized + 'a;
 
@@ -39,10 +102,12 @@ impl Engine for HeapTableManager {
                 "Attempted to insert table that already exists!".to_string(),
             ));
         }
-        self.key_directory
-            .insert(table.name().to_string(), BTreeMap::new());
-        self.heaps
-            .insert(table.name().to_string(), TableHeap::new(table, &self.bpm));
+        let heap = TableHeap::new(table, &self.bpm);
+        self.catalog
+            .create_table(&heap.schema, heap.first_page_id, heap.fsm_page_id())?;
+        let name = heap.schema.name().to_string();
+        self.key_directory.insert(name.clone(), BTreeMap::new());
+        self.heaps.insert(name, heap);
         Ok(())
     }
 
@@ -50,11 +115,26 @@ impl Engine for HeapTableManager {
         if !self.key_directory.contains_key(table_name) {
             return Ok(false);
         }
+        self.catalog.delete_table(table_name)?;
         self.key_directory.remove(table_name);
-        self.heaps.remove(table_name);
+        if let Some(heap) = self.heaps.remove(table_name) {
+            heap.delete()?;
+        }
         Ok(true)
     }
 
+    fn truncate_table(&mut self, table_name: &str) -> Result<()> {
+        let heap = self
+            .heaps
+            .get_mut(table_name)
+            .ok_or_else(|| Error::InvalidInput(format!("table {table_name} does not exist")))?;
+        heap.truncate()?;
+        if let Some(keys) = self.key_directory.get_mut(table_name) {
+            keys.clear();
+        }
+        Ok(())
+    }
+
     fn get_table(&mut self, table_name: &str) -> Result<Option<Table>> {
         match self.heaps.get(table_name) {
             Some(heap) => Ok(Some(heap.schema())),
@@ -62,12 +142,54 @@ impl Engine for HeapTableManager {
         }
     }
 
+    fn alter_table(&mut self, table_name: &str, change: SchemaChange) -> Result<Table> {
+        if let SchemaChange::RenameTable(new_name) = &change {
+            if self.heaps.contains_key(new_name) {
+                return errinput!("table {new_name} already exists");
+            }
+        }
+        // The heap's schema is only replaced once the catalog has been updated.
+        let mut table = self
+            .heaps
+            .get(table_name)
+            .ok_or_else(|| Error::InvalidInput(format!("table {table_name} does not exist")))?
+            .schema();
+        table.alter(change)?;
+        self.catalog.update_table(table_name, &table)?;
+        self.heaps
+            .get_mut(table_name)
+            .expect("the table exists")
+            .schema = table.clone();
+
+        if table.name() != table_name {
+            let heap = self.heaps.remove(table_name).expect("the table exists");
+            self.heaps.insert(table.name().to_string(), heap);
+            if let Some(keys) = self.key_directory.remove(table_name) {
+                self.key_directory.insert(table.name().to_string(), keys);
+            }
+        }
+        Ok(table)
+    }
+
     fn delete(&mut self, key: Key) -> Result<()> {
         let heap = self
             .heaps
             .get_mut(key.table_name)
             .ok_or_else(|| Error::InvalidData(key.table_name.to_string()))?;
-        heap.delete_tuple(key.record_id)
+        if heap.is_deleted(key.record_id)? {
+            return Ok(());
+        }
+        let primary_key = match heap.schema.primary_key().is_empty() {
+            true => None,
+            false => primary_key(&heap.schema, &heap.get_tuple(key.record_id)?)?,
+        };
+        heap.delete_tuple(key.record_id)?;
+        if let (Some(primary_key), Some(keys)) =
+            (primary_key, self.key_directory.get_mut(key.table_name))
+        {
+            keys.remove(&primary_key);
+        }
+        Ok(())
     }
 
     fn get(&mut self, key: Key) -> Result<Tuple> {
@@ -83,7 +205,19 @@ impl Engine for HeapTableManager {
             .heaps
             .get_mut(table_name)
             .ok_or_else(|| Error::InvalidData(table_name.to_string()))?;
-        heap.insert_tuple(value)
+        let keys = self
+            .key_directory
+            .entry(table_name.to_string())
+            .or_default();
+        let primary_key = primary_key(&heap.schema, &value)?;
+        if let Some(primary_key) = &primary_key {
+            check_unique(keys, &heap.schema, primary_key)?;
+        }
+        let rid = heap.insert_tuple(value)?;
+        if let Some(primary_key) = primary_key {
+            keys.insert(primary_key, rid.clone());
+        }
+        Ok(rid)
     }
 
     fn scan(&mut self, table_name: &str) -> Self::ScanIterator<'_>
@@ -97,8 +231,73 @@ impl Engine for HeapTableManager {
         ScanIterator { inner: heap.iter() }
     }
 
-    fn scan_dyn(&mut self) -> Box<dyn engine::ScanIterator + '_> {
-        todo!()
+    fn scan_dyn(&mut self, table_name: &str) -> Result<Box<dyn engine::ScanIterator + '_>> {
+        if !self.heaps.contains_key(table_name) {
+            return errinput!("table {table_name} does not exist");
+        }
+        Ok(Box::new(self.scan(table_name)))
+    }
+
+    fn scan_range(
+        &mut self,
+        table_name: &str,
+        range: ScanRange,
+    ) -> Result<Box<dyn engine::ScanIterator + '_>> {
+        let heap = self
+            .heaps
+            .get(table_name)
+            .ok_or_else(|| Error::InvalidInput(format!("table {table_name} does not exist")))?;
+        match range.bounds {
+            ScanBounds::RecordIds(start, end) => {
+                let record_ids = (start, end);
+                if is_empty_range(&record_ids) {
+                    return Ok(Box::new(std::iter::empty()));
+                }
+                // Only the pages the range covers are read, in the order of their ids.
+                let pages = (
+                    record_ids.0.as_ref().map(RecordId::page_id),
+                    record_ids.1.as_ref().map(RecordId::page_id),
+                );
+                let mut page_ids = heap.page_ids();
+                page_ids.retain(|page_id| match (&pages.0, &pages.1) {
+                    (Bound::Included(start) | Bound::Excluded(start), _) if page_id < start => {
+                        false
+                    }
+                    (_, Bound::Included(end) | Bound::Excluded(end)) => page_id <= end,
+                    _ => true,
+                });
+                page_ids.sort_unstable();
+                if range.reverse {
+                    page_ids.reverse();
+                }
+                let rows = heap.iter_pages(page_ids).filter(move |row| match row {
+                    Ok((record_id, _)) => record_ids.contains(record_id),
+                    Err(_) => true,
+                });
+                Ok(match range.reverse {
+                    true => Box::new(ReversePages::new(rows)),
+                    false => Box::new(rows),
+                })
+            }
+            ScanBounds::PrimaryKeys(start, end) => {
+                let keys = encode_key_range(&heap.schema, &start, &end)?;
+                if is_empty_range(&keys) {
+                    return Ok(Box::new(std::iter::empty()));
+                }
+                let Some(directory) = self.key_directory.get(table_name) else {
+                    return Ok(Box::new(std::iter::empty()));
+                };
+                let record_ids = directory.range(keys).map(|(_, record_id)| record_id);
+                let rows = |record_id: &RecordId| {
+                    let tuple = heap.get_tuple(record_id)?;
+                    Ok((record_id.clone(), tuple))
+                };
+                Ok(match range.reverse {
+                    true => Box::new(record_ids.rev().map(rows)),
+                    false => Box::new(record_ids.map(rows)),
+                })
+            }
+        }
     }
 
     fn update(&mut self, key: Key, value: Tuple) -> Result<()> {
@@ -106,11 +305,64 @@ impl Engine for HeapTableManager {
             .heaps
             .get_mut(key.table_name)
             .ok_or_else(|| Error::InvalidData(key.table_name.to_string()))?;
-        heap.update_tuple(key.record_id, value)
+        let Some(new_key) = primary_key(&heap.schema, &value)? else {
+            return heap.update_tuple(key.record_id, value);
+        };
+        let old_key = primary_key(&heap.schema, &heap.get_tuple(key.record_id)?)?;
+        let keys = self
+            .key_directory
+            .entry(key.table_name.to_string())
+            .or_default();
+        if old_key.as_ref() != Some(&new_key) {
+            check_unique(keys, &heap.schema, &new_key)?;
+        }
+        heap.update_tuple(key.record_id, value)?;
+        if let Some(old_key) = old_key {
+            keys.remove(&old_key);
+        }
+        keys.insert(new_key, key.record_id.clone());
+        Ok(())
+    }
+
+    fn lookup(&mut self, table_name: &str, key: &[Field]) -> Result<Option<RecordId>> {
+        let heap = self
+            .heaps
+            .get(table_name)
+            .ok_or_else(|| Error::InvalidInput(format!("table {table_name} does not exist")))?;
+        let key = heap.schema.encode_primary_key(key)?;
+        Ok(self
+            .key_directory
+            .get(table_name)
+            .and_then(|keys| keys.get(&key))
+            .cloned())
     }
 
     fn status(&mut self) -> Result<Status> {
-        todo!()
+        let mut tables = BTreeMap::new();
+        for (name, heap) in &self.heaps {
+            let mut table = TableStatus {
+                pages: heap.num_pages() as u64,
+                ..TableStatus::default()
+            };
+            for row in heap.iter() {
+                let (_, tuple) = row?;
+                table.keys += 1;
+                table.size += tuple.data.len() as u64;
+            }
+            tables.insert(name.clone(), table);
+        }
+
+        let bpm = self.bpm.read().expect(COULD_NOT_UNWRAP_BPM_MSG);
+        let disk_size = bpm.disk_manager.read()?.size();
+        Ok(Status {
+            name: "heap".to_string(),
+            keys: tables.values().map(|table| table.keys).sum(),
+            size: tables.values().map(|table| table.size).sum(),
+            tables,
+            pages: disk_size / RUSTY_DB_PAGE_SIZE_BYTES as u64,
+            disk_size,
+            buffer_pool: Some(bpm.status()),
+        })
     }
 }
 
@@ -122,6 +374,620 @@ impl Iterator for ScanIterator<'_> {
     type Item = Result<(RecordId, Tuple)>;
 
     fn next(&mut self) -> Option<Self::Item> {
-        self.inner.next().map(Ok)
+        self.inner.next()
+    }
+}
+
+/// Reverses the rows of a scan over pages given in descending order, by buffering the rows of
+/// one page at a time. Errors are passed through once the rows buffered before them are returned.
+struct ReversePages<I: Iterator<Item = Result<(RecordId, Tuple)>>> {
+    inner: Peekable<I>,
+    page: Vec<(RecordId, Tuple)>,
+}
+
+impl<I: Iterator<Item = Result<(RecordId, Tuple)>>> ReversePages<I> {
+    fn new(inner: I) -> Self {
+        Self {
+            inner: inner.peekable(),
+            page: Vec::new(),
+        }
+    }
+}
+
+impl<I: Iterator<Item = Result<(RecordId, Tuple)>>> Iterator for ReversePages<I> {
+    type Item = Result<(RecordId, Tuple)>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.page.is_empty() {
+            let first = match self.inner.next()? {
+                Ok(row) => row,
+                Err(err) => return Some(Err(err)),
+            };
+            let page_id = first.0.page_id();
+            self.page.push(first);
+            while let Some(Ok(row)) = self
+                .inner
+                .next_if(|row| matches!(row, Ok((rid, _)) if rid.page_id() == page_id))
+            {
+                self.page.push(row);
+            }
+        }
+        self.page.pop().map(Ok)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::catalog::CATALOG_ROOT_PAGE_ID;
+    use crate::storage::disk::disk_manager::DiskManager;
+    use crate::storage::page::Page;
+    use crate::storage::tuple::{Row, TupleMetadata};
+    use crate::types::field::Field;
+    use crate::types::{Column, DataType};
+
+    crate::storage::engine::tests::test_engine!({
+        let disk_manager = DiskManager::new_with_handle_for_test();
+        let bpm = Arc::new(RwLock::new(BufferPoolManager::new(50, 5, disk_manager)));
+        HeapTableManager::new(&bpm).unwrap()
+    });
+
+    fn create_engine() -> HeapTableManager {
+        let disk_manager = DiskManager::new_with_handle_for_test();
+        let bpm = Arc::new(RwLock::new(BufferPoolManager::new(50, 5, disk_manager)));
+        let mut engine = HeapTableManager::new(&bpm).unwrap();
+        let table = Table::builder()
+            .name("people")
+            .column("id", DataType::Int, false, None, None)
+            .column("name", DataType::Text, false, None, Some(5))
+            .column("score", DataType::Float, true, None, None)
+            .build();
+        engine.create_table(table).unwrap();
+        engine
+    }
+
+    fn assert_invalid_column(result: Result<impl std::fmt::Debug>, column: &str) {
+        match result {
+            Err(Error::InvalidInput(msg)) => assert!(msg.contains(column), "{msg}"),
+            result => panic!("expected invalid input for column {column}, got {result:?}"),
+        }
+    }
+
+    #[test]
+    fn test_insert_and_update_row() {
+        let mut engine = create_engine();
+        let rid = engine
+            .insert_row(
+                "people",
+                Row::from(vec![Field::from(1), Field::from("ann")]),
+            )
+            .unwrap();
+        let expected = Row::from(vec![Field::from(1), Field::from("ann"), Field::Null]);
+        assert_eq!(expected, engine.get_row(Key::new("people", &rid)).unwrap());
+
+        let updated = Row::from(vec![Field::from(1), Field::from("anna"), Field::from(2.5)]);
+        engine
+            .update_row(Key::new("people", &rid), updated.clone())
+            .unwrap();
+        assert_eq!(updated, engine.get_row(Key::new("people", &rid)).unwrap());
+    }
+
+    #[test]
+    fn test_status_and_scan_dyn() {
+        let mut engine = create_engine();
+        engine.create_table(Table::from(DataType::Int)).unwrap();
+        let mut sizes = Vec::new();
+        for (id, name) in [(1, "ann"), (2, "bob"), (3, "carol")] {
+            let row = Row::from(vec![Field::from(id), Field::from(name)]);
+            let rid = engine.insert_row("people", row).unwrap();
+            sizes.push(engine.get(Key::new("people", &rid)).unwrap().data.len() as u64);
+        }
+        let bob = engine
+            .scan_dyn("people")
+            .unwrap()
+            .nth(1)
+            .unwrap()
+            .unwrap()
+            .0;
+        engine.delete(Key::new("people", &bob)).unwrap();
+
+        let status = engine.status().unwrap();
+        let people = TableStatus {
+            keys: 2,
+            size: sizes[0] + sizes[2],
+            pages: 1,
+        };
+        assert_eq!(status.name, "heap");
+        assert_eq!(status.tables["people"], people);
+        assert_eq!(
+            status.tables[""],
+            TableStatus {
+                pages: 1,
+                ..TableStatus::default()
+            }
+        );
+        assert_eq!((status.keys, status.size), (people.keys, people.size));
+        assert!(status.pages >= 6);
+        assert_eq!(
+            status.disk_size,
+            status.pages * RUSTY_DB_PAGE_SIZE_BYTES as u64
+        );
+
+        let buffer_pool = status.buffer_pool.unwrap();
+        assert_eq!(buffer_pool.frames, 50);
+        assert!(buffer_pool.cached_pages >= buffer_pool.pinned_pages);
+        assert!(buffer_pool.dirty_pages > 0);
+
+        let scanned: Vec<_> = engine
+            .scan_dyn("people")
+            .unwrap()
+            .map(|r| r.unwrap().0)
+            .collect();
+        assert_eq!(scanned.len(), 2);
+        assert!(!scanned.contains(&bob));
+        assert!(matches!(
+            engine.scan_dyn("pets"),
+            Err(Error::InvalidInput(_))
+        ));
+    }
+
+    #[test]
+    fn test_status_after_inserts_and_deletes() {
+        let mut engine = create_engine();
+        let row = |id: i32| Row::from(vec![Field::from(id), Field::from("ann")]);
+        let mut rids = Vec::new();
+        for id in 0..1000 {
+            rids.push(engine.insert_row("people", row(id)).unwrap());
+        }
+        let size = engine.get(Key::new("people", &rids[0])).unwrap().data.len() as u64;
+        let status = engine.status().unwrap();
+        let pages = status.tables["people"].pages;
+        assert!(pages > 1);
+        assert_eq!(
+            status.tables["people"],
+            TableStatus {
+                keys: 1000,
+                size: 1000 * size,
+                pages,
+            }
+        );
+
+        // Deletes leave the pages in place, and only change the key and size counts.
+        for rid in rids.iter().step_by(2) {
+            engine.delete(Key::new("people", rid)).unwrap();
+        }
+        let status = engine.status().unwrap();
+        assert_eq!(
+            status.tables["people"],
+            TableStatus {
+                keys: 500,
+                size: 500 * size,
+                pages,
+            }
+        );
+        assert_eq!((status.keys, status.size), (500, 500 * size));
+
+        engine.truncate_table("people").unwrap();
+        let status = engine.status().unwrap();
+        assert_eq!(
+            status.tables["people"],
+            TableStatus {
+                pages: 1,
+                ..TableStatus::default()
+            }
+        );
+    }
+
+    #[test]
+    fn test_scan_range_across_pages() {
+        let mut engine = create_engine();
+        for id in 0..1000 {
+            let row = Row::from(vec![Field::from(id), Field::from("ann")]);
+            engine.insert_row("people", row).unwrap();
+        }
+        assert!(engine.status().unwrap().tables["people"].pages > 2);
+        let all: Vec<RecordId> = engine.scan("people").map(|item| item.unwrap().0).collect();
+        let (start, end) = (all[100].clone(), all[900].clone());
+        assert_ne!(start.page_id(), end.page_id());
+
+        let mut scan = |range: ScanRange| -> Vec<RecordId> {
+            engine
+                .scan_range("people", range)
+                .unwrap()
+                .map(|item| item.unwrap().0)
+                .collect()
+        };
+        assert_eq!(
+            scan(ScanRange::record_ids(start.clone()..end.clone())),
+            all[100..900]
+        );
+        let mut reversed = all[101..=900].to_vec();
+        reversed.reverse();
+        assert_eq!(
+            scan(ScanRange::record_ids((Bound::Excluded(start), Bound::Included(end))).reverse()),
+            reversed
+        );
+        let mut reversed = all.clone();
+        reversed.reverse();
+        assert_eq!(scan(ScanRange::all().reverse()), reversed);
+    }
+
+    #[test]
+    fn test_insert_row_rejects_invalid_rows() {
+        let mut engine = create_engine();
+        let null_id = Row::from(vec![Field::Null, Field::from("ann")]);
+        assert_invalid_column(engine.insert_row("people", null_id), "id");
+
+        let wrong_type = Row::from(vec![Field::from(1), Field::from(2)]);
+        assert_invalid_column(engine.insert_row("people", wrong_type), "name");
+
+        let long_name = Row::from(vec![Field::from(1), Field::from("annabel")]);
+        assert_invalid_column(engine.insert_row("people", long_name), "name");
+
+        let missing_table = Row::from(vec![Field::from(1)]);
+        assert_invalid_column(engine.insert_row("pets", missing_table), "pets");
+
+        assert_eq!(0, engine.scan("people").count());
+    }
+
+    #[test]
+    fn test_alter_table() {
+        let mut engine = create_engine();
+        let ann = engine
+            .insert_row(
+                "people",
+                Row::from(vec![Field::from(1), Field::from("ann")]),
+            )
+            .unwrap();
+
+        // Rows written before a column is added read back with its default.
+        let active = Column::new(
+            "active",
+            DataType::Bool,
+            false,
+            Some(Field::from(true)),
+            None,
+        );
+        let table = engine.add_column("people", active).unwrap();
+        assert_eq!(table.version(), 1);
+        let bob = engine
+            .insert_row(
+                "people",
+                Row::from(vec![
+                    Field::from(2),
+                    Field::from("bob"),
+                    Field::from(1.5),
+                    Field::from(false),
+                ]),
+            )
+            .unwrap();
+        let expected = Row::from(vec![
+            Field::from(1),
+            Field::from("ann"),
+            Field::Null,
+            Field::from(true),
+        ]);
+        assert_eq!(expected, engine.get_row(Key::new("people", &ann)).unwrap());
+
+        // Dropping a column removes it from rows of every version.
+        engine.drop_column("people", "score").unwrap();
+        engine
+            .rename_column("people", "name", "first_name")
+            .unwrap();
+        let table = engine.rename_table("people", "persons").unwrap();
+        assert_eq!(table.name(), "persons");
+        assert_eq!(table.get_column_name(1), "first_name");
+        assert_eq!(table.version(), 4);
+        assert!(engine.get_table("people").unwrap().is_none());
+
+        let rows: Vec<Row> = engine
+            .scan("persons")
+            .map(|item| Row::from_tuple(item.unwrap().1, &table).unwrap())
+            .collect();
+        assert_eq!(
+            rows,
+            vec![
+                Row::from(vec![Field::from(1), Field::from("ann"), Field::from(true)]),
+                Row::from(vec![Field::from(2), Field::from("bob"), Field::from(false)]),
+            ]
+        );
+
+        // Rows are upgraded when they're rewritten.
+        let anna = Row::from(vec![
+            Field::from(1),
+            Field::from("anna"),
+            Field::from(false),
+        ]);
+        engine
+            .update_row(Key::new("persons", &ann), anna.clone())
+            .unwrap();
+        assert_eq!(anna, engine.get_row(Key::new("persons", &ann)).unwrap());
+        let stored = engine.get(Key::new("persons", &bob)).unwrap();
+        assert_eq!(stored.data[..2], 1_u16.to_be_bytes());
+        let stored = engine.get(Key::new("persons", &ann)).unwrap();
+        assert_eq!(stored.data[..2], 4_u16.to_be_bytes());
+    }
+
+    #[test]
+    fn test_catalog_survives_restart() {
+        let disk_manager = DiskManager::new_with_handle_for_test();
+        let reopen = || {
+            let bpm = Arc::new(RwLock::new(BufferPoolManager::new(
+                50,
+                5,
+                Arc::clone(&disk_manager),
+            )));
+            (HeapTableManager::new(&bpm).unwrap(), bpm)
+        };
+
+        let (mut engine, bpm) = reopen();
+        let table = Table::builder()
+            .name("people")
+            .column("id", DataType::Int, false, None, None)
+            .column("name", DataType::Text, false, None, Some(5))
+            .build();
+        engine.create_table(table).unwrap();
+        engine.create_table(Table::from(DataType::Int)).unwrap();
+        let rid = engine
+            .insert_row(
+                "people",
+                Row::from(vec![Field::from(1), Field::from("ann")]),
+            )
+            .unwrap();
+        let nickname = Column::new("nickname", DataType::Text, true, None, Some(5));
+        let expected = engine.add_column("people", nickname).unwrap();
+        engine.delete_table("").unwrap();
+        bpm.write().unwrap().flush_all_pages();
+        drop(engine);
+
+        let (mut engine, bpm) = reopen();
+        assert_eq!(engine.get_table("people").unwrap(), Some(expected));
+        assert_eq!(engine.get_table("").unwrap(), None);
+        assert_eq!(
+            Row::from(vec![Field::from(1), Field::from("ann"), Field::Null]),
+            engine.get_row(Key::new("people", &rid)).unwrap()
+        );
+        engine
+            .insert_row(
+                "people",
+                Row::from(vec![Field::from(2), Field::from("bob")]),
+            )
+            .unwrap();
+        engine.rename_table("people", "persons").unwrap();
+        bpm.write().unwrap().flush_all_pages();
+        drop(engine);
+
+        let (mut engine, _) = reopen();
+        assert_eq!(engine.get_table("people").unwrap(), None);
+        assert_eq!(engine.scan("persons").count(), 2);
+    }
+
+    #[test]
+    fn test_catalog_survives_alter_and_restart() {
+        let disk_manager = DiskManager::new_with_handle_for_test();
+        let reopen = || {
+            let bpm = Arc::new(RwLock::new(BufferPoolManager::new(
+                50,
+                5,
+                Arc::clone(&disk_manager),
+            )));
+            (HeapTableManager::new(&bpm).unwrap(), bpm)
+        };
+
+        let (mut engine, bpm) = reopen();
+        let table = Table::builder()
+            .name("people")
+            .column("id", DataType::Int, false, None, None)
+            .column("age", DataType::Int, true, None, None)
+            .column("name", DataType::Text, false, None, Some(5))
+            .primary_key(&["name"])
+            .build();
+        engine.create_table(table).unwrap();
+        engine
+            .insert_row(
+                "people",
+                Row::from(vec![Field::from(1), Field::from(30), Field::from("ann")]),
+            )
+            .unwrap();
+        engine.drop_column("people", "age").unwrap();
+        let expected = engine.rename_table("people", "persons").unwrap();
+        assert_eq!(expected.primary_key(), [1]);
+        bpm.write().unwrap().flush_all_pages();
+        drop(engine);
+
+        let (mut engine, _) = reopen();
+        assert_eq!(engine.get_table("people").unwrap(), None);
+        assert_eq!(engine.get_table("persons").unwrap(), Some(expected));
+        assert_eq!(
+            engine.get_by_key("persons", &[Field::from("ann")]).unwrap(),
+            Some(Row::from(vec![Field::from(1), Field::from("ann")]))
+        );
+        assert!(matches!(
+            engine.insert_row(
+                "persons",
+                Row::from(vec![Field::from(2), Field::from("ann")])
+            ),
+            Err(Error::InvalidInput(_))
+        ));
+    }
+
+    #[test]
+    fn test_corrupt_catalog_root() {
+        let write_root = |data: Vec<u8>| {
+            let disk_manager = DiskManager::new_with_handle_for_test();
+            let bpm = Arc::new(RwLock::new(BufferPoolManager::new(50, 5, disk_manager)));
+            {
+                let mut bpm = bpm.write().unwrap();
+                let page = bpm.fetch_page(&CATALOG_ROOT_PAGE_ID).unwrap();
+                page.write()
+                    .unwrap()
+                    .insert_tuple(TupleMetadata::new(false), Tuple::from(data))
+                    .unwrap();
+                bpm.unpin_page(&CATALOG_ROOT_PAGE_ID, true);
+            }
+            bpm
+        };
+
+        // A root that isn't a catalog root.
+        let bpm = write_root(b"junk".to_vec());
+        assert!(matches!(
+            HeapTableManager::new(&bpm),
+            Err(Error::InvalidData(_))
+        ));
+
+        // A root pointing to heaps that were never written.
+        let mut data = b"RDBC".to_vec();
+        for page_id in [100_u32, 101, 102, 103, 104, 105] {
+            data.extend(page_id.to_le_bytes());
+        }
+        let bpm = write_root(data);
+        assert!(matches!(
+            HeapTableManager::new(&bpm),
+            Err(Error::InvalidData(_))
+        ));
+    }
+
+    #[test]
+    fn test_deleted_pages_are_reused() {
+        let disk_manager = DiskManager::new_with_handle_for_test();
+        let bpm = Arc::new(RwLock::new(BufferPoolManager::new(
+            50,
+            5,
+            Arc::clone(&disk_manager),
+        )));
+        let mut engine = HeapTableManager::new(&bpm).unwrap();
+        let table = Table::builder()
+            .name("people")
+            .column("id", DataType::Int, false, None, None)
+            .column("name", DataType::Text, false, None, Some(5))
+            .build();
+        let fill = |engine: &mut HeapTableManager| {
+            for id in 0..1000 {
+                let row = Row::from(vec![Field::from(id), Field::from("ann")]);
+                engine.insert_row("people", row).unwrap();
+            }
+        };
+        engine.create_table(table.clone()).unwrap();
+        fill(&mut engine);
+        let status = engine.status().unwrap();
+        assert!(status.tables["people"].pages > 2);
+
+        engine.delete_table("people").unwrap();
+        engine.create_table(table).unwrap();
+        fill(&mut engine);
+        assert_eq!(engine.status().unwrap().pages, status.pages);
+
+        engine.truncate_table("people").unwrap();
+        assert_eq!(engine.status().unwrap().tables["people"].pages, 1);
+        fill(&mut engine);
+        assert_eq!(engine.status().unwrap().pages, status.pages);
+        bpm.write().unwrap().flush_all_pages();
+        drop(engine);
+
+        let bpm = Arc::new(RwLock::new(BufferPoolManager::new(50, 5, disk_manager)));
+        let mut engine = HeapTableManager::new(&bpm).unwrap();
+        assert_eq!(engine.scan("people").count(), 1000);
+    }
+
+    #[test]
+    fn test_primary_key() {
+        let disk_manager = DiskManager::new_with_handle_for_test();
+        let reopen = || {
+            let bpm = Arc::new(RwLock::new(BufferPoolManager::new(
+                50,
+                5,
+                Arc::clone(&disk_manager),
+            )));
+            (HeapTableManager::new(&bpm).unwrap(), bpm)
+        };
+        let row = |id: i32, name: &str| Row::from(vec![Field::from(id), Field::from(name)]);
+
+        let (mut engine, bpm) = reopen();
+        let table = Table::builder()
+            .name("people")
+            .column("id", DataType::Int, false, None, None)
+            .column("name", DataType::Text, false, None, Some(5))
+            .primary_key(&["id"])
+            .build();
+        engine.create_table(table).unwrap();
+        engine.insert_row("people", row(1, "ann")).unwrap();
+        let bob = engine.insert_row("people", row(2, "bob")).unwrap();
+        assert!(matches!(
+            engine.insert_row("people", row(1, "carol")),
+            Err(Error::InvalidInput(_))
+        ));
+        assert!(engine
+            .update_row(Key::new("people", &bob), row(1, "bob"))
+            .is_err());
+        engine
+            .update_row(Key::new("people", &bob), row(3, "bob"))
+            .unwrap();
+        assert_eq!(engine.lookup("people", &[Field::from(2)]).unwrap(), None);
+        assert_eq!(
+            engine.lookup("people", &[Field::from(3)]).unwrap(),
+            Some(bob)
+        );
+        assert!(engine.lookup("people", &[Field::from("3")]).is_err());
+        bpm.write().unwrap().flush_all_pages();
+        drop(engine);
+
+        let (mut engine, _) = reopen();
+        assert_eq!(
+            engine.get_table("people").unwrap().unwrap().primary_key(),
+            [0]
+        );
+        assert_eq!(
+            engine.get_by_key("people", &[Field::from(1)]).unwrap(),
+            Some(row(1, "ann"))
+        );
+        assert!(engine
+            .update_by_key("people", &[Field::from(3)], row(3, "bobby"))
+            .unwrap());
+        assert!(!engine
+            .update_by_key("people", &[Field::from(4)], row(4, "dan"))
+            .unwrap());
+        assert_eq!(
+            engine.get_by_key("people", &[Field::from(3)]).unwrap(),
+            Some(row(3, "bobby"))
+        );
+        assert!(engine.delete_by_key("people", &[Field::from(1)]).unwrap());
+        assert!(!engine.delete_by_key("people", &[Field::from(1)]).unwrap());
+        engine.insert_row("people", row(1, "carol")).unwrap();
+        assert_eq!(engine.scan("people").count(), 2);
+
+        engine.create_table(Table::from(DataType::Int)).unwrap();
+        assert!(engine.lookup("", &[Field::from(1)]).is_err());
+    }
+
+    #[test]
+    fn test_alter_table_rejects_invalid_changes() {
+        let mut engine = create_engine();
+        let no_default = Column::new("age", DataType::Int, false, None, None);
+        assert_invalid_column(engine.add_column("people", no_default), "age");
+        let wrong_type = Column::new("age", DataType::Int, false, Some(Field::from("1")), None);
+        assert_invalid_column(engine.add_column("people", wrong_type), "age");
+        let duplicate = Column::new("name", DataType::Text, true, None, None);
+        assert_invalid_column(engine.add_column("people", duplicate), "name");
+        assert_invalid_column(engine.drop_column("people", "age"), "age");
+        assert_invalid_column(engine.rename_column("people", "id", "name"), "name");
+        assert_invalid_column(engine.rename_table("pets", "animals"), "pets");
+
+        engine.create_table(Table::from(DataType::Int)).unwrap();
+        assert_invalid_column(engine.rename_table("people", ""), "");
+        assert_eq!(engine.must_get_table("people").unwrap().version(), 0);
+    }
+
+    #[test]
+    fn test_update_row_rejects_invalid_rows() {
+        let mut engine = create_engine();
+        let row = Row::from(vec![Field::from(1), Field::from("ann"), Field::Null]);
+        let rid = engine.insert_row("people", row.clone()).unwrap();
+
+        let long_name = Row::from(vec![Field::from(1), Field::from("annabel")]);
+        assert_invalid_column(
+            engine.update_row(Key::new("people", &rid), long_name),
+            "name",
+        );
+        assert_eq!(row, engine.get_row(Key::new("people", &rid)).unwrap());
     }
 }
@@ -3,17 +3,44 @@ use serde::{Deserialize, Serialize};
 #[derive(PartialEq, Eq, Hash, Clone, Debug, Copy, Deserialize, Serialize)]
 pub struct TupleMetadata {
     is_deleted: bool,
+    /// The payload stored in the slot is an overflow pointer rather than the tuple itself.
+    is_overflow: bool,
+    /// The tuple was moved elsewhere, and the slot's payload is the [`crate::storage::page::RecordId`]
+    /// of its current location.
+    is_forwarded: bool,
+    /// The tuple was moved here from its original slot, which forwards to this one. Scans skip
+    /// relocated tuples and report them at their original slot instead.
+    is_relocated: bool,
 }
 
 impl TupleMetadata {
     pub fn new(is_deleted: bool) -> Self {
-        Self { is_deleted }
+        Self {
+            is_deleted,
+            is_overflow: false,
+            is_forwarded: false,
+            is_relocated: false,
+        }
     }
 
     pub fn deleted_payload_metadata() -> TupleMetadata {
         Self::new(true)
     }
 
+    /// Metadata for a live slot whose payload points to a chain of overflow pages.
+    pub fn overflow_payload_metadata() -> TupleMetadata {
+        let mut metadata = Self::new(false);
+        metadata.set_overflow(true);
+        metadata
+    }
+
+    /// Metadata for a live slot whose payload points to the tuple's current location.
+    pub fn forwarding_payload_metadata() -> TupleMetadata {
+        let mut metadata = Self::new(false);
+        metadata.set_forwarded(true);
+        metadata
+    }
+
     pub fn set_deleted(&mut self, d: bool) {
         self.is_deleted = d;
     }
@@ -22,7 +49,34 @@ impl TupleMetadata {
         self.is_deleted
     }
 
+    pub fn set_overflow(&mut self, o: bool) {
+        self.is_overflow = o;
+    }
+
+    pub fn is_overflow(&self) -> bool {
+        self.is_overflow
+    }
+
+    pub fn set_forwarded(&mut self, f: bool) {
+        self.is_forwarded = f;
+    }
+
+    pub fn is_forwarded(&self) -> bool {
+        self.is_forwarded
+    }
+
+    pub fn set_relocated(&mut self, r: bool) {
+        self.is_relocated = r;
+    }
+
+    pub fn is_relocated(&self) -> bool {
+        self.is_relocated
+    }
+
     pub fn to_string(&self) -> String {
-        format!("Deleted: {})", self.is_deleted)
+        format!(
+            "Deleted: {}, Overflow: {}, Forwarded: {}, Relocated: {})",
+            self.is_deleted, self.is_overflow, self.is_forwarded, self.is_relocated
+        )
     }
 }
@@ -2,7 +2,8 @@ use crate::common::{Error, Result};
 use crate::storage::page::RecordId;
 use crate::storage::tuple::Tuple;
 use crate::types::field::Field;
-use crate::types::{DataType, Table};
+use crate::types::{DataType, Json, KeyOrder, Table};
+use crate::{errdata, errinput};
 use dyn_clone::DynClone;
 use itertools::Itertools;
 use serde::{Deserialize, Serialize};
@@ -83,7 +84,7 @@ impl Row {
             .get_mut(index)
             .ok_or_else(|| Error::OutOfBounds)?;
 
-        match field.get_type() == new.get_type() {
+        match std::mem::discriminant(field) == std::mem::discriminant(&new) {
             true => {
                 *field = new;
                 Ok(())
@@ -108,91 +109,251 @@ impl Row {
             .join(", ")
     }
 
+    /// Serializes the row into a tuple, see [`Row::serialize`], prefixed with the version of the
+    /// schema as a big-endian u16.
     pub fn to_tuple(&self, schema: &Table) -> Result<Tuple> {
-        Ok(Tuple::from(self.serialize(schema)?))
+        let mut data = schema.version().to_be_bytes().to_vec();
+        data.extend(self.serialize(schema)?);
+        Ok(Tuple::from(data))
     }
 
-    pub fn from_tuple(tuple: Tuple, schema: &Table) -> Result<Row> {
-        Ok(Self::deserialize(tuple.data, schema))
+    /// Deserializes a tuple produced by [`Row::to_tuple`]. A tuple written at an earlier version of
+    /// the schema is read with the schema of that version and upgraded to the current one, see
+    /// [`Table::upgrade`].
+    pub fn from_tuple(mut tuple: Tuple, schema: &Table) -> Result<Row> {
+        if tuple.data.len() < 2 {
+            return errdata!("tuple of {} bytes has no schema version", tuple.data.len());
+        }
+        let data = tuple.data.split_off(2);
+        let version = u16::from_be_bytes([tuple.data[0], tuple.data[1]]);
+        if version == schema.version() {
+            return Self::deserialize(data, schema);
+        }
+        let Some(original) = schema.at_version(version) else {
+            return errdata!(
+                "tuple has schema version {version}, but table {} is at version {}",
+                schema.name(),
+                schema.version()
+            );
+        };
+        let row = Self::deserialize(data, original)?;
+        Ok(Row::new(schema.upgrade(row.values, version)?))
+    }
+
+    /// Encodes the row as a key that sorts bytewise like the row's values, compared column by
+    /// column, see [`Field::encode_key`]. `orders` gives the order of each column, and columns
+    /// past its end are ascending with NULLs first.
+    pub fn encode_key(&self, orders: &[KeyOrder]) -> Vec<u8> {
+        self.values
+            .iter()
+            .enumerate()
+            .flat_map(|(i, field)| field.encode_key(orders.get(i).copied().unwrap_or_default()))
+            .collect()
+    }
+
+    /// Decodes a key encoded by [`Row::encode_key`], given the types and orders of its columns.
+    pub fn decode_key(mut key: &[u8], types: &[DataType], orders: &[KeyOrder]) -> Result<Row> {
+        let values = types
+            .iter()
+            .enumerate()
+            .map(|(i, data_type)| {
+                let order = orders.get(i).copied().unwrap_or_default();
+                Field::decode_key(&mut key, *data_type, order)
+            })
+            .collect::<Result<Vec<_>>>()?;
+        if !key.is_empty() {
+            return errdata!("unexpected {} bytes at end of key", key.len());
+        }
+        Ok(Row::new(values))
     }
 
     /// Serializes the Row's header and data into a byte-stream, structured as follows:
     ///
-    /// | variable length field offset map | field data in bytes |
-    ///                 ^                               ^
-    ///     a text field's `stored_offset` points       |
-    ///     here, which stores the field's offset into here
+    /// | null bitmap | variable length field offset map | fixed length field data | variable length field data |
+    ///                               ^                                                       ^
+    ///              a variable length field's `stored_offset` is                             |
+    ///              its index here, which stores the field's offset into here ---------------
     ///
-    ///   a fixed length field's stored_offset is to the offset from the start of
-    ///   the field data portion (possibly not the beginning of the byte stream!)
+    /// Text, blob and JSON fields have variable length.
+    ///
+    /// - The null bitmap holds one bit per column, in column order, which is set if the column's
+    ///   value is NULL.
+    /// - The offset map holds a u16 per variable length field: the offset of the field's data from
+    ///   the start of the byte stream. A field ends where the next one starts, and the last one ends
+    ///   at the end of the byte stream.
+    /// - A fixed length field's `stored_offset` is its offset from the start of the fixed length
+    ///   field data. A NULL fixed length field is zeroed, so that the other fields keep their
+    ///   offsets, while a NULL variable length field takes no space.
+    ///
+    /// Values missing from the end of the row are filled in with their column's default value. The
+    /// row must otherwise be valid for the schema, see [`Row::validate`].
     pub fn serialize(&self, schema: &Table) -> Result<Vec<u8>> {
-        let mut offsets = Vec::new(); // Offset map for variable-length fields
-        let mut field_data = Vec::new(); // Field data portion
-
-        let mut current_offset = 0;
+        let values = self.values_for(schema)?;
+        let bitmap_size = null_bitmap_size(schema.col_count());
+        let header_size = bitmap_size + 2 * schema.variable_length_fields();
+        let mut bytes = vec![0; header_size + schema.fixed_field_size_bytes() as usize];
 
-        for (i, field) in self.values.iter().enumerate() {
-            match field.get_type() {
-                DataType::Text => {
-                    let serialized_field = field.serialize();
-                    let field_len = serialized_field.len();
-                    offsets.push(current_offset as u16);
-                    field_data.extend_from_slice(&serialized_field);
-                    current_offset += field_len;
-                },
-                _ => {
-                    let serialized_field = field.serialize();
-                    field_data.extend_from_slice(&serialized_field);
-                }
+        // Fixed length fields have a known position, so they're written first.
+        for (i, (column, field)) in schema.columns().iter().zip(&values).enumerate() {
+            if field.is_null() {
+                bytes[i / 8] |= 1 << (i % 8);
+            } else if !column.get_data_type().is_variable_length() {
+                let start = header_size + column.stored_offset() as usize;
+                let serialized_field = field.serialize();
+                bytes[start..(start + serialized_field.len())].copy_from_slice(&serialized_field);
             }
         }
-        let mut serialized_offsets = Vec::new();
-        for offset in offsets {
-            serialized_offsets.extend_from_slice(&offset.to_be_bytes());
+
+        for (column, field) in schema.columns().iter().zip(&values) {
+            if !column.get_data_type().is_variable_length() {
+                continue;
+            }
+            let offset = u16::try_from(bytes.len())
+                .or_else(|_| errinput!("row is too large to serialize"))?;
+            let index = bitmap_size + 2 * column.stored_offset() as usize;
+            bytes[index..(index + 2)].copy_from_slice(&offset.to_be_bytes());
+            if !field.is_null() {
+                bytes.extend_from_slice(&field.serialize());
+            }
         }
-        serialized_offsets.extend_from_slice(&field_data);
-        Ok(serialized_offsets)
+        Ok(bytes)
     }
 
-    /// Deserializes a byte stream into a Row object.
-    ///
-    /// `bytes` contains u16 offsets for variable-length fields, followed
-    /// by fixed-length fields, with variable-length fields at the end.
-    pub fn deserialize(bytes: Vec<u8>, schema: &Table) -> Self {
-       // Get the offsets of the variable length text fields, if any exist.
-        let variable_field_offsets: Vec<u16> = (0..schema.variable_length_fields())
-            .map(|i| u16::from_be_bytes([bytes[2 * i], bytes[(2 * i) + 1]]))
-            .collect();
+    /// Deserializes a byte stream produced by [`Row::serialize`] into a Row object.
+    pub fn deserialize(bytes: Vec<u8>, schema: &Table) -> Result<Self> {
+        let bitmap_size = null_bitmap_size(schema.col_count());
+        let header_size = bitmap_size + 2 * schema.variable_length_fields();
+        if bytes.len() < header_size + schema.fixed_field_size_bytes() as usize {
+            return errdata!("row of {} bytes is too short for its schema", bytes.len());
+        }
 
-        // The first byte in `bytes` of the field data
-        let field_data_start = variable_field_offsets.len() * 2;
+        // Get the offsets of the variable length text fields, if any exist.
+        let variable_field_offsets: Vec<usize> = (0..schema.variable_length_fields())
+            .map(|i| {
+                let index = bitmap_size + 2 * i;
+                u16::from_be_bytes([bytes[index], bytes[index + 1]]) as usize
+            })
+            .collect();
 
         let values = schema
             .columns()
             .iter()
-            .map(|column| match column.get_data_type() {
-                DataType::Text => {
-                    // Get the index into the variable length field offset array.
-                    let offset_index = column.stored_offset() as usize;
-                    let start = *variable_field_offsets.get(offset_index).unwrap() as usize;
-                    let end = if offset_index == variable_field_offsets.len() - 1 {
-                        bytes.len()
-                    } else {
-                        *variable_field_offsets.get(offset_index + 1).unwrap() as usize
-                    };
-
-                    Field::deserialize(&bytes[start..end], DataType::Text)
+            .enumerate()
+            .map(|(i, column)| {
+                if bytes[i / 8] & (1 << (i % 8)) != 0 {
+                    return Ok(Field::Null);
                 }
-                datatype => {
-                    // Get the offset of the field in the byte stream.
-                    let start = column.stored_offset() as usize + field_data_start;
-                    let end = start + column.length_bytes() as usize;
-
-                    Field::deserialize(&bytes[start..end], datatype)
+                let (start, end) = match column.get_data_type() {
+                    data_type if data_type.is_variable_length() => {
+                        // Get the index into the variable length field offset array.
+                        let offset_index = column.stored_offset() as usize;
+                        let end = match variable_field_offsets.get(offset_index + 1) {
+                            Some(next) => *next,
+                            None => bytes.len(),
+                        };
+                        (variable_field_offsets[offset_index], end)
+                    }
+                    _ => {
+                        // Get the offset of the field in the byte stream.
+                        let start = header_size + column.stored_offset() as usize;
+                        (
+                            start,
+                            start + column.get_data_type().length_bytes() as usize,
+                        )
+                    }
+                };
+                if start > end || end > bytes.len() {
+                    return errdata!("column {} is out of bounds", column.get_name());
                 }
+                Field::deserialize(&bytes[start..end], column.get_data_type())
             })
-            .collect();
-        Self { values }
+            .collect::<Result<Vec<_>>>()?;
+        Ok(Self { values })
+    }
 
+    /// Checks that the row can be stored in a table with the given schema, see [`Row::serialize`].
+    /// Fails with an [`Error::InvalidInput`] naming the offending column otherwise.
+    pub fn validate(&self, schema: &Table) -> Result<()> {
+        self.values_for(schema).map(|_| ())
     }
+
+    /// Returns the row's value for every column of `schema`, filling in missing values with the
+    /// column's default value. Fails if a value doesn't match its column's type, if a string is
+    /// longer than its column allows, or if a non-nullable column would be NULL.
+    fn values_for(&self, schema: &Table) -> Result<Vec<Field>> {
+        if self.values.len() > schema.col_count() {
+            return errinput!(
+                "row has {} values, but table {} only has {} columns",
+                self.values.len(),
+                schema.name(),
+                schema.col_count()
+            );
+        }
+        schema
+            .columns()
+            .iter()
+            .enumerate()
+            .map(|(i, column)| {
+                let value = match (self.values.get(i), column.default()) {
+                    (Some(value), _) => value.clone(),
+                    (None, Some(default)) => default.clone(),
+                    (None, None) => {
+                        return errinput!("no value given for column {}", column.get_name())
+                    }
+                };
+                match &value {
+                    // Decimals are rescaled to their column's scale, as long as they fit.
+                    Field::Decimal(d) => match column.get_data_type() {
+                        DataType::Decimal(precision, scale) => {
+                            Ok(Field::Decimal(d.fit(precision, scale)?))
+                        }
+                        data_type => errinput!(
+                            "invalid datatype {} for {} column {}",
+                            value.get_type(),
+                            data_type,
+                            column.get_name()
+                        ),
+                    },
+                    Field::Null if !column.is_nullable() => {
+                        errinput!("NULL value not allowed for column {}", column.get_name())
+                    }
+                    Field::Null => Ok(value),
+                    // JSON can be given as text, which must be a valid document.
+                    Field::String(s) if column.get_data_type() == DataType::Json => {
+                        match s.parse::<Json>() {
+                            Ok(json) => Ok(Field::Json(json)),
+                            Err(err) => errinput!(
+                                "invalid json value for column {}: {err}",
+                                column.get_name()
+                            ),
+                        }
+                    }
+                    value if value.get_type() != column.get_data_type() => errinput!(
+                        "invalid datatype {} for {} column {}",
+                        value.get_type(),
+                        column.get_data_type(),
+                        column.get_name()
+                    ),
+                    Field::String(s) if s.len() > column.max_str_bytes() => errinput!(
+                        "string value of {} bytes exceeds the maximum length {} of column {}",
+                        s.len(),
+                        column.max_str_bytes(),
+                        column.get_name()
+                    ),
+                    Field::Blob(b) if b.len() > column.max_str_bytes() => errinput!(
+                        "blob value of {} bytes exceeds the maximum length {} of column {}",
+                        b.len(),
+                        column.max_str_bytes(),
+                        column.get_name()
+                    ),
+                    _ => Ok(value),
+                }
+            })
+            .collect()
+    }
+}
+
+/// Returns the number of bytes needed for a null bitmap with a bit for each of `columns` columns.
+fn null_bitmap_size(columns: usize) -> usize {
+    columns.div_ceil(8)
 }
@@ -1,6 +1,8 @@
 use super::*;
 use crate::common::utility::create_table_definition;
+use crate::common::Error;
 use crate::types::field::Field;
+use crate::types::{DataType, Decimal, Json, Table, Uuid};
 use std::sync::Arc;
 
 #[test]
@@ -19,8 +21,8 @@ pub fn test_comparison() {
     assert_eq!(row, row_eq);
 
     // Tuples with some differing fields should not be equal.
-    fields[2] = fields[2].clone() + Field::from(2);
-    fields[4] = fields[4].clone() + Field::from(2);
+    fields[2] = (fields[2].clone() + Field::from(2)).unwrap();
+    fields[4] = (fields[4].clone() + Field::from(2)).unwrap();
     let row_ne = Row::from(fields);
     assert_ne!(row, row_ne);
 }
@@ -78,3 +80,326 @@ pub fn test_int_serialization() {
         .enumerate()
         .for_each(|(i, field)| assert_eq!(row2.get_field(i).unwrap(), *field));
 }
+
+#[test]
+pub fn test_mixed_serialization() {
+    let schema = Table::builder()
+        .name("test_table")
+        .column("column0", DataType::Text, false, None, Some(20))
+        .column("column1", DataType::Int, false, None, None)
+        .column("column2", DataType::Text, false, None, Some(20))
+        .column("column3", DataType::Bool, false, None, None)
+        .column("column4", DataType::Float, false, None, None)
+        .build();
+    let row = Row::from(vec![
+        Field::from("hello"),
+        Field::from(7),
+        Field::from(""),
+        Field::from(true),
+        Field::from(2.5),
+    ]);
+
+    let tuple = row.to_tuple(&schema).unwrap();
+    assert_eq!(row, Row::from_tuple(tuple, &schema).unwrap());
+}
+
+#[test]
+pub fn test_null_serialization() {
+    let schema = Table::builder()
+        .name("test_table")
+        .column("column0", DataType::Int, true, None, None)
+        .column("column1", DataType::Text, true, None, Some(20))
+        .column("column2", DataType::Float, false, None, None)
+        .column("column3", DataType::Text, true, None, Some(20))
+        .build();
+    let row = Row::from(vec![
+        Field::Null,
+        Field::Null,
+        Field::from(1.5),
+        Field::from("world"),
+    ]);
+
+    let tuple = row.to_tuple(&schema).unwrap();
+    assert_eq!(row, Row::from_tuple(tuple, &schema).unwrap());
+
+    // The fixed length field following a NULL one is still found at its offset.
+    let row = Row::from(vec![Field::from(3), Field::from("hi"), Field::from(4.5)]);
+    let tuple = row.to_tuple(&schema).unwrap();
+    let expected = Row::from(vec![
+        Field::from(3),
+        Field::from("hi"),
+        Field::from(4.5),
+        Field::Null,
+    ]);
+    assert_eq!(expected, Row::from_tuple(tuple, &schema).unwrap());
+}
+
+#[test]
+pub fn test_serialize_rejects_invalid_values() {
+    let schema = Table::builder()
+        .name("test_table")
+        .column("id", DataType::Int, false, None, None)
+        .column("flag", DataType::Bool, false, Some(Field::from(true)), None)
+        .column("name", DataType::Text, false, None, Some(20))
+        .build();
+
+    let null_row = Row::from(vec![Field::Null, Field::from(false), Field::from("a")]);
+    assert_eq!(
+        Err(Error::InvalidInput(
+            "NULL value not allowed for column id".to_string()
+        )),
+        null_row.to_tuple(&schema)
+    );
+
+    let wrong_type = Row::from(vec![Field::from(1), Field::from(2), Field::from("a")]);
+    assert!(matches!(
+        wrong_type.to_tuple(&schema),
+        Err(Error::InvalidInput(_))
+    ));
+
+    // A missing value without a default can't be filled in.
+    let missing = Row::from(vec![Field::from(1)]);
+    assert_eq!(
+        Err(Error::InvalidInput(
+            "no value given for column name".to_string()
+        )),
+        missing.to_tuple(&schema)
+    );
+
+    // String lengths are limited in bytes, not characters.
+    let row = |name: &str| Row::from(vec![Field::from(1), Field::from(true), Field::from(name)]);
+    assert!(row(&"a".repeat(20)).to_tuple(&schema).is_ok());
+    assert!(row(&"é".repeat(10)).to_tuple(&schema).is_ok());
+    assert_eq!(
+        Err(Error::InvalidInput(
+            "string value of 22 bytes exceeds the maximum length 20 of column name".to_string()
+        )),
+        row(&"é".repeat(11)).to_tuple(&schema)
+    );
+}
+
+#[test]
+pub fn test_serialize_fills_in_defaults() {
+    let schema = Table::builder()
+        .name("test_table")
+        .column("id", DataType::Int, false, None, None)
+        .column("flag", DataType::Bool, false, Some(Field::from(true)), None)
+        .column("note", DataType::Text, true, None, Some(20))
+        .build();
+
+    let tuple = Row::from(vec![Field::from(1)]).to_tuple(&schema).unwrap();
+    let expected = Row::from(vec![Field::from(1), Field::from(true), Field::Null]);
+    assert_eq!(expected, Row::from_tuple(tuple, &schema).unwrap());
+}
+
+#[test]
+pub fn test_numeric_serialization() {
+    let schema = Table::builder()
+        .name("test_table")
+        .column("id", DataType::BigInt, false, None, None)
+        .column("ratio", DataType::Double, false, None, None)
+        .column("price", DataType::Decimal(6, 2), true, None, None)
+        .build();
+    let row = Row::from(vec![
+        Field::from(1i64 << 40),
+        Field::Double(0.1),
+        Field::from(Decimal::new(-12345, 2)),
+    ]);
+    let tuple = row.to_tuple(&schema).unwrap();
+    assert_eq!(row, Row::from_tuple(tuple, &schema).unwrap());
+
+    // Decimals are stored at their column's scale.
+    let row = Row::from(vec![
+        Field::from(1i64),
+        Field::Double(0.0),
+        Field::from("1.005".parse::<Decimal>().unwrap()),
+    ]);
+    let tuple = row.to_tuple(&schema).unwrap();
+    let price = Row::from_tuple(tuple, &schema)
+        .unwrap()
+        .get_field(2)
+        .unwrap();
+    assert_eq!(price.to_string(), "1.01");
+
+    let too_large = Row::from(vec![
+        Field::from(1i64),
+        Field::Double(0.0),
+        Field::from(Decimal::from(10_000)),
+    ]);
+    assert!(matches!(
+        too_large.to_tuple(&schema),
+        Err(Error::InvalidInput(_))
+    ));
+}
+
+#[test]
+pub fn test_binary_serialization() {
+    let schema = Table::builder()
+        .name("test_table")
+        .column("id", DataType::Uuid, false, None, None)
+        .column("name", DataType::Text, false, None, Some(20))
+        .column("payload", DataType::Blob, true, None, Some(4))
+        .column("checksum", DataType::Blob, false, None, None)
+        .build();
+    assert_eq!(schema.variable_length_fields(), 3);
+
+    // Blobs needn't be valid UTF-8.
+    let row = Row::from(vec![
+        Field::from(Uuid::from_bytes([7; 16])),
+        Field::from("hello"),
+        Field::from(vec![0xc3, 0x28]),
+        Field::from(vec![0xff; 16]),
+    ]);
+    let tuple = row.to_tuple(&schema).unwrap();
+    assert_eq!(row, Row::from_tuple(tuple, &schema).unwrap());
+
+    let row = Row::from(vec![
+        Field::from(Uuid::from_bytes([7; 16])),
+        Field::from(""),
+        Field::Null,
+        Field::from(Vec::new()),
+    ]);
+    let tuple = row.to_tuple(&schema).unwrap();
+    assert_eq!(row, Row::from_tuple(tuple, &schema).unwrap());
+
+    let too_long = Row::from(vec![
+        Field::from(Uuid::from_bytes([7; 16])),
+        Field::from(""),
+        Field::from(vec![0; 5]),
+        Field::from(Vec::new()),
+    ]);
+    assert!(matches!(
+        too_long.to_tuple(&schema),
+        Err(Error::InvalidInput(_))
+    ));
+}
+
+#[test]
+pub fn test_json_serialization() {
+    let schema = Table::builder()
+        .name("test_table")
+        .column("id", DataType::Int, false, None, None)
+        .column("doc", DataType::Json, true, None, None)
+        .column("name", DataType::Text, false, None, Some(20))
+        .build();
+
+    let doc: Json = r#"{"a": [1, {"b": "c"}], "d": false}"#.parse().unwrap();
+    let row = Row::from(vec![
+        Field::from(1),
+        Field::from(doc.clone()),
+        Field::from("x"),
+    ]);
+    let tuple = row.to_tuple(&schema).unwrap();
+    assert_eq!(row, Row::from_tuple(tuple, &schema).unwrap());
+
+    // Documents given as text are validated and stored as JSON.
+    let row = Row::from(vec![
+        Field::from(1),
+        Field::from(r#"{"d": false, "a": [1, {"b": "c"}]}"#),
+        Field::from("x"),
+    ]);
+    let tuple = row.to_tuple(&schema).unwrap();
+    let stored = Row::from_tuple(tuple, &schema)
+        .unwrap()
+        .get_field(1)
+        .unwrap();
+    assert_eq!(stored, Field::from(doc));
+
+    let invalid = Row::from(vec![Field::from(1), Field::from("{"), Field::from("x")]);
+    assert!(matches!(
+        invalid.to_tuple(&schema),
+        Err(Error::InvalidInput(_))
+    ));
+}
+
+#[test]
+fn test_key_encoding() {
+    use crate::types::KeyOrder;
+    use std::collections::BTreeMap;
+
+    let types = [DataType::Text, DataType::Int, DataType::Decimal(6, 2)];
+    let orders = [
+        KeyOrder::default(),
+        KeyOrder {
+            descending: true,
+            nulls_last: true,
+            ..Default::default()
+        },
+    ];
+    let row = |name: Option<&str>, id: Option<i32>, amount: &str| {
+        Row::from(vec![
+            name.map_or(Field::Null, Field::from),
+            id.map_or(Field::Null, Field::from),
+            Field::Decimal(amount.parse::<Decimal>().unwrap().rescale(2).unwrap()),
+        ])
+    };
+    // Sorted by name ascending with NULLs first, then id descending with NULLs last.
+    let rows = vec![
+        row(None, Some(1), "0"),
+        row(None, None, "0"),
+        row(Some(""), Some(2), "-1.5"),
+        row(Some("a"), Some(10), "1"),
+        row(Some("a"), Some(2), "-1"),
+        row(Some("a"), Some(-2), "1.25"),
+        row(Some("a"), None, "1.25"),
+        row(Some("a\0"), Some(1), "0"),
+        row(Some("ab"), Some(1), "0"),
+    ];
+
+    let index: BTreeMap<Vec<u8>, usize> = rows
+        .iter()
+        .enumerate()
+        .rev()
+        .map(|(i, row)| (row.encode_key(&orders), i))
+        .collect();
+    assert_eq!(
+        index.values().copied().collect::<Vec<_>>(),
+        (0..rows.len()).collect::<Vec<_>>()
+    );
+
+    for (key, i) in index {
+        assert_eq!(Row::decode_key(&key, &types, &orders).unwrap(), rows[i]);
+    }
+
+    let key = rows[3].encode_key(&orders);
+    assert!(Row::decode_key(&key, &types[..2], &orders).is_err());
+    assert!(Row::decode_key(&key[..key.len() - 1], &types, &orders).is_err());
+}
+
+#[test]
+fn test_case_insensitive_key() {
+    use crate::types::{Collation, Column};
+    use std::collections::BTreeMap;
+
+    let table = Table::builder()
+        .name("users")
+        .column_from_definition(
+            Column::builder()
+                .name("name".to_string())
+                .data_type(DataType::Text)
+                .max_str_len(20)
+                .collation(Collation::UnicodeCaseInsensitive)
+                .build(),
+        )
+        .build();
+    assert_eq!(
+        table.get_column(0).to_string(),
+        "name:varchar(20) collate unicode_ci"
+    );
+    let orders = table.key_orders();
+    let key = |name: &str| Row::from(vec![Field::from(name)]).encode_key(&orders);
+
+    // A unique lookup on the name finds it regardless of case.
+    let mut names = BTreeMap::new();
+    for (i, name) in ["Zoë", "alice", "Bob"].into_iter().enumerate() {
+        assert!(names.insert(key(name), i).is_none());
+    }
+    assert!(names.insert(key("ALICE"), 3).is_some());
+    assert_eq!(names.get(&key("ZOË")), Some(&0));
+    assert_eq!(names.get(&key("bob")), Some(&2));
+    assert_eq!(names.values().copied().collect::<Vec<_>>(), [3, 2, 0]);
+
+    // Keys decode to the folded name.
+    let decoded = Row::decode_key(&key("Bob"), &[DataType::Text], &orders).unwrap();
+    assert_eq!(decoded, Row::from(vec![Field::from("bob")]));
+}
@@ -0,0 +1,75 @@
+use crate::common::{Error, Result};
+use crate::errinput;
+use serde::{Deserialize, Serialize};
+use std::fmt::Write;
+
+/// A universally unique identifier, stored as its 16 bytes and written in the usual hyphenated
+/// hex form, e.g. `123e4567-e89b-12d3-a456-426614174000`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub struct Uuid([u8; 16]);
+
+impl Uuid {
+    pub fn from_bytes(bytes: [u8; 16]) -> Uuid {
+        Uuid(bytes)
+    }
+
+    pub fn as_bytes(&self) -> &[u8; 16] {
+        &self.0
+    }
+}
+
+impl std::str::FromStr for Uuid {
+    type Err = Error;
+
+    /// Parses a UUID written as 32 hex digits, either hyphenated or not.
+    fn from_str(s: &str) -> Result<Uuid> {
+        let hyphenated = s.len() == 36
+            && s.char_indices()
+                .all(|(i, c)| (c == '-') == matches!(i, 8 | 13 | 18 | 23));
+        let digits = match hyphenated {
+            true => s.replace('-', ""),
+            false => s.to_string(),
+        };
+        match decode_hex(&digits).map(<[u8; 16]>::try_from) {
+            Some(Ok(bytes)) => Ok(Uuid(bytes)),
+            _ => errinput!("invalid uuid {s}"),
+        }
+    }
+}
+
+impl std::fmt::Display for Uuid {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        let hex = encode_hex(&self.0);
+        write!(
+            f,
+            "{}-{}-{}-{}-{}",
+            &hex[..8],
+            &hex[8..12],
+            &hex[12..16],
+            &hex[16..20],
+            &hex[20..]
+        )
+    }
+}
+
+/// Returns the bytes as lowercase hex digits.
+pub(crate) fn encode_hex(bytes: &[u8]) -> String {
+    bytes
+        .iter()
+        .fold(String::with_capacity(2 * bytes.len()), |mut hex, byte| {
+            let _ = write!(hex, "{byte:02x}");
+            hex
+        })
+}
+
+/// Decodes hex digits, in either case, into bytes. Returns `None` if the string isn't an even
+/// number of hex digits.
+pub(crate) fn decode_hex(hex: &str) -> Option<Vec<u8>> {
+    if !hex.len().is_multiple_of(2) || !hex.bytes().all(|b| b.is_ascii_hexdigit()) {
+        return None;
+    }
+    (0..hex.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(&hex[i..i + 2], 16).ok())
+        .collect()
+}
@@ -0,0 +1,366 @@
+use crate::common::{Error, Result};
+use crate::errinput;
+use crate::types::field::Field;
+use crate::types::{DataType, Decimal, TimestampTz, Uuid, MAX_DECIMAL_PRECISION};
+use std::cmp::Ordering;
+
+impl DataType {
+    pub fn is_numeric(&self) -> bool {
+        matches!(
+            self,
+            DataType::Int
+                | DataType::BigInt
+                | DataType::Float
+                | DataType::Double
+                | DataType::Decimal(_, _)
+        )
+    }
+
+    /// Returns the type that values of the two types are implicitly converted to when they're
+    /// combined, e.g. compared or added, or `None` if they can't be combined:
+    ///
+    /// - Numbers widen: ints to bigints, and ints and bigints to decimals wide enough to hold
+    ///   them. Floats combined with ints stay floats, but combined with any other number, as well
+    ///   as any combination with a double, become doubles.
+    /// - Dates widen to timestamps, and dates and timestamps to timestamps with time zone.
+    /// - Text is converted to a boolean, date and time, uuid or json value it's combined with, as
+    ///   a quoted literal would be. It isn't implicitly converted to a number.
+    pub fn common_type(&self, other: &DataType) -> Option<DataType> {
+        use DataType::*;
+        Some(match (*self, *other) {
+            (Invalid, _) | (_, Invalid) => return None,
+            (Decimal(_, _), Int | BigInt | Decimal(_, _)) | (Int | BigInt, Decimal(_, _)) => {
+                let (precision, scale) = self.decimal_digits()?;
+                let (other_precision, other_scale) = other.decimal_digits()?;
+                let integer_digits = (precision - scale).max(other_precision - other_scale);
+                let scale = scale.max(other_scale);
+                Decimal((integer_digits + scale).min(MAX_DECIMAL_PRECISION), scale)
+            }
+            (lhs, rhs) if lhs == rhs => lhs,
+            (Int, BigInt) | (BigInt, Int) => BigInt,
+            (Int, Float) | (Float, Int) => Float,
+            (lhs, rhs) if lhs.is_numeric() && rhs.is_numeric() => Double,
+            (Date, Timestamp) | (Timestamp, Date) => Timestamp,
+            (Date | Timestamp, TimestampTz) | (TimestampTz, Date | Timestamp) => TimestampTz,
+            (Text, rhs) if rhs.is_parsed_from_text() => rhs,
+            (lhs, Text) if lhs.is_parsed_from_text() => lhs,
+            _ => return None,
+        })
+    }
+
+    /// Returns the precision and scale of the decimals that hold all values of the type.
+    fn decimal_digits(&self) -> Option<(u8, u8)> {
+        match self {
+            DataType::Int => Some((10, 0)),
+            DataType::BigInt => Some((19, 0)),
+            DataType::Decimal(precision, scale) => Some((*precision, *scale)),
+            _ => None,
+        }
+    }
+
+    /// Returns true if text is implicitly converted to the type, see [`DataType::common_type`].
+    fn is_parsed_from_text(&self) -> bool {
+        matches!(
+            self,
+            DataType::Bool
+                | DataType::Date
+                | DataType::Timestamp
+                | DataType::TimestampTz
+                | DataType::Interval
+                | DataType::Uuid
+                | DataType::Json
+        )
+    }
+}
+
+impl Field {
+    /// Converts the value to the given type, as a SQL `CAST` does. NULL casts to NULL of any
+    /// type. Otherwise:
+    ///
+    /// - Any value can be cast to text, which gives its text form. Blobs are written as hex.
+    /// - Text can be cast to any type by parsing it. Casting text to a blob gives its bytes.
+    /// - Numbers can be cast to other numeric types, rounding half away from zero where digits
+    ///   are lost, and to and from booleans if they're integers.
+    /// - Dates and timestamps can be cast to each other. A timestamp with time zone is converted
+    ///   at its own UTC offset, and a date or timestamp is taken to be UTC.
+    /// - Uuids can be cast to and from 16 byte blobs.
+    ///
+    /// Fails if there's no conversion between the types, if text doesn't parse as the type, or if
+    /// a number is out of range for it.
+    pub fn cast(&self, data_type: DataType) -> Result<Field> {
+        Ok(match (self, data_type) {
+            (Field::Null, _) => Field::Null,
+            (Field::Decimal(d), DataType::Decimal(precision, scale)) => {
+                Field::Decimal(d.fit(precision, scale)?)
+            }
+            (value, data_type) if value.get_type() == data_type => value.clone(),
+            (value, DataType::Text) => Field::String(value.to_string()),
+            (Field::String(s), data_type) => Self::parse(s, data_type)?,
+            (Field::Boolean(b), DataType::Int) => Field::Integer(*b as i32),
+            (Field::Boolean(b), DataType::BigInt) => Field::BigInt(*b as i64),
+            (Field::Integer(i), DataType::Bool) => Field::Boolean(*i != 0),
+            (Field::BigInt(i), DataType::Bool) => Field::Boolean(*i != 0),
+            (value, data_type) if value.is_numeric() && data_type.is_numeric() => {
+                value.cast_numeric(data_type)?
+            }
+            (Field::Date(d), DataType::Timestamp) => Field::Timestamp(d.to_timestamp()),
+            (Field::Date(d), DataType::TimestampTz) => {
+                Field::TimestampTz(TimestampTz::new(d.to_timestamp(), 0)?)
+            }
+            (Field::Timestamp(t), DataType::Date) => Field::Date(t.date()),
+            (Field::Timestamp(t), DataType::TimestampTz) => {
+                Field::TimestampTz(TimestampTz::new(*t, 0)?)
+            }
+            (Field::TimestampTz(t), DataType::Date) => Field::Date(t.local().date()),
+            (Field::TimestampTz(t), DataType::Timestamp) => Field::Timestamp(t.local()),
+            (Field::Uuid(u), DataType::Blob) => Field::Blob(u.as_bytes().to_vec()),
+            (Field::Blob(b), DataType::Uuid) => match <[u8; 16]>::try_from(b.as_slice()) {
+                Ok(bytes) => Field::Uuid(Uuid::from_bytes(bytes)),
+                Err(_) => return errinput!("can't cast blob of {} bytes to uuid", b.len()),
+            },
+            (value, data_type) => {
+                return errinput!("can't cast {} {value} to {data_type}", value.get_type())
+            }
+        })
+    }
+
+    /// Converts two values to their common type, see [`DataType::common_type`], so that they can
+    /// be combined. NULL is left as is.
+    pub fn coerce(&self, other: &Field) -> Result<(Field, Field)> {
+        if self.is_null() || other.is_null() {
+            return Ok((self.clone(), other.clone()));
+        }
+        let (lhs_type, rhs_type) = (self.get_type(), other.get_type());
+        match lhs_type.common_type(&rhs_type) {
+            Some(data_type) => Ok((self.cast(data_type)?, other.cast(data_type)?)),
+            None => {
+                errinput!("can't combine {lhs_type} value {self} with {rhs_type} value {other}")
+            }
+        }
+    }
+
+    /// Compares two values after converting them to their common type, see [`Field::coerce`].
+    /// Returns `None` if either value is NULL, since the comparison is then unknown.
+    pub fn checked_cmp(&self, other: &Field) -> Result<Option<Ordering>> {
+        if self.is_null() || other.is_null() {
+            return Ok(None);
+        }
+        let (lhs, rhs) = self.coerce(other)?;
+        Ok(Some(lhs.cmp(&rhs)))
+    }
+
+    /// Parses text as a value of the given type.
+    fn parse(s: &str, data_type: DataType) -> Result<Field> {
+        let invalid = || errinput!("invalid {data_type} value '{s}'");
+        let trimmed = s.trim();
+        Ok(match data_type {
+            DataType::Bool => match trimmed.to_lowercase().as_str() {
+                "true" | "t" | "yes" | "y" | "on" | "1" => Field::Boolean(true),
+                "false" | "f" | "no" | "n" | "off" | "0" => Field::Boolean(false),
+                _ => return invalid(),
+            },
+            DataType::Int => match trimmed.parse() {
+                Ok(i) => Field::Integer(i),
+                Err(_) => return invalid(),
+            },
+            DataType::BigInt => match trimmed.parse() {
+                Ok(i) => Field::BigInt(i),
+                Err(_) => return invalid(),
+            },
+            DataType::Float => match trimmed.parse() {
+                Ok(f) => Field::Float(f),
+                Err(_) => return invalid(),
+            },
+            DataType::Double => match trimmed.parse() {
+                Ok(f) => Field::Double(f),
+                Err(_) => return invalid(),
+            },
+            DataType::Decimal(precision, scale) => {
+                Field::Decimal(trimmed.parse::<Decimal>()?.fit(precision, scale)?)
+            }
+            DataType::Date => Field::Date(trimmed.parse()?),
+            DataType::Timestamp => Field::Timestamp(trimmed.parse()?),
+            DataType::TimestampTz => Field::TimestampTz(trimmed.parse()?),
+            DataType::Interval => Field::Interval(trimmed.parse()?),
+            DataType::Uuid => Field::Uuid(trimmed.parse()?),
+            DataType::Json => Field::Json(s.parse()?),
+            DataType::Text => Field::String(s.to_string()),
+            DataType::Blob => Field::Blob(s.as_bytes().to_vec()),
+            DataType::Invalid => return errinput!("can't cast text '{s}' to {data_type}"),
+        })
+    }
+
+    /// Converts a number to another numeric type.
+    fn cast_numeric(&self, data_type: DataType) -> Result<Field> {
+        let out_of_range = || errinput!("{self} is out of range for {data_type}");
+        Ok(match data_type {
+            DataType::Float => {
+                let f = self.to_f64() as f32;
+                if f.is_infinite() && self.to_f64().is_finite() {
+                    return out_of_range();
+                }
+                Field::Float(f)
+            }
+            DataType::Double => Field::Double(self.to_f64()),
+            DataType::Decimal(precision, scale) => {
+                let decimal = match self {
+                    Field::Integer(i) => Decimal::from(*i),
+                    Field::BigInt(i) => Decimal::from(*i),
+                    Field::Decimal(d) => *d,
+                    // Floats are converted via their shortest text form, which is what they
+                    // were most likely written as.
+                    Field::Float(_) | Field::Double(_) => match self.to_string().parse() {
+                        Ok(decimal) => decimal,
+                        Err(_) => return out_of_range(),
+                    },
+                    _ => return out_of_range(),
+                };
+                Field::Decimal(decimal.fit(precision, scale)?)
+            }
+            DataType::Int | DataType::BigInt => {
+                let integer: i128 = match self {
+                    Field::Integer(i) => *i as i128,
+                    Field::BigInt(i) => *i as i128,
+                    Field::Decimal(d) => d.rescale(0).ok_or(Error::OverflowError)?.value(),
+                    value => match value.to_f64().round() {
+                        f if f.is_finite() && f.abs() < 1e38 => f as i128,
+                        _ => return out_of_range(),
+                    },
+                };
+                match data_type {
+                    DataType::Int => match i32::try_from(integer) {
+                        Ok(i) => Field::Integer(i),
+                        Err(_) => return out_of_range(),
+                    },
+                    _ => match i64::try_from(integer) {
+                        Ok(i) => Field::BigInt(i),
+                        Err(_) => return out_of_range(),
+                    },
+                }
+            }
+            data_type => return errinput!("can't cast {self} to {data_type}"),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cast() {
+        let cast = |field: Field, data_type: DataType| field.cast(data_type);
+
+        assert_eq!(
+            cast(Field::from(" 42 "), DataType::Int),
+            Ok(Field::from(42))
+        );
+        assert_eq!(
+            cast(Field::from(42), DataType::Float),
+            Ok(Field::from(42.0))
+        );
+        assert_eq!(cast(Field::from(2.5), DataType::Int), Ok(Field::from(3)));
+        assert_eq!(cast(Field::from(-2.5), DataType::Int), Ok(Field::from(-3)));
+        assert_eq!(
+            cast(Field::from(true), DataType::Text),
+            Ok(Field::from("true"))
+        );
+        assert_eq!(
+            cast(Field::from("Off"), DataType::Bool),
+            Ok(Field::from(false))
+        );
+        assert_eq!(cast(Field::from(0), DataType::Bool), Ok(Field::from(false)));
+        assert_eq!(
+            cast(Field::from(1i64 << 40), DataType::Text),
+            Ok(Field::from("1099511627776"))
+        );
+        assert_eq!(
+            cast(Field::from(0.1), DataType::Decimal(5, 2)),
+            Ok(Field::from("0.10".parse::<Decimal>().unwrap()))
+        );
+        assert_eq!(
+            cast(Field::from("12.345"), DataType::Decimal(5, 2)).map(|d| d.to_string()),
+            Ok("12.35".to_string())
+        );
+        assert_eq!(
+            cast(Field::from("2024-03-01 12:00:00"), DataType::Date).map(|d| d.to_string()),
+            Err(Error::InvalidInput(
+                "invalid date 2024-03-01 12:00:00".to_string()
+            ))
+        );
+        assert_eq!(
+            cast(
+                Field::from("2024-03-01 12:00:00+02:00"),
+                DataType::TimestampTz
+            )
+            .and_then(|t| t.cast(DataType::Date))
+            .map(|d| d.to_string()),
+            Ok("2024-03-01".to_string())
+        );
+        assert_eq!(cast(Field::Null, DataType::Int), Ok(Field::Null));
+
+        assert_eq!(
+            cast(Field::from("abc"), DataType::Int),
+            Err(Error::InvalidInput("invalid int value 'abc'".to_string()))
+        );
+        assert_eq!(
+            cast(Field::from(1i64 << 40), DataType::Int),
+            Err(Error::InvalidInput(
+                "1099511627776 is out of range for int".to_string()
+            ))
+        );
+        assert_eq!(
+            cast(Field::from(true), DataType::Date),
+            Err(Error::InvalidInput(
+                "can't cast bool TRUE to date".to_string()
+            ))
+        );
+        assert!(cast(Field::Double(f64::NAN), DataType::BigInt).is_err());
+        assert!(cast(Field::from(1e30_f32), DataType::BigInt).is_err());
+        assert!(cast(Field::from(vec![1, 2]), DataType::Uuid).is_err());
+    }
+
+    #[test]
+    fn test_common_type() {
+        use DataType::*;
+        assert_eq!(Int.common_type(&BigInt), Some(BigInt));
+        assert_eq!(Int.common_type(&Float), Some(Float));
+        assert_eq!(BigInt.common_type(&Float), Some(Double));
+        assert_eq!(Decimal(5, 2).common_type(&Int), Some(Decimal(12, 2)));
+        assert_eq!(
+            Decimal(5, 2).common_type(&Decimal(4, 3)),
+            Some(Decimal(6, 3))
+        );
+        assert_eq!(Decimal(5, 2).common_type(&Double), Some(Double));
+        assert_eq!(Date.common_type(&TimestampTz), Some(TimestampTz));
+        assert_eq!(Text.common_type(&Date), Some(Date));
+        assert_eq!(Text.common_type(&Int), None);
+        assert_eq!(Bool.common_type(&Int), None);
+        assert_eq!(Invalid.common_type(&Invalid), None);
+    }
+
+    #[test]
+    fn test_coerced_comparison() {
+        assert_eq!(
+            Field::from(1).checked_cmp(&Field::from(1i64)),
+            Ok(Some(Ordering::Equal))
+        );
+        assert_eq!(
+            Field::from(2).checked_cmp(&Field::from(1.5)),
+            Ok(Some(Ordering::Greater))
+        );
+        assert_eq!(
+            Field::from("1.50".parse::<Decimal>().unwrap()).checked_cmp(&Field::from(2)),
+            Ok(Some(Ordering::Less))
+        );
+        assert_eq!(
+            Field::from("2024-03-01").checked_cmp(&Field::Date("2024-03-01".parse().unwrap())),
+            Ok(Some(Ordering::Equal))
+        );
+        assert_eq!(Field::Null.checked_cmp(&Field::from(1)), Ok(None));
+        assert!(Field::from("1").checked_cmp(&Field::from(1)).is_err());
+        assert!(Field::from("soon")
+            .checked_cmp(&Field::Date("2024-03-01".parse().unwrap()))
+            .is_err());
+    }
+}
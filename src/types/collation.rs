@@ -0,0 +1,153 @@
+use crate::common::{Error, Result};
+use crate::errinput;
+use crate::types::field::Field;
+use serde::{Deserialize, Serialize};
+use std::borrow::Cow;
+use std::cmp::Ordering;
+
+/// How the text values of a column compare. Text that compares equal under a collation also
+/// groups, hashes and encodes as a key the same, see [`Field::collate`].
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum Collation {
+    /// Compares the UTF-8 bytes, i.e. by code point.
+    #[default]
+    Binary,
+    /// Like binary, but ignores the case of ASCII letters.
+    AsciiCaseInsensitive,
+    /// Like binary, but ignores case as given by Unicode simple case folding, which maps each
+    /// character to a single character, e.g. `Σ`, `σ` and `ς` are equal but `ß` and `ss` aren't.
+    UnicodeCaseInsensitive,
+}
+
+impl Collation {
+    /// Returns the text that all text equal to `s` under the collation folds to. Folded text
+    /// compares like the original under the collation, but by code point.
+    pub fn fold<'a>(&self, s: &'a str) -> Cow<'a, str> {
+        match self {
+            Collation::Binary => Cow::Borrowed(s),
+            Collation::AsciiCaseInsensitive if !s.bytes().any(|b| b.is_ascii_uppercase()) => {
+                Cow::Borrowed(s)
+            }
+            Collation::AsciiCaseInsensitive => Cow::Owned(s.to_ascii_lowercase()),
+            Collation::UnicodeCaseInsensitive => Cow::Owned(s.chars().map(fold_char).collect()),
+        }
+    }
+
+    pub fn compare(&self, lhs: &str, rhs: &str) -> Ordering {
+        match self {
+            Collation::Binary => lhs.cmp(rhs),
+            collation => collation.fold(lhs).cmp(&collation.fold(rhs)),
+        }
+    }
+}
+
+/// Folds a character by its simple case mapping: to lowercase by way of uppercase, so that
+/// characters with several lowercase forms fold together, unless either mapping isn't to a single
+/// character.
+fn fold_char(c: char) -> char {
+    let mut upper = c.to_uppercase();
+    let upper = match (upper.next(), upper.next()) {
+        (Some(upper), None) => upper,
+        _ => c,
+    };
+    let mut lower = upper.to_lowercase();
+    match (lower.next(), lower.next()) {
+        (Some(lower), None) => lower,
+        _ => c,
+    }
+}
+
+impl std::str::FromStr for Collation {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Collation> {
+        match s.to_ascii_lowercase().as_str() {
+            "binary" => Ok(Collation::Binary),
+            "ascii_ci" => Ok(Collation::AsciiCaseInsensitive),
+            "unicode_ci" => Ok(Collation::UnicodeCaseInsensitive),
+            _ => errinput!("unknown collation {s}"),
+        }
+    }
+}
+
+impl std::fmt::Display for Collation {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        f.write_str(match self {
+            Collation::Binary => "binary",
+            Collation::AsciiCaseInsensitive => "ascii_ci",
+            Collation::UnicodeCaseInsensitive => "unicode_ci",
+        })
+    }
+}
+
+impl Field {
+    /// Returns the value that the values equal to it under the collation share, for grouping and
+    /// hashing: the folded text of a string, or the value itself otherwise.
+    pub fn collate(&self, collation: Collation) -> Field {
+        match self {
+            Field::String(s) => Field::String(collation.fold(s).into_owned()),
+            value => value.clone(),
+        }
+    }
+
+    /// Compares the values like [`Field`]'s `Ord` implementation, but strings by the collation.
+    pub fn cmp_collated(&self, other: &Field, collation: Collation) -> Ordering {
+        match (self, other) {
+            (Field::String(lhs), Field::String(rhs)) => collation.compare(lhs, rhs),
+            (lhs, rhs) => lhs.cmp(rhs),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_collations() {
+        let (binary, ascii, unicode) = (
+            Collation::Binary,
+            Collation::AsciiCaseInsensitive,
+            Collation::UnicodeCaseInsensitive,
+        );
+        assert_eq!(binary.compare("Bob", "alice"), Ordering::Less);
+        assert_eq!(ascii.compare("Bob", "alice"), Ordering::Greater);
+        assert_eq!(ascii.compare("BOB", "bob"), Ordering::Equal);
+        assert_eq!(ascii.compare("ÉMILE", "émile"), Ordering::Less);
+        assert_eq!(unicode.compare("ÉMILE", "émile"), Ordering::Equal);
+        assert_eq!(unicode.compare("ΣΊΣΥΦΟΣ", "σίσυφος"), Ordering::Equal);
+        assert_eq!(unicode.compare("STRASSE", "straße"), Ordering::Less);
+        assert_eq!(unicode.compare("\u{212a}elvin", "kelvin"), Ordering::Equal);
+
+        assert!(matches!(ascii.fold("already folded"), Cow::Borrowed(_)));
+        assert_eq!(unicode.fold("Àb"), "àb");
+
+        for collation in [binary, ascii, unicode] {
+            assert_eq!(collation.to_string().parse(), Ok(collation));
+        }
+        assert!("french".parse::<Collation>().is_err());
+    }
+
+    #[test]
+    fn test_collated_fields() {
+        let ascii = Collation::AsciiCaseInsensitive;
+        let mut names = ["bob", "Carol", "alice", "Bob"].map(Field::from).to_vec();
+        names.push(Field::Null);
+        names.sort_by(|lhs, rhs| lhs.cmp_collated(rhs, ascii));
+        assert_eq!(
+            names,
+            [
+                Field::Null,
+                Field::from("alice"),
+                Field::from("bob"),
+                Field::from("Bob"),
+                Field::from("Carol")
+            ]
+        );
+
+        let groups: std::collections::HashSet<_> =
+            names.iter().map(|name| name.collate(ascii)).collect();
+        assert_eq!(groups.len(), 4);
+        assert_eq!(Field::from(1).collate(ascii), Field::from(1));
+    }
+}
@@ -0,0 +1,746 @@
+use crate::common::{Error, Result};
+use crate::errinput;
+use serde::{Deserialize, Serialize};
+use std::cmp::Ordering;
+use std::hash::{Hash, Hasher};
+use std::str::FromStr;
+
+pub const MICROS_PER_SECOND: i64 = 1_000_000;
+pub const MICROS_PER_DAY: i64 = 86_400 * MICROS_PER_SECOND;
+
+/// The number of days a month counts as when comparing intervals.
+const DAYS_PER_MONTH: i64 = 30;
+/// Dates and timestamps are limited to years 1 to 9999.
+const MIN_YEAR: i64 = 1;
+const MAX_YEAR: i64 = 9999;
+/// The largest UTC offset a timestamp with time zone can have, in seconds.
+const MAX_OFFSET_SECONDS: i32 = 18 * 3600;
+
+/// A calendar date, stored as the number of days since 1970-01-01.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub struct Date {
+    days: i32,
+}
+
+impl Date {
+    /// Returns the given date, or an error if it doesn't exist.
+    pub fn from_ymd(year: i64, month: u32, day: u32) -> Result<Date> {
+        if !(MIN_YEAR..=MAX_YEAR).contains(&year)
+            || !(1..=12).contains(&month)
+            || day == 0
+            || day > days_in_month(year, month)
+        {
+            return errinput!("invalid date {year:04}-{month:02}-{day:02}");
+        }
+        Ok(Date {
+            days: days_from_civil(year, month, day) as i32,
+        })
+    }
+
+    pub fn from_days(days: i32) -> Date {
+        Date { days }
+    }
+
+    /// Returns the number of days since 1970-01-01.
+    pub fn days(&self) -> i32 {
+        self.days
+    }
+
+    /// Returns the date's year, month and day.
+    pub fn ymd(&self) -> (i64, u32, u32) {
+        civil_from_days(self.days as i64)
+    }
+
+    pub fn checked_add_days(&self, days: i64) -> Result<Date> {
+        let days = (self.days as i64)
+            .checked_add(days)
+            .ok_or(Error::OverflowError)?;
+        let (year, _, _) = civil_from_days(days);
+        match (MIN_YEAR..=MAX_YEAR).contains(&year) {
+            true => Ok(Date { days: days as i32 }),
+            false => Err(Error::OverflowError),
+        }
+    }
+
+    /// Adds calendar months to the date. The day is clamped to the length of the resulting month,
+    /// e.g. a month after January 31st is the last day of February.
+    pub fn checked_add_months(&self, months: i64) -> Result<Date> {
+        let (year, month, day) = self.ymd();
+        let month_index = (year * 12 + month as i64 - 1)
+            .checked_add(months)
+            .ok_or(Error::OverflowError)?;
+        let (year, month) = (
+            month_index.div_euclid(12),
+            month_index.rem_euclid(12) as u32 + 1,
+        );
+        if !(MIN_YEAR..=MAX_YEAR).contains(&year) {
+            return Err(Error::OverflowError);
+        }
+        Date::from_ymd(year, month, day.min(days_in_month(year, month)))
+    }
+
+    /// Returns the timestamp at midnight of the date.
+    pub fn to_timestamp(&self) -> Timestamp {
+        Timestamp::from_micros(self.days as i64 * MICROS_PER_DAY)
+    }
+}
+
+impl FromStr for Date {
+    type Err = Error;
+
+    /// Parses a date written as `YYYY-MM-DD`.
+    fn from_str(s: &str) -> Result<Date> {
+        match parse_ymd(s.trim()) {
+            Some((year, month, day)) => Date::from_ymd(year, month, day),
+            None => errinput!("invalid date {s}"),
+        }
+    }
+}
+
+impl std::fmt::Display for Date {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        let (year, month, day) = self.ymd();
+        write!(f, "{year:04}-{month:02}-{day:02}")
+    }
+}
+
+/// A date and time of day without a time zone, with microsecond precision. Stored as the number
+/// of microseconds since 1970-01-01 00:00:00.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub struct Timestamp {
+    micros: i64,
+}
+
+impl Timestamp {
+    pub fn from_micros(micros: i64) -> Timestamp {
+        Timestamp { micros }
+    }
+
+    /// Returns the number of microseconds since 1970-01-01 00:00:00.
+    pub fn micros(&self) -> i64 {
+        self.micros
+    }
+
+    pub fn date(&self) -> Date {
+        Date::from_days(self.micros.div_euclid(MICROS_PER_DAY) as i32)
+    }
+
+    /// Returns the number of microseconds since midnight.
+    pub fn time(&self) -> i64 {
+        self.micros.rem_euclid(MICROS_PER_DAY)
+    }
+
+    /// Adds an interval to the timestamp: first its months, then its days and then its time.
+    pub fn checked_add(&self, interval: &Interval) -> Result<Timestamp> {
+        let date = self
+            .date()
+            .checked_add_months(interval.months as i64)?
+            .checked_add_days(interval.days as i64)?;
+        let micros = (date.days as i64 * MICROS_PER_DAY + self.time())
+            .checked_add(interval.micros)
+            .ok_or(Error::OverflowError)?;
+        let timestamp = Timestamp { micros };
+        // Check that the time didn't move the timestamp out of range.
+        timestamp.date().checked_add_days(0)?;
+        Ok(timestamp)
+    }
+
+    pub fn checked_sub(&self, interval: &Interval) -> Result<Timestamp> {
+        self.checked_add(&interval.checked_neg()?)
+    }
+
+    /// Returns the interval from `other` to this timestamp, in days and time.
+    pub fn since(&self, other: &Timestamp) -> Interval {
+        let micros = self.micros - other.micros;
+        Interval::new(0, (micros / MICROS_PER_DAY) as i32, micros % MICROS_PER_DAY)
+    }
+}
+
+impl FromStr for Timestamp {
+    type Err = Error;
+
+    /// Parses a timestamp written as `YYYY-MM-DD HH:MM:SS.ffffff`. The date and time may also be
+    /// separated by a `T`, the seconds and fraction are optional, and a date alone is midnight.
+    fn from_str(s: &str) -> Result<Timestamp> {
+        let parse = || {
+            let (date, time) = split_date_time(s.trim());
+            let time = match time {
+                Some(time) => parse_time_of_day(time)?,
+                None => 0,
+            };
+            Some((parse_ymd(date)?, time))
+        };
+        match parse() {
+            Some(((year, month, day), time)) => {
+                let date = Date::from_ymd(year, month, day)?;
+                Ok(Timestamp::from_micros(
+                    date.days as i64 * MICROS_PER_DAY + time,
+                ))
+            }
+            None => errinput!("invalid timestamp {s}"),
+        }
+    }
+}
+
+impl std::fmt::Display for Timestamp {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "{} ", self.date())?;
+        write_time(f, self.time() as u64)
+    }
+}
+
+/// A point in time along with the UTC offset it was given in, with microsecond precision.
+/// Timestamps with time zone compare by the point in time, regardless of their offsets.
+#[derive(Clone, Copy, Debug, Serialize, Deserialize)]
+pub struct TimestampTz {
+    /// The UTC time.
+    utc: Timestamp,
+    /// The offset from UTC in seconds, positive east of Greenwich.
+    offset: i32,
+}
+
+impl TimestampTz {
+    pub fn new(utc: Timestamp, offset: i32) -> Result<TimestampTz> {
+        if offset.abs() > MAX_OFFSET_SECONDS {
+            return errinput!("invalid UTC offset of {offset} seconds");
+        }
+        Ok(TimestampTz { utc, offset })
+    }
+
+    pub fn utc(&self) -> Timestamp {
+        self.utc
+    }
+
+    pub fn offset(&self) -> i32 {
+        self.offset
+    }
+
+    /// Returns the time at the timestamp's UTC offset.
+    pub fn local(&self) -> Timestamp {
+        Timestamp::from_micros(self.utc.micros + self.offset as i64 * MICROS_PER_SECOND)
+    }
+
+    /// Adds an interval to the timestamp at its UTC offset, see [`Timestamp::checked_add`].
+    pub fn checked_add(&self, interval: &Interval) -> Result<TimestampTz> {
+        let local = self.local().checked_add(interval)?;
+        let utc = Timestamp::from_micros(local.micros - self.offset as i64 * MICROS_PER_SECOND);
+        Ok(TimestampTz {
+            utc,
+            offset: self.offset,
+        })
+    }
+
+    pub fn checked_sub(&self, interval: &Interval) -> Result<TimestampTz> {
+        self.checked_add(&interval.checked_neg()?)
+    }
+
+    /// Returns the interval from `other` to this timestamp, in days and time.
+    pub fn since(&self, other: &TimestampTz) -> Interval {
+        self.utc.since(&other.utc)
+    }
+
+    pub fn to_bytes(&self) -> [u8; 12] {
+        let mut bytes = [0; 12];
+        bytes[..8].copy_from_slice(&self.utc.micros.to_le_bytes());
+        bytes[8..].copy_from_slice(&self.offset.to_le_bytes());
+        bytes
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> TimestampTz {
+        TimestampTz {
+            utc: Timestamp::from_micros(i64::from_le_bytes(bytes[..8].try_into().unwrap())),
+            offset: i32::from_le_bytes(bytes[8..12].try_into().unwrap()),
+        }
+    }
+}
+
+impl PartialEq for TimestampTz {
+    fn eq(&self, other: &TimestampTz) -> bool {
+        self.utc == other.utc
+    }
+}
+
+impl Eq for TimestampTz {}
+
+impl Hash for TimestampTz {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.utc.hash(state);
+    }
+}
+
+impl Ord for TimestampTz {
+    fn cmp(&self, other: &TimestampTz) -> Ordering {
+        self.utc.cmp(&other.utc)
+    }
+}
+
+impl PartialOrd for TimestampTz {
+    fn partial_cmp(&self, other: &TimestampTz) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl FromStr for TimestampTz {
+    type Err = Error;
+
+    /// Parses a timestamp as for [`Timestamp`], followed by a UTC offset written as `Z`, `+HH`,
+    /// `+HH:MM` or `+HHMM`. A timestamp without an offset is in UTC.
+    fn from_str(s: &str) -> Result<TimestampTz> {
+        let parse = || {
+            let (date, time) = split_date_time(s.trim());
+            let (time, offset) = match time {
+                Some(time) => {
+                    let (time, offset) = split_offset(time)?;
+                    (parse_time_of_day(time)?, offset)
+                }
+                None => (0, 0),
+            };
+            Some((parse_ymd(date)?, time, offset))
+        };
+        match parse() {
+            Some(((year, month, day), time, offset)) => {
+                let date = Date::from_ymd(year, month, day)?;
+                let local = date.days as i64 * MICROS_PER_DAY + time;
+                let utc = Timestamp::from_micros(local - offset as i64 * MICROS_PER_SECOND);
+                TimestampTz::new(utc, offset)
+            }
+            None => errinput!("invalid timestamp {s}"),
+        }
+    }
+}
+
+impl std::fmt::Display for TimestampTz {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        let sign = if self.offset < 0 { '-' } else { '+' };
+        let offset = self.offset.unsigned_abs();
+        write!(f, "{}", self.local())?;
+        write!(f, "{sign}{:02}:{:02}", offset / 3600, offset % 3600 / 60)
+    }
+}
+
+/// A span of time in months, days and microseconds. These are kept apart because the length of
+/// a month or day depends on the date an interval is added to.
+///
+/// Intervals compare as if months have 30 days, so e.g. `1 mon` and `30 days` are equal.
+#[derive(Clone, Copy, Debug, Serialize, Deserialize)]
+pub struct Interval {
+    months: i32,
+    days: i32,
+    micros: i64,
+}
+
+impl Interval {
+    pub fn new(months: i32, days: i32, micros: i64) -> Interval {
+        Interval {
+            months,
+            days,
+            micros,
+        }
+    }
+
+    pub fn months(&self) -> i32 {
+        self.months
+    }
+
+    pub fn days(&self) -> i32 {
+        self.days
+    }
+
+    pub fn micros(&self) -> i64 {
+        self.micros
+    }
+
+    pub fn checked_add(&self, other: &Interval) -> Result<Interval> {
+        Ok(Interval {
+            months: self
+                .months
+                .checked_add(other.months)
+                .ok_or(Error::OverflowError)?,
+            days: self
+                .days
+                .checked_add(other.days)
+                .ok_or(Error::OverflowError)?,
+            micros: self
+                .micros
+                .checked_add(other.micros)
+                .ok_or(Error::OverflowError)?,
+        })
+    }
+
+    pub fn checked_sub(&self, other: &Interval) -> Result<Interval> {
+        self.checked_add(&other.checked_neg()?)
+    }
+
+    pub fn checked_neg(&self) -> Result<Interval> {
+        Ok(Interval {
+            months: self.months.checked_neg().ok_or(Error::OverflowError)?,
+            days: self.days.checked_neg().ok_or(Error::OverflowError)?,
+            micros: self.micros.checked_neg().ok_or(Error::OverflowError)?,
+        })
+    }
+
+    /// Returns the length of the interval in microseconds, counting a month as 30 days.
+    pub(crate) fn total_micros(&self) -> i128 {
+        let days = self.months as i128 * DAYS_PER_MONTH as i128 + self.days as i128;
+        days * MICROS_PER_DAY as i128 + self.micros as i128
+    }
+
+    /// Returns an interval of the given length in microseconds, split into as many 30-day months
+    /// and then days as fit, or `None` if it doesn't fit.
+    pub(crate) fn from_total_micros(total: i128) -> Option<Interval> {
+        let day = MICROS_PER_DAY as i128;
+        let month = DAYS_PER_MONTH as i128 * day;
+        Some(Interval {
+            months: i32::try_from(total / month).ok()?,
+            days: (total % month / day) as i32,
+            micros: (total % day) as i64,
+        })
+    }
+
+    pub fn to_bytes(&self) -> [u8; 16] {
+        let mut bytes = [0; 16];
+        bytes[..4].copy_from_slice(&self.months.to_le_bytes());
+        bytes[4..8].copy_from_slice(&self.days.to_le_bytes());
+        bytes[8..].copy_from_slice(&self.micros.to_le_bytes());
+        bytes
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Interval {
+        Interval {
+            months: i32::from_le_bytes(bytes[..4].try_into().unwrap()),
+            days: i32::from_le_bytes(bytes[4..8].try_into().unwrap()),
+            micros: i64::from_le_bytes(bytes[8..16].try_into().unwrap()),
+        }
+    }
+}
+
+impl PartialEq for Interval {
+    fn eq(&self, other: &Interval) -> bool {
+        self.total_micros() == other.total_micros()
+    }
+}
+
+impl Eq for Interval {}
+
+impl Hash for Interval {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.total_micros().hash(state);
+    }
+}
+
+impl Ord for Interval {
+    fn cmp(&self, other: &Interval) -> Ordering {
+        self.total_micros().cmp(&other.total_micros())
+    }
+}
+
+impl PartialOrd for Interval {
+    fn partial_cmp(&self, other: &Interval) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl FromStr for Interval {
+    type Err = Error;
+
+    /// Parses an interval written as quantities and units, optionally followed by a time, e.g.
+    /// `1 year 2 mons -3 days 04:05:06.5`. The units are years, months (or mons), weeks, days,
+    /// hours, minutes, seconds and microseconds, in singular or plural.
+    fn from_str(s: &str) -> Result<Interval> {
+        let parse = || {
+            let (mut months, mut days, mut micros) = (0_i64, 0_i64, 0_i64);
+            let mut tokens = s.split_whitespace().peekable();
+            tokens.peek()?;
+            while let Some(token) = tokens.next() {
+                if token.contains(':') {
+                    let (sign, time) = match token.strip_prefix('-') {
+                        Some(time) => (-1, time),
+                        None => (1, token),
+                    };
+                    micros = micros.checked_add(sign * parse_time(time)?)?;
+                    continue;
+                }
+                let quantity: i64 = token.parse().ok()?;
+                let (total, unit) = match tokens.next()? {
+                    "year" | "years" => (&mut months, 12),
+                    "mon" | "mons" | "month" | "months" => (&mut months, 1),
+                    "week" | "weeks" => (&mut days, 7),
+                    "day" | "days" => (&mut days, 1),
+                    "hour" | "hours" => (&mut micros, 3600 * MICROS_PER_SECOND),
+                    "minute" | "minutes" | "min" | "mins" => (&mut micros, 60 * MICROS_PER_SECOND),
+                    "second" | "seconds" | "sec" | "secs" => (&mut micros, MICROS_PER_SECOND),
+                    "microsecond" | "microseconds" => (&mut micros, 1),
+                    _ => return None,
+                };
+                *total = total.checked_add(quantity.checked_mul(unit)?)?;
+            }
+            Some(Interval::new(
+                months.try_into().ok()?,
+                days.try_into().ok()?,
+                micros,
+            ))
+        };
+        match parse() {
+            Some(interval) => Ok(interval),
+            None => errinput!("invalid interval {s}"),
+        }
+    }
+}
+
+impl std::fmt::Display for Interval {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        let plural = |n: i32, unit: &str, units: &str| match n {
+            1 => format!("{n} {unit}"),
+            _ => format!("{n} {units}"),
+        };
+        let mut parts = Vec::new();
+        if self.months / 12 != 0 {
+            parts.push(plural(self.months / 12, "year", "years"));
+        }
+        if self.months % 12 != 0 {
+            parts.push(plural(self.months % 12, "mon", "mons"));
+        }
+        if self.days != 0 {
+            parts.push(plural(self.days, "day", "days"));
+        }
+        write!(f, "{}", parts.join(" "))?;
+        if self.micros != 0 || parts.is_empty() {
+            if !parts.is_empty() {
+                write!(f, " ")?;
+            }
+            if self.micros < 0 {
+                write!(f, "-")?;
+            }
+            write_time(f, self.micros.unsigned_abs())?;
+        }
+        Ok(())
+    }
+}
+
+/// Writes a time of `micros` microseconds as `HH:MM:SS`, followed by the fraction of a second
+/// without trailing zeroes, if any.
+fn write_time(f: &mut std::fmt::Formatter, micros: u64) -> std::fmt::Result {
+    let seconds = micros / MICROS_PER_SECOND as u64;
+    write!(
+        f,
+        "{:02}:{:02}:{:02}",
+        seconds / 3600,
+        seconds % 3600 / 60,
+        seconds % 60
+    )?;
+    match micros % MICROS_PER_SECOND as u64 {
+        0 => Ok(()),
+        fraction => write!(f, ".{}", format!("{fraction:06}").trim_end_matches('0')),
+    }
+}
+
+/// Splits a timestamp into its date and, if given, time.
+fn split_date_time(s: &str) -> (&str, Option<&str>) {
+    match s.split_once([' ', 'T']) {
+        Some((date, time)) => (date, Some(time.trim())),
+        None => (s, None),
+    }
+}
+
+/// Splits a time into the time itself and its UTC offset in seconds, which is 0 if not given.
+fn split_offset(time: &str) -> Option<(&str, i32)> {
+    if let Some(time) = time.strip_suffix('Z') {
+        return Some((time, 0));
+    }
+    let Some(index) = time.rfind(['+', '-']) else {
+        return Some((time, 0));
+    };
+    let (time, offset) = time.split_at(index);
+    let sign = if offset.starts_with('-') { -1 } else { 1 };
+    let offset = &offset[1..];
+    let (hours, minutes) = match offset.split_once(':') {
+        Some((hours, minutes)) => (hours, minutes),
+        None if offset.len() == 4 => offset.split_at(2),
+        None => (offset, "00"),
+    };
+    let hours: i32 = parse_digits(hours, 1..=2)?;
+    let minutes: i32 = parse_digits(minutes, 2..=2)?;
+    if minutes >= 60 {
+        return None;
+    }
+    Some((time.trim(), sign * (hours * 3600 + minutes * 60)))
+}
+
+/// Parses a date written as `YYYY-MM-DD`, without checking that it exists.
+fn parse_ymd(s: &str) -> Option<(i64, u32, u32)> {
+    let mut parts = s.split('-');
+    let year = parse_digits(parts.next()?, 4..=4)?;
+    let month = parse_digits(parts.next()?, 2..=2)?;
+    let day = parse_digits(parts.next()?, 2..=2)?;
+    match parts.next() {
+        Some(_) => None,
+        None => Some((year, month, day)),
+    }
+}
+
+/// Parses a time of day, see [`parse_time`].
+fn parse_time_of_day(s: &str) -> Option<i64> {
+    parse_time(s).filter(|micros| *micros < MICROS_PER_DAY)
+}
+
+/// Parses a time written as `HH:MM`, `HH:MM:SS` or `HH:MM:SS.ffffff` into microseconds. The hours
+/// aren't limited to a day.
+fn parse_time(s: &str) -> Option<i64> {
+    let (time, fraction) = s.split_once('.').unwrap_or((s, ""));
+    let mut parts = time.split(':');
+    let hours: i64 = parse_digits(parts.next()?, 1..=9)?;
+    let minutes: i64 = parse_digits(parts.next()?, 2..=2)?;
+    let seconds: i64 = match parts.next() {
+        Some(seconds) => parse_digits(seconds, 2..=2)?,
+        None if fraction.is_empty() => 0,
+        None => return None,
+    };
+    let fraction: i64 = match fraction {
+        "" => 0,
+        fraction => parse_digits::<i64>(fraction, 1..=6)? * 10_i64.pow(6 - fraction.len() as u32),
+    };
+    if parts.next().is_some() || minutes >= 60 || seconds >= 60 {
+        return None;
+    }
+    Some(((hours * 60 + minutes) * 60 + seconds) * MICROS_PER_SECOND + fraction)
+}
+
+/// Parses a number of ASCII digits, as many as `len` allows.
+fn parse_digits<T: FromStr>(s: &str, len: std::ops::RangeInclusive<usize>) -> Option<T> {
+    match len.contains(&s.len()) && s.bytes().all(|b| b.is_ascii_digit()) {
+        true => s.parse().ok(),
+        false => None,
+    }
+}
+
+fn is_leap_year(year: i64) -> bool {
+    year % 4 == 0 && (year % 100 != 0 || year % 400 == 0)
+}
+
+fn days_in_month(year: i64, month: u32) -> u32 {
+    match month {
+        2 if is_leap_year(year) => 29,
+        2 => 28,
+        4 | 6 | 9 | 11 => 30,
+        _ => 31,
+    }
+}
+
+/// Returns the number of days from 1970-01-01 to the given date in the proleptic Gregorian
+/// calendar. See http://howardhinnant.github.io/date_algorithms.html#days_from_civil.
+fn days_from_civil(year: i64, month: u32, day: u32) -> i64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let year_of_era = year - era * 400;
+    let month = month as i64;
+    let day_of_year =
+        (153 * (if month > 2 { month - 3 } else { month + 9 }) + 2) / 5 + day as i64 - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    era * 146_097 + day_of_era - 719_468
+}
+
+/// The inverse of [`days_from_civil`].
+fn civil_from_days(days: i64) -> (i64, u32, u32) {
+    let days = days + 719_468;
+    let era = days.div_euclid(146_097);
+    let day_of_era = days - era * 146_097;
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let shifted_month = (5 * day_of_year + 2) / 153;
+    let day = (day_of_year - (153 * shifted_month + 2) / 5 + 1) as u32;
+    let month = if shifted_month < 10 {
+        shifted_month + 3
+    } else {
+        shifted_month - 9
+    } as u32;
+    let year = year_of_era + era * 400;
+    (if month <= 2 { year + 1 } else { year }, month, day)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_date_round_trip() {
+        for s in [
+            "1970-01-01",
+            "2000-02-29",
+            "0001-01-01",
+            "9999-12-31",
+            "1969-12-31",
+        ] {
+            let date: Date = s.parse().unwrap();
+            assert_eq!(date.to_string(), s);
+        }
+        assert_eq!("1970-01-02".parse::<Date>().unwrap().days(), 1);
+        assert_eq!("1969-12-31".parse::<Date>().unwrap().days(), -1);
+        assert!("2023-02-29".parse::<Date>().is_err());
+        assert!("2023-1-01".parse::<Date>().is_err());
+        assert!("10000-01-01".parse::<Date>().is_err());
+    }
+
+    #[test]
+    fn test_timestamp_round_trip() {
+        let timestamp: Timestamp = "2024-03-01 12:34:56.5".parse().unwrap();
+        assert_eq!(timestamp.to_string(), "2024-03-01 12:34:56.5");
+        assert_eq!(
+            "2024-03-01T00:00".parse::<Timestamp>().unwrap(),
+            "2024-03-01".parse::<Timestamp>().unwrap()
+        );
+        assert!("2024-03-01 24:00:00".parse::<Timestamp>().is_err());
+        assert!("2024-03-01 12:00:00.1234567".parse::<Timestamp>().is_err());
+
+        let tz: TimestampTz = "2024-03-01 12:00:00+02:00".parse().unwrap();
+        assert_eq!(tz.to_string(), "2024-03-01 12:00:00+02:00");
+        assert_eq!(tz.utc().to_string(), "2024-03-01 10:00:00");
+        assert_eq!(tz, "2024-03-01 10:00:00Z".parse().unwrap());
+        assert_eq!(tz, TimestampTz::from_bytes(&tz.to_bytes()));
+        assert_eq!(
+            tz.offset(),
+            TimestampTz::from_bytes(&tz.to_bytes()).offset()
+        );
+        assert!("2024-03-01 12:00:00+19:00".parse::<TimestampTz>().is_err());
+    }
+
+    #[test]
+    fn test_interval_round_trip() {
+        for s in [
+            "1 year 2 mons 3 days 04:05:06.5",
+            "-1 days",
+            "00:00:00",
+            "-36:00:00",
+        ] {
+            let interval: Interval = s.parse().unwrap();
+            assert_eq!(interval.to_string(), s);
+        }
+        let interval: Interval = "2 weeks 3 hours 1 minute".parse().unwrap();
+        assert_eq!(interval.to_string(), "14 days 03:01:00");
+        assert_eq!(interval, Interval::from_bytes(&interval.to_bytes()));
+        assert_eq!("1 mon".parse::<Interval>(), "30 days".parse::<Interval>());
+        assert!("1 fortnight".parse::<Interval>().is_err());
+        assert!("".parse::<Interval>().is_err());
+    }
+
+    #[test]
+    fn test_timestamp_arithmetic() {
+        let timestamp: Timestamp = "2024-01-31 23:00:00".parse().unwrap();
+        let interval: Interval = "1 mon 2 hours".parse().unwrap();
+        assert_eq!(
+            timestamp.checked_add(&interval).unwrap().to_string(),
+            "2024-03-01 01:00:00"
+        );
+        assert_eq!(
+            timestamp.checked_sub(&interval).unwrap().to_string(),
+            "2023-12-31 21:00:00"
+        );
+
+        let later: Timestamp = "2024-02-02 01:30:00".parse().unwrap();
+        assert_eq!(later.since(&timestamp).to_string(), "1 day 02:30:00");
+        assert_eq!(timestamp.since(&later).to_string(), "-1 days -02:30:00");
+
+        let end: Timestamp = "9999-12-31 23:00:00".parse().unwrap();
+        assert_eq!(end.checked_add(&interval), Err(Error::OverflowError));
+    }
+}
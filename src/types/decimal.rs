@@ -0,0 +1,230 @@
+use crate::common::{Error, Result};
+use crate::errinput;
+use serde::{Deserialize, Serialize};
+use std::cmp::Ordering;
+use std::hash::{Hash, Hasher};
+
+/// The maximum number of significant digits of a decimal, which is what fits in an i128.
+pub const MAX_DECIMAL_PRECISION: u8 = 38;
+
+/// The number of fractional digits kept when dividing decimals, unless the operands have more.
+const DIVISION_SCALE: u8 = 6;
+
+/// An exact fixed-point number, stored as an unscaled integer `value` and the number of digits
+/// `scale` after the decimal point, i.e. the number is `value * 10^-scale`.
+///
+/// Decimals compare by their numeric value, so e.g. `1.5` and `1.50` are equal.
+#[derive(Clone, Copy, Debug, Serialize, Deserialize)]
+pub struct Decimal {
+    value: i128,
+    scale: u8,
+}
+
+impl Decimal {
+    pub fn new(value: i128, scale: u8) -> Decimal {
+        Decimal { value, scale }
+    }
+
+    /// Returns the unscaled value.
+    pub fn value(&self) -> i128 {
+        self.value
+    }
+
+    pub fn scale(&self) -> u8 {
+        self.scale
+    }
+
+    /// Returns the number of significant digits of the decimal, which is never less than its
+    /// scale, i.e. `0.05` has a precision of 2.
+    pub fn precision(&self) -> u8 {
+        let digits = match self.value.unsigned_abs() {
+            0 => 1,
+            value => value.ilog10() as u8 + 1,
+        };
+        digits.max(self.scale)
+    }
+
+    /// Returns the decimal with `scale` fractional digits, rounding half away from zero if digits
+    /// are dropped. Returns `None` if the result doesn't fit.
+    pub fn rescale(&self, scale: u8) -> Option<Decimal> {
+        let value = match scale.cmp(&self.scale) {
+            Ordering::Equal => self.value,
+            Ordering::Greater => self
+                .value
+                .checked_mul(10_i128.checked_pow((scale - self.scale) as u32)?)?,
+            Ordering::Less => {
+                let divisor = 10_i128.checked_pow((self.scale - scale) as u32)?;
+                let (quotient, remainder) = (self.value / divisor, self.value % divisor);
+                match remainder.unsigned_abs() * 2 >= divisor.unsigned_abs() {
+                    true => quotient + self.value.signum(),
+                    false => quotient,
+                }
+            }
+        };
+        Some(Decimal { value, scale })
+    }
+
+    /// Returns the decimal with the given scale if it has at most `precision` significant digits
+    /// at that scale, as required by a `DECIMAL(precision, scale)` column.
+    pub fn fit(&self, precision: u8, scale: u8) -> Result<Decimal> {
+        match self.rescale(scale) {
+            Some(decimal) if decimal.precision() <= precision => Ok(decimal),
+            _ => errinput!("decimal {self} doesn't fit in decimal({precision},{scale})"),
+        }
+    }
+
+    pub fn to_f64(&self) -> f64 {
+        self.value as f64 / 10_f64.powi(self.scale as i32)
+    }
+
+    pub fn checked_add(&self, other: &Decimal) -> Result<Decimal> {
+        let (lhs, rhs, scale) = Self::align(self, other)?;
+        let value = lhs.checked_add(rhs).ok_or(Error::OverflowError)?;
+        Ok(Decimal { value, scale })
+    }
+
+    pub fn checked_sub(&self, other: &Decimal) -> Result<Decimal> {
+        let (lhs, rhs, scale) = Self::align(self, other)?;
+        let value = lhs.checked_sub(rhs).ok_or(Error::OverflowError)?;
+        Ok(Decimal { value, scale })
+    }
+
+    pub fn checked_mul(&self, other: &Decimal) -> Result<Decimal> {
+        let value = self
+            .value
+            .checked_mul(other.value)
+            .ok_or(Error::OverflowError)?;
+        let product = Decimal::new(value, self.scale + other.scale);
+        match product.scale > MAX_DECIMAL_PRECISION {
+            true => product
+                .rescale(MAX_DECIMAL_PRECISION)
+                .ok_or(Error::OverflowError),
+            false => Ok(product),
+        }
+    }
+
+    /// Divides the decimals, keeping at least [`DIVISION_SCALE`] fractional digits.
+    pub fn checked_div(&self, other: &Decimal) -> Result<Decimal> {
+        if other.value == 0 {
+            return Err(Error::OverflowError);
+        }
+        let scale = self.scale.max(other.scale).max(DIVISION_SCALE);
+        // Compute one extra digit, which is rounded away.
+        let shift = (scale + 1 + other.scale) as i32 - self.scale as i32;
+        let dividend = self
+            .value
+            .checked_mul(
+                10_i128
+                    .checked_pow(shift as u32)
+                    .ok_or(Error::OverflowError)?,
+            )
+            .ok_or(Error::OverflowError)?;
+        Decimal::new(dividend / other.value, scale + 1)
+            .rescale(scale)
+            .ok_or(Error::OverflowError)
+    }
+
+    pub fn checked_rem(&self, other: &Decimal) -> Result<Decimal> {
+        let (lhs, rhs, scale) = Self::align(self, other)?;
+        let value = lhs.checked_rem(rhs).ok_or(Error::OverflowError)?;
+        Ok(Decimal { value, scale })
+    }
+
+    /// Returns the unscaled values of both decimals at their common scale.
+    fn align(lhs: &Decimal, rhs: &Decimal) -> Result<(i128, i128, u8)> {
+        let scale = lhs.scale.max(rhs.scale);
+        let lhs = lhs.rescale(scale).ok_or(Error::OverflowError)?;
+        let rhs = rhs.rescale(scale).ok_or(Error::OverflowError)?;
+        Ok((lhs.value, rhs.value, scale))
+    }
+
+    /// Returns the decimal with trailing fractional zeroes removed, which is the same for all
+    /// decimals of equal value.
+    fn normalize(&self) -> Decimal {
+        let mut decimal = *self;
+        while decimal.scale > 0 && decimal.value % 10 == 0 {
+            decimal.value /= 10;
+            decimal.scale -= 1;
+        }
+        decimal
+    }
+}
+
+impl PartialEq for Decimal {
+    fn eq(&self, other: &Decimal) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for Decimal {}
+
+impl Hash for Decimal {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        let normalized = self.normalize();
+        normalized.value.hash(state);
+        normalized.scale.hash(state);
+    }
+}
+
+impl Ord for Decimal {
+    fn cmp(&self, other: &Decimal) -> Ordering {
+        match Self::align(self, other) {
+            Ok((lhs, rhs, _)) => lhs.cmp(&rhs),
+            // Scaling up overflowed, so the decimal with the smaller scale is larger in magnitude.
+            Err(_) => match self.scale < other.scale {
+                true => self.value.signum().cmp(&0),
+                false => 0.cmp(&other.value.signum()),
+            },
+        }
+    }
+}
+
+impl PartialOrd for Decimal {
+    fn partial_cmp(&self, other: &Decimal) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl From<i32> for Decimal {
+    fn from(v: i32) -> Self {
+        Decimal::new(v as i128, 0)
+    }
+}
+
+impl From<i64> for Decimal {
+    fn from(v: i64) -> Self {
+        Decimal::new(v as i128, 0)
+    }
+}
+
+impl std::str::FromStr for Decimal {
+    type Err = Error;
+
+    /// Parses a decimal written as e.g. `-12.340`, keeping the number of fractional digits given.
+    fn from_str(s: &str) -> Result<Decimal> {
+        let (integer, fraction) = s.split_once('.').unwrap_or((s, ""));
+        let digits = format!("{integer}{fraction}");
+        let scale = fraction.len();
+        if scale > MAX_DECIMAL_PRECISION as usize || !fraction.chars().all(|c| c.is_ascii_digit()) {
+            return errinput!("invalid decimal {s}");
+        }
+        match digits.parse::<i128>() {
+            Ok(value) => Ok(Decimal::new(value, scale as u8)),
+            Err(_) => errinput!("invalid decimal {s}"),
+        }
+    }
+}
+
+impl std::fmt::Display for Decimal {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        let sign = if self.value < 0 { "-" } else { "" };
+        let digits = self.value.unsigned_abs().to_string();
+        let scale = self.scale as usize;
+        if scale == 0 {
+            return write!(f, "{sign}{digits}");
+        }
+        let digits = format!("{digits:0>width$}", width = scale + 1);
+        let (integer, fraction) = digits.split_at(digits.len() - scale);
+        write!(f, "{sign}{integer}.{fraction}")
+    }
+}
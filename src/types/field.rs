@@ -1,6 +1,7 @@
 use crate::common::{Error, Result};
-use crate::errinput;
-use crate::types::DataType;
+use crate::types::binary::encode_hex;
+use crate::types::{DataType, Date, Decimal, Interval, Json, Timestamp, TimestampTz, Uuid};
+use crate::{errdata, errinput};
 use serde::{Deserialize, Serialize};
 use std::ops::{Add, Div, Mul, Rem, Sub};
 
@@ -9,8 +10,18 @@ pub enum Field {
     Null,
     Boolean(bool),
     Integer(i32),
+    BigInt(i64),
     Float(f32),
+    Double(f64),
+    Decimal(Decimal),
+    Date(Date),
+    Timestamp(Timestamp),
+    TimestampTz(TimestampTz),
+    Interval(Interval),
+    Uuid(Uuid),
     String(String),
+    Blob(Vec<u8>),
+    Json(Json),
 }
 
 impl PartialEq for Field {
@@ -28,15 +39,55 @@ impl PartialEq for Field {
                 Field::Integer(i2) => i == i2,
                 _ => false,
             },
+            Field::BigInt(i) => match other {
+                Field::BigInt(i2) => i == i2,
+                _ => false,
+            },
             // match on NaN as well as equality
             Field::Float(f) => match other {
                 Field::Float(f2) => (f == f2) || (f.is_nan() && f2.is_nan()),
                 _ => false,
             },
+            Field::Double(f) => match other {
+                Field::Double(f2) => (f == f2) || (f.is_nan() && f2.is_nan()),
+                _ => false,
+            },
+            Field::Decimal(d) => match other {
+                Field::Decimal(d2) => d == d2,
+                _ => false,
+            },
+            Field::Date(d) => match other {
+                Field::Date(d2) => d == d2,
+                _ => false,
+            },
+            Field::Timestamp(t) => match other {
+                Field::Timestamp(t2) => t == t2,
+                _ => false,
+            },
+            Field::TimestampTz(t) => match other {
+                Field::TimestampTz(t2) => t == t2,
+                _ => false,
+            },
+            Field::Interval(i) => match other {
+                Field::Interval(i2) => i == i2,
+                _ => false,
+            },
+            Field::Uuid(u) => match other {
+                Field::Uuid(u2) => u == u2,
+                _ => false,
+            },
             Field::String(s) => match other {
                 Field::String(s2) => s == s2,
                 _ => false,
             },
+            Field::Blob(b) => match other {
+                Field::Blob(b2) => b == b2,
+                _ => false,
+            },
+            Field::Json(j) => match other {
+                Field::Json(j2) => j == j2,
+                _ => false,
+            },
         }
     }
 }
@@ -49,6 +100,7 @@ impl std::hash::Hash for Field {
             Field::Null => 0.hash(state),
             Field::Boolean(b) => b.hash(state),
             Field::Integer(i) => i.hash(state),
+            Field::BigInt(i) => i.hash(state),
             Field::Float(f) => {
                 if f.is_nan() {
                     0.hash(state);
@@ -56,12 +108,27 @@ impl std::hash::Hash for Field {
                     f.to_bits().hash(state);
                 }
             }
+            Field::Double(f) => {
+                if f.is_nan() {
+                    0.hash(state);
+                } else {
+                    f.to_bits().hash(state);
+                }
+            }
+            Field::Decimal(d) => d.hash(state),
+            Field::Date(d) => d.hash(state),
+            Field::Timestamp(t) => t.hash(state),
+            Field::TimestampTz(t) => t.hash(state),
+            Field::Interval(i) => i.hash(state),
+            Field::Uuid(u) => u.hash(state),
             Field::String(s) => s.hash(state),
+            Field::Blob(b) => b.hash(state),
+            Field::Json(j) => j.hash(state),
         }
     }
 }
 
-// for use in sorting
+// for use in sorting. SQL comparisons, where NULL isn't comparable, are in the logic module.
 impl Ord for Field {
     fn cmp(&self, other: &Self) -> std::cmp::Ordering {
         match (self, other) {
@@ -70,6 +137,7 @@ impl Ord for Field {
             (_, Field::Null) => std::cmp::Ordering::Greater,
             (Field::Boolean(b), Field::Boolean(b2)) => b.cmp(b2),
             (Field::Integer(i), Field::Integer(i2)) => i.cmp(i2),
+            (Field::BigInt(i), Field::BigInt(i2)) => i.cmp(i2),
 
             (Field::Float(f), Field::Float(f2)) => match (f.is_nan(), f2.is_nan()) {
                 (true, true) => std::cmp::Ordering::Equal,
@@ -77,14 +145,23 @@ impl Ord for Field {
                 (false, true) => std::cmp::Ordering::Less,
                 (false, false) => f.partial_cmp(f2).unwrap_or(std::cmp::Ordering::Equal),
             },
+            (Field::Double(f), Field::Double(f2)) => match (f.is_nan(), f2.is_nan()) {
+                (true, true) => std::cmp::Ordering::Equal,
+                (true, false) => std::cmp::Ordering::Greater,
+                (false, true) => std::cmp::Ordering::Less,
+                (false, false) => f.partial_cmp(f2).unwrap_or(std::cmp::Ordering::Equal),
+            },
+            (Field::Decimal(d), Field::Decimal(d2)) => d.cmp(d2),
+            (Field::Date(d), Field::Date(d2)) => d.cmp(d2),
+            (Field::Timestamp(t), Field::Timestamp(t2)) => t.cmp(t2),
+            (Field::TimestampTz(t), Field::TimestampTz(t2)) => t.cmp(t2),
+            (Field::Interval(i), Field::Interval(i2)) => i.cmp(i2),
+            (Field::Uuid(u), Field::Uuid(u2)) => u.cmp(u2),
             (Field::String(s), Field::String(s2)) => s.cmp(s2),
-            (Field::Boolean(_), _) => std::cmp::Ordering::Less,
-            (Field::Integer(_), Field::Boolean(_)) => std::cmp::Ordering::Greater,
-            (Field::Integer(_), _) => std::cmp::Ordering::Less,
-            (Field::Float(_), Field::Boolean(_)) => std::cmp::Ordering::Greater,
-            (Field::Float(_), Field::Integer(_)) => std::cmp::Ordering::Greater,
-            (Field::Float(_), _) => std::cmp::Ordering::Less,
-            (Field::String(_), _) => std::cmp::Ordering::Greater,
+            (Field::Blob(b), Field::Blob(b2)) => b.cmp(b2),
+            (Field::Json(j), Field::Json(j2)) => j.cmp(j2),
+            // Values of different types are ordered by type.
+            (lhs, rhs) => lhs.type_rank().cmp(&rhs.type_rank()),
         }
     }
 }
@@ -95,48 +172,48 @@ impl PartialOrd for Field {
     }
 }
 
+/// Fails if the values can't be added, see [`Field::checked_add`].
 impl Add for Field {
-    type Output = Self;
+    type Output = Result<Self>;
 
-    fn add(self, other: Self) -> Self {
-        let tmp = self.checked_add(&other);
-        tmp.unwrap_or_else(|_e| Field::Null)
+    fn add(self, other: Self) -> Result<Self> {
+        self.checked_add(&other)
     }
 }
 
+/// Fails if the values can't be subtracted, see [`Field::checked_sub`].
 impl Sub for Field {
-    type Output = Self;
+    type Output = Result<Self>;
 
-    fn sub(self, other: Self) -> Self {
-        let tmp = self.checked_sub(&other);
-        tmp.unwrap_or_else(|_e| Field::Null)
+    fn sub(self, other: Self) -> Result<Self> {
+        self.checked_sub(&other)
     }
 }
 
+/// Fails if the values can't be multiplied, see [`Field::checked_mul`].
 impl Mul for Field {
-    type Output = Self;
+    type Output = Result<Self>;
 
-    fn mul(self, other: Self) -> Self {
-        let tmp = self.checked_mul(&other);
-        tmp.unwrap_or_else(|_e| Field::Null)
+    fn mul(self, other: Self) -> Result<Self> {
+        self.checked_mul(&other)
     }
 }
 
+/// Fails if the values can't be divided, see [`Field::checked_div`].
 impl Div for Field {
-    type Output = Self;
+    type Output = Result<Self>;
 
-    fn div(self, other: Self) -> Self {
-        let tmp = self.checked_div(&other);
-        tmp.unwrap_or_else(|_e| Field::Null)
+    fn div(self, other: Self) -> Result<Self> {
+        self.checked_div(&other)
     }
 }
 
+/// Fails if the values can't be divided, see [`Field::checked_mod`].
 impl Rem for Field {
-    type Output = Self;
+    type Output = Result<Self>;
 
-    fn rem(self, other: Self) -> Self {
-        let tmp = self.checked_mod(&other);
-        tmp.unwrap_or_else(|_e| Field::Null)
+    fn rem(self, other: Self) -> Result<Self> {
+        self.checked_mod(&other)
     }
 }
 
@@ -147,8 +224,18 @@ impl std::fmt::Display for Field {
             Self::Boolean(true) => f.write_str("TRUE"),
             Self::Boolean(false) => f.write_str("FALSE"),
             Self::Integer(integer) => integer.fmt(f),
+            Self::BigInt(integer) => integer.fmt(f),
             Self::Float(float) => write!(f, "{float:?}"),
+            Self::Double(double) => write!(f, "{double:?}"),
+            Self::Decimal(decimal) => decimal.fmt(f),
+            Self::Date(date) => date.fmt(f),
+            Self::Timestamp(timestamp) => timestamp.fmt(f),
+            Self::TimestampTz(timestamp) => timestamp.fmt(f),
+            Self::Interval(interval) => interval.fmt(f),
+            Self::Uuid(uuid) => uuid.fmt(f),
             Self::String(string) => write!(f, "'{}'", string.escape_debug()),
+            Self::Blob(bytes) => write!(f, "x'{}'", encode_hex(bytes)),
+            Self::Json(json) => json.fmt(f),
         }
     }
 }
@@ -165,6 +252,60 @@ impl From<i32> for Field {
     }
 }
 
+impl From<i64> for Field {
+    fn from(v: i64) -> Self {
+        Field::BigInt(v)
+    }
+}
+
+impl From<Decimal> for Field {
+    fn from(v: Decimal) -> Self {
+        Field::Decimal(v)
+    }
+}
+
+impl From<Date> for Field {
+    fn from(v: Date) -> Self {
+        Field::Date(v)
+    }
+}
+
+impl From<Timestamp> for Field {
+    fn from(v: Timestamp) -> Self {
+        Field::Timestamp(v)
+    }
+}
+
+impl From<TimestampTz> for Field {
+    fn from(v: TimestampTz) -> Self {
+        Field::TimestampTz(v)
+    }
+}
+
+impl From<Interval> for Field {
+    fn from(v: Interval) -> Self {
+        Field::Interval(v)
+    }
+}
+
+impl From<Uuid> for Field {
+    fn from(v: Uuid) -> Self {
+        Field::Uuid(v)
+    }
+}
+
+impl From<Vec<u8>> for Field {
+    fn from(v: Vec<u8>) -> Self {
+        Field::Blob(v)
+    }
+}
+
+impl From<Json> for Field {
+    fn from(v: Json) -> Self {
+        Field::Json(v)
+    }
+}
+
 impl From<String> for Field {
     fn from(v: String) -> Self {
         Field::String(v)
@@ -189,18 +330,42 @@ impl Field {
         match d {
             DataType::Bool => Field::from(false),
             DataType::Int => Field::from(0i32),
+            DataType::BigInt => Field::from(0i64),
             DataType::Float => Field::from(0.0),
+            DataType::Double => Field::Double(0.0),
+            DataType::Decimal(_, scale) => Field::from(Decimal::new(0, scale)),
+            DataType::Date => Field::from(Date::from_days(0)),
+            DataType::Timestamp => Field::from(Timestamp::from_micros(0)),
+            DataType::TimestampTz => {
+                Field::from(TimestampTz::new(Timestamp::from_micros(0), 0).unwrap())
+            }
+            DataType::Interval => Field::from(Interval::new(0, 0, 0)),
+            DataType::Uuid => Field::from(Uuid::from_bytes([0; 16])),
             DataType::Text => Field::from("".to_string()),
+            DataType::Blob => Field::Blob(Vec::new()),
+            DataType::Json => Field::Json(Json::Null),
             DataType::Invalid => Field::Null,
         }
     }
+    /// Returns the value's type. A decimal's precision is the number of digits it has, rather
+    /// than that of the column it may be stored in.
     pub fn get_type(&self) -> DataType {
         match self {
             Field::Null => DataType::Invalid,
             Field::Boolean(_) => DataType::Bool,
             Field::Integer(_) => DataType::Int,
+            Field::BigInt(_) => DataType::BigInt,
             Field::Float(_) => DataType::Float,
+            Field::Double(_) => DataType::Double,
+            Field::Decimal(d) => DataType::Decimal(d.precision(), d.scale()),
+            Field::Date(_) => DataType::Date,
+            Field::Timestamp(_) => DataType::Timestamp,
+            Field::TimestampTz(_) => DataType::TimestampTz,
+            Field::Interval(_) => DataType::Interval,
+            Field::Uuid(_) => DataType::Uuid,
             Field::String(_) => DataType::Text,
+            Field::Blob(_) => DataType::Blob,
+            Field::Json(_) => DataType::Json,
         }
     }
     // size in bytes
@@ -209,8 +374,18 @@ impl Field {
             Field::Null => 0,
             Field::Boolean(_) => 1,
             Field::Integer(_) => 4,
+            Field::BigInt(_) => 8,
             Field::Float(_) => 4,
+            Field::Double(_) => 8,
+            Field::Decimal(_) => 16,
+            Field::Date(_) => 4,
+            Field::Timestamp(_) => 8,
+            Field::TimestampTz(_) => 12,
+            Field::Interval(_) => 16,
+            Field::Uuid(_) => 16,
             Field::String(s) => s.len() as u16,
+            Field::Blob(b) => b.len() as u16,
+            Field::Json(j) => j.to_bytes().len() as u16,
         }
     }
     pub fn to_string(&self) -> String {
@@ -218,101 +393,72 @@ impl Field {
             Field::Null => "NULL".to_string(),
             Field::Boolean(b) => b.to_string(),
             Field::Integer(i) => i.to_string(),
+            Field::BigInt(i) => i.to_string(),
             Field::Float(f) => f.to_string(),
+            Field::Double(f) => f.to_string(),
+            Field::Decimal(d) => d.to_string(),
+            Field::Date(d) => d.to_string(),
+            Field::Timestamp(t) => t.to_string(),
+            Field::TimestampTz(t) => t.to_string(),
+            Field::Interval(i) => i.to_string(),
+            Field::Uuid(u) => u.to_string(),
             Field::String(s) => s.clone(),
+            Field::Blob(b) => encode_hex(b),
+            Field::Json(j) => j.to_string(),
         }
     }
     pub fn checked_add(&self, other: &Field) -> Result<Field> {
-        use Field::*;
-        match (&self, other) {
-            (Integer(lhs), Integer(rhs)) => match lhs.checked_add(*rhs) {
-                Some(v) => Ok(Integer(v)),
-                None => Result::from(Error::OverflowError),
-            },
-            (Integer(lhs), Float(rhs)) => {
-                let result = (*lhs as f32) + rhs;
-                Ok(Float(result))
-            }
-            (Float(lhs), Integer(rhs)) => Ok(Float(lhs + (*rhs as f32))),
-            (Float(lhs), Float(rhs)) => Ok(Float(lhs + rhs)),
-            (Null, Integer(_)) | (Null, Float(_)) => Ok(Null),
-            (Integer(_), Null) | (Float(_), Null) => Ok(Null),
-            (Null, Null) => Ok(Null),
-            _ => {
-                let msg = format!("Cannot add {:?} and {:?}", self, other);
-                Result::from(Error::InvalidData(msg))
-            }
+        if self.is_temporal() || other.is_temporal() {
+            return self.checked_temporal_add(other, false);
         }
+        self.checked_arithmetic(
+            other,
+            "add",
+            i32::checked_add,
+            i64::checked_add,
+            Decimal::checked_add,
+            |lhs, rhs| lhs + rhs,
+            |lhs, rhs| lhs + rhs,
+        )
     }
 
     pub fn checked_sub(&self, other: &Field) -> Result<Field> {
-        use Field::*;
-        match (&self, other) {
-            (Integer(lhs), Integer(rhs)) => match lhs.checked_sub(*rhs) {
-                Some(v) => Ok(Integer(v)),
-                None => Result::from(Error::OverflowError),
-            },
-            (Integer(lhs), Float(rhs)) => {
-                let result = (*lhs as f32) - rhs;
-                Ok(Float(result))
-            }
-            (Float(lhs), Integer(rhs)) => Ok(Float(lhs - (*rhs as f32))),
-            (Float(lhs), Float(rhs)) => Ok(Float(lhs - rhs)),
-            (Null, Integer(_)) | (Null, Float(_)) => Ok(Null),
-            (Integer(_), Null) | (Float(_), Null) => Ok(Null),
-            (Null, Null) => Ok(Null),
-            _ => {
-                let msg = format!("Cannot subtract {:?} and {:?}", self, other);
-                Result::from(Error::InvalidData(msg))
-            }
+        if self.is_temporal() || other.is_temporal() {
+            return self.checked_temporal_add(other, true);
         }
+        self.checked_arithmetic(
+            other,
+            "subtract",
+            i32::checked_sub,
+            i64::checked_sub,
+            Decimal::checked_sub,
+            |lhs, rhs| lhs - rhs,
+            |lhs, rhs| lhs - rhs,
+        )
     }
 
     pub fn checked_mul(&self, other: &Field) -> Result<Field> {
-        use Field::*;
-        match (&self, other) {
-            (Integer(lhs), Integer(rhs)) => match lhs.checked_mul(*rhs) {
-                Some(v) => Ok(Integer(v)),
-                None => Result::from(Error::OverflowError),
-            },
-            (Integer(lhs), Float(rhs)) => {
-                let result = (*lhs as f32) * rhs;
-                Ok(Float(result))
-            }
-            (Float(lhs), Integer(rhs)) => Ok(Float(lhs * (*rhs as f32))),
-            (Float(lhs), Float(rhs)) => Ok(Float(lhs * rhs)),
-            (Null, Integer(_)) | (Null, Float(_)) => Ok(Null),
-            (Integer(_), Null) | (Float(_), Null) => Ok(Null),
-            (Null, Null) => Ok(Null),
-            _ => {
-                let msg = format!("Cannot multiply {:?} and {:?}", self, other);
-                Result::from(Error::InvalidData(msg))
-            }
-        }
+        self.checked_arithmetic(
+            other,
+            "multiply",
+            i32::checked_mul,
+            i64::checked_mul,
+            Decimal::checked_mul,
+            |lhs, rhs| lhs * rhs,
+            |lhs, rhs| lhs * rhs,
+        )
     }
 
     pub fn checked_div(&self, other: &Field) -> Result<Field> {
-        use Field::*;
-        match (&self, other) {
-            (Integer(lhs), Integer(rhs)) => match lhs.checked_div(*rhs) {
-                Some(v) => Ok(Integer(v)),
-                None => Result::from(Error::OverflowError),
-            },
-            (Integer(lhs), Float(rhs)) => {
-                let result = (*lhs as f32) / rhs;
-                Ok(Float(result))
-            }
-            (Float(lhs), Integer(rhs)) => Ok(Float(lhs / (*rhs as f32))),
-            (Float(lhs), Float(rhs)) => Ok(Float(lhs / rhs)),
-            (Null, Integer(_)) | (Null, Float(_)) => Ok(Null),
-            (Integer(_), Null) | (Float(_), Null) => Ok(Null),
-            (Null, Null) => Ok(Null),
-            _ => {
-                let msg = format!("Cannot divide {:?} and {:?}", self, other);
-                Result::from(Error::InvalidData(msg))
-            }
-        }
-        //  _ =>  Null,
+        self.checked_arithmetic(
+            other,
+            "divide",
+            i32::checked_div,
+            i64::checked_div,
+            Decimal::checked_div,
+            |lhs, rhs| lhs / rhs,
+            |lhs, rhs| lhs / rhs,
+        )
     }
 
     /// Exponentiates two values. Errors when invalid.
@@ -332,34 +478,230 @@ impl Field {
             (Integer(lhs), Float(rhs)) => Float((*lhs as f32).powf(*rhs)),
             (Float(lhs), Integer(rhs)) => Float((lhs).powi(*rhs as i32)),
             (Float(lhs), Float(rhs)) => Float((lhs).powf(*rhs)),
-            (Integer(_) | Float(_), Null) => Null,
-            (Null, Integer(_) | Float(_) | Null) => Null,
+            (BigInt(_), Integer(_) | BigInt(_)) | (Integer(_), BigInt(_)) => {
+                match self.promote(other)? {
+                    (BigInt(lhs), BigInt(rhs)) if rhs >= 0 => {
+                        let rhs = rhs.try_into().or_else(|_| errinput!("integer overflow"))?;
+                        match lhs.checked_pow(rhs) {
+                            Some(i) => BigInt(i),
+                            None => return errinput!("integer overflow"),
+                        }
+                    }
+                    (BigInt(lhs), BigInt(rhs)) => Double((lhs as f64).powf(rhs as f64)),
+                    _ => unreachable!("integers promote to bigints"),
+                }
+            }
+            // Other combinations, e.g. those involving decimals, are computed approximately.
+            (lhs, rhs) if lhs.is_numeric() && rhs.is_numeric() => {
+                Double(lhs.to_f64().powf(rhs.to_f64()))
+            }
+            (lhs, Null) if lhs.is_numeric() => Null,
+            (Null, rhs) if rhs.is_numeric() || rhs.is_null() => Null,
             (lhs, rhs) => return errinput!("can't exponentiate {lhs} and {rhs}"),
         })
     }
 
     pub fn checked_mod(&self, other: &Field) -> Result<Field> {
+        self.checked_arithmetic(
+            other,
+            "mod",
+            i32::checked_rem,
+            i64::checked_rem,
+            Decimal::checked_rem,
+            |lhs, rhs| lhs % rhs,
+            |lhs, rhs| lhs % rhs,
+        )
+    }
+
+    /// Applies an arithmetic operation after promoting both operands to a common numeric type,
+    /// see [`Field::promote`]. Integer overflow, including division by zero, is an error, as is a
+    /// value that doesn't fit the common type. NULL combined with any numeric value is NULL.
+    #[allow(clippy::too_many_arguments)]
+    fn checked_arithmetic(
+        &self,
+        other: &Field,
+        verb: &str,
+        integer: fn(i32, i32) -> Option<i32>,
+        bigint: fn(i64, i64) -> Option<i64>,
+        decimal: fn(&Decimal, &Decimal) -> Result<Decimal>,
+        float: fn(f32, f32) -> f32,
+        double: fn(f64, f64) -> f64,
+    ) -> Result<Field> {
         use Field::*;
-        match (&self, other) {
-            (Integer(lhs), Integer(rhs)) => match lhs.checked_rem(*rhs) {
+        let (lhs_numeric, rhs_numeric) = (self.is_numeric(), other.is_numeric());
+        if !lhs_numeric || !rhs_numeric {
+            if (lhs_numeric || self.is_null()) && (rhs_numeric || other.is_null()) {
+                return Ok(Null);
+            }
+            let msg = format!("Cannot {} {:?} and {:?}", verb, self, other);
+            return Result::from(Error::InvalidData(msg));
+        }
+        match self.promote(other)? {
+            (Integer(lhs), Integer(rhs)) => match integer(lhs, rhs) {
                 Some(v) => Ok(Integer(v)),
                 None => Result::from(Error::OverflowError),
             },
-            (Integer(lhs), Float(rhs)) => {
-                let result = (*lhs as f32) % rhs;
-                Ok(Float(result))
+            (BigInt(lhs), BigInt(rhs)) => match bigint(lhs, rhs) {
+                Some(v) => Ok(BigInt(v)),
+                None => Result::from(Error::OverflowError),
+            },
+            (Field::Decimal(lhs), Field::Decimal(rhs)) => Ok(Field::Decimal(decimal(&lhs, &rhs)?)),
+            (Float(lhs), Float(rhs)) => Ok(Float(float(lhs, rhs))),
+            (Double(lhs), Double(rhs)) => Ok(Double(double(lhs, rhs))),
+            (lhs, rhs) => unreachable!("{lhs:?} and {rhs:?} don't have the same numeric type"),
+        }
+    }
+
+    /// Adds or, if `subtract` is set, subtracts date and time values:
+    ///
+    /// - An interval can be added to or subtracted from a date, giving a timestamp, or from a
+    ///   timestamp, giving a timestamp of the same kind. Intervals can be added to each other.
+    /// - An integer number of days can be added to or subtracted from a date.
+    /// - Subtracting dates gives the number of days between them, and subtracting timestamps
+    ///   gives the interval between them.
+    fn checked_temporal_add(&self, other: &Field, subtract: bool) -> Result<Field> {
+        let interval = |i: &Interval| match subtract {
+            true => i.checked_neg(),
+            false => Ok(*i),
+        };
+        Ok(match (self, other) {
+            (Field::Date(d), Field::Interval(i)) => {
+                Field::Timestamp(d.to_timestamp().checked_add(&interval(i)?)?)
+            }
+            (Field::Interval(i), Field::Date(d)) if !subtract => {
+                Field::Timestamp(d.to_timestamp().checked_add(i)?)
+            }
+            (Field::Date(d), Field::Integer(n)) => {
+                Field::Date(d.checked_add_days(match subtract {
+                    true => -(*n as i64),
+                    false => *n as i64,
+                })?)
+            }
+            (Field::Integer(n), Field::Date(d)) if !subtract => {
+                Field::Date(d.checked_add_days(*n as i64)?)
             }
-            (Float(lhs), Integer(rhs)) => Ok(Float(lhs % (*rhs as f32))),
-            (Float(lhs), Float(rhs)) => Ok(Float(lhs % rhs)),
-            (Null, Integer(_)) | (Null, Float(_)) => Ok(Null),
-            (Integer(_), Null) | (Float(_), Null) => Ok(Null),
-            (Null, Null) => Ok(Null),
+            (Field::Date(d), Field::Date(d2)) if subtract => Field::Integer(d.days() - d2.days()),
+            (Field::Timestamp(t), Field::Interval(i)) => {
+                Field::Timestamp(t.checked_add(&interval(i)?)?)
+            }
+            (Field::Interval(i), Field::Timestamp(t)) if !subtract => {
+                Field::Timestamp(t.checked_add(i)?)
+            }
+            (Field::Timestamp(t), Field::Timestamp(t2)) if subtract => Field::Interval(t.since(t2)),
+            (Field::TimestampTz(t), Field::Interval(i)) => {
+                Field::TimestampTz(t.checked_add(&interval(i)?)?)
+            }
+            (Field::Interval(i), Field::TimestampTz(t)) if !subtract => {
+                Field::TimestampTz(t.checked_add(i)?)
+            }
+            (Field::TimestampTz(t), Field::TimestampTz(t2)) if subtract => {
+                Field::Interval(t.since(t2))
+            }
+            (Field::Interval(i), Field::Interval(i2)) => {
+                Field::Interval(i.checked_add(&interval(i2)?)?)
+            }
+            (Field::Null, _) | (_, Field::Null) => Field::Null,
             _ => {
-                let msg = format!("Cannot mod {:?} and {:?}", self, other);
-                Result::from(Error::InvalidData(msg))
+                let verb = if subtract { "subtract" } else { "add" };
+                let msg = format!("Cannot {} {:?} and {:?}", verb, self, other);
+                return Result::from(Error::InvalidData(msg));
             }
+        })
+    }
+
+    /// Returns the member of a JSON object with the given key, or the element of a JSON array at
+    /// the given index, counting from the end if negative. This is the `->` operator. Returns NULL
+    /// if there is no such member or element, or if either value is NULL.
+    pub fn json_get(&self, key: &Field) -> Result<Field> {
+        let json = match (self, key) {
+            (Field::Null, _) | (_, Field::Null) => return Ok(Field::Null),
+            (Field::Json(json), Field::String(key)) => json.get_key(key),
+            (Field::Json(json), Field::Integer(index)) => json.get_index(*index as i64),
+            (Field::Json(json), Field::BigInt(index)) => json.get_index(*index),
+            (lhs, rhs) => return errinput!("can't extract {rhs} from {lhs}"),
+        };
+        Ok(json.cloned().map(Field::Json).unwrap_or(Field::Null))
+    }
+
+    /// Like [`Field::json_get`], but returns the value as text: the contents of a string, and
+    /// other values as JSON. A JSON null is NULL. This is the `->>` operator.
+    pub fn json_get_text(&self, key: &Field) -> Result<Field> {
+        Ok(match self.json_get(key)? {
+            Field::Json(Json::Null) => Field::Null,
+            Field::Json(Json::String(s)) => Field::String(s),
+            Field::Json(json) => Field::String(json.to_string()),
+            field => field,
+        })
+    }
+
+    /// Follows a path of object keys and array indexes, see [`Field::json_get`]. This is the `#>`
+    /// operator.
+    pub fn json_get_path(&self, path: &[Field]) -> Result<Field> {
+        path.iter()
+            .try_fold(self.clone(), |json, key| json.json_get(key))
+    }
+
+    pub fn is_temporal(&self) -> bool {
+        matches!(
+            self,
+            Field::Date(_) | Field::Timestamp(_) | Field::TimestampTz(_) | Field::Interval(_)
+        )
+    }
+
+    /// Converts two numeric values to their common type, see [`DataType::common_type`], so that
+    /// arithmetic can be applied to them.
+    ///
+    /// Fails if either value isn't numeric, or doesn't fit the common type, e.g. a decimal with
+    /// more integer digits than the common decimal type allows.
+    pub fn promote(&self, other: &Field) -> Result<(Field, Field)> {
+        if !self.is_numeric() || !other.is_numeric() {
+            return errinput!("can't promote {self} and {other} to a numeric type");
+        }
+        self.coerce(other)
+    }
+
+    pub fn is_numeric(&self) -> bool {
+        matches!(
+            self,
+            Field::Integer(_)
+                | Field::BigInt(_)
+                | Field::Float(_)
+                | Field::Double(_)
+                | Field::Decimal(_)
+        )
+    }
+
+    /// Returns a numeric value as a double, which may lose precision. Other values are NaN.
+    pub(crate) fn to_f64(&self) -> f64 {
+        match self {
+            Field::Integer(i) => *i as f64,
+            Field::BigInt(i) => *i as f64,
+            Field::Float(f) => *f as f64,
+            Field::Double(f) => *f,
+            Field::Decimal(d) => d.to_f64(),
+            _ => f64::NAN,
+        }
+    }
+
+    /// The position of the value's type in the ordering of values of different types.
+    fn type_rank(&self) -> u8 {
+        match self {
+            Field::Null => 0,
+            Field::Boolean(_) => 1,
+            Field::Integer(_) => 2,
+            Field::BigInt(_) => 3,
+            Field::Decimal(_) => 4,
+            Field::Float(_) => 5,
+            Field::Double(_) => 6,
+            Field::Date(_) => 7,
+            Field::Timestamp(_) => 8,
+            Field::TimestampTz(_) => 9,
+            Field::Interval(_) => 10,
+            Field::Uuid(_) => 11,
+            Field::String(_) => 12,
+            Field::Blob(_) => 13,
+            Field::Json(_) => 14,
         }
-        //  _ =>  Null,
     }
 
     pub fn is_null(&self) -> bool {
@@ -380,30 +722,66 @@ impl Field {
                 }
             }
             Field::Integer(i) => i.to_le_bytes().to_vec(),
+            Field::BigInt(i) => i.to_le_bytes().to_vec(),
             Field::Float(f) => f.to_le_bytes().to_vec(),
+            Field::Double(f) => f.to_le_bytes().to_vec(),
+            Field::Decimal(d) => d.value().to_le_bytes().to_vec(),
+            Field::Date(d) => d.days().to_le_bytes().to_vec(),
+            Field::Timestamp(t) => t.micros().to_le_bytes().to_vec(),
+            Field::TimestampTz(t) => t.to_bytes().to_vec(),
+            Field::Interval(i) => i.to_bytes().to_vec(),
+            Field::Uuid(u) => u.as_bytes().to_vec(),
             Field::String(s) => s.as_bytes().to_vec(),
+            Field::Blob(b) => b.clone(),
+            Field::Json(j) => j.to_bytes(),
         }
     }
 
-    pub fn deserialize(data: &[u8], data_type: DataType) -> Field {
-        match data_type {
-            DataType::Bool => {
-                if data[0] == 0 {
-                    Field::Boolean(false)
-                } else {
-                    Field::Boolean(true)
-                }
+    /// Decodes a value of the given type from the bytes written by [`Field::serialize`]. Fails
+    /// with an [`Error::InvalidData`] if the bytes aren't a valid encoding of the type.
+    pub fn deserialize(data: &[u8], data_type: DataType) -> Result<Field> {
+        Ok(match data_type {
+            DataType::Bool => match data {
+                [0] => Field::Boolean(false),
+                [_] => Field::Boolean(true),
+                _ => return errdata!("invalid boolean of {} bytes", data.len()),
+            },
+            DataType::Int => Field::Integer(i32::from_le_bytes(data.try_into()?)),
+            DataType::BigInt => Field::BigInt(i64::from_le_bytes(data.try_into()?)),
+            DataType::Float => Field::Float(f32::from_le_bytes(data.try_into()?)),
+            DataType::Double => Field::Double(f64::from_le_bytes(data.try_into()?)),
+            // A decimal is stored as its unscaled value at the scale of its column.
+            DataType::Decimal(_, scale) => {
+                Field::Decimal(Decimal::new(i128::from_le_bytes(data.try_into()?), scale))
+            }
+            DataType::Date => Field::Date(Date::from_days(i32::from_le_bytes(data.try_into()?))),
+            DataType::Timestamp => {
+                Field::Timestamp(Timestamp::from_micros(i64::from_le_bytes(data.try_into()?)))
             }
-            DataType::Int => Field::Integer(i32::from_le_bytes(data.try_into().unwrap())),
-            DataType::Float => Field::Float(f32::from_le_bytes(data.try_into().unwrap())),
-            DataType::Text => Field::String(String::from_utf8(data.to_vec()).unwrap()),
+            DataType::TimestampTz => {
+                let bytes: [u8; 12] = data.try_into()?;
+                Field::TimestampTz(TimestampTz::from_bytes(&bytes))
+            }
+            DataType::Interval => {
+                let bytes: [u8; 16] = data.try_into()?;
+                Field::Interval(Interval::from_bytes(&bytes))
+            }
+            DataType::Uuid => Field::Uuid(Uuid::from_bytes(data.try_into()?)),
+            DataType::Text => match String::from_utf8(data.to_vec()) {
+                Ok(s) => Field::String(s),
+                Err(_) => return errdata!("invalid UTF-8 in text value"),
+            },
+            DataType::Blob => Field::Blob(data.to_vec()),
+            DataType::Json => Field::Json(Json::from_bytes(data)?),
             _ => Field::Null,
-        }
+        })
     }
 
     /// Returns true if the value is undefined (NULL or NaN).
     pub fn is_undefined(&self) -> bool {
-        *self == Self::Null || matches!(self, Self::Float(f) if f.is_nan())
+        *self == Self::Null
+            || matches!(self, Self::Float(f) if f.is_nan())
+            || matches!(self, Self::Double(f) if f.is_nan())
     }
 }
 
@@ -446,8 +824,9 @@ impl From<Option<String>> for Label {
 
 #[allow(unused_imports)]
 mod tests {
+    use crate::common::Error;
     use crate::types::field::Field;
-    use crate::types::DataType;
+    use crate::types::{DataType, Decimal, Json, Uuid};
 
     #[test]
     pub fn test_init() {
@@ -475,7 +854,7 @@ mod tests {
         let lhs = Field::Integer(10);
         let rhs = Field::Integer(7);
 
-        let result = lhs + rhs;
+        let result = (lhs + rhs).unwrap();
 
         match result {
             Field::Integer(i) => assert_eq!(i, 17),
@@ -485,7 +864,7 @@ mod tests {
         let lhs = Field::Float(10.0);
         let rhs = Field::Float(7.0);
 
-        let result = lhs + rhs;
+        let result = (lhs + rhs).unwrap();
 
         match result {
             Field::Float(f) => assert_eq!(f, 17.0),
@@ -493,6 +872,24 @@ mod tests {
         }
     }
 
+    #[test]
+    pub fn test_operators_coerce_and_fail() {
+        // Operands are promoted to their common type.
+        assert_eq!(Field::Integer(2) * Field::BigInt(3), Ok(Field::BigInt(6)));
+        assert_eq!(Field::Integer(7) % Field::Null, Ok(Field::Null));
+
+        // Errors are returned rather than turned into NULL.
+        assert_eq!(
+            Field::Integer(i32::MAX) + Field::Integer(1),
+            Err(Error::OverflowError)
+        );
+        assert_eq!(
+            Field::Integer(1) / Field::Integer(0),
+            Err(Error::OverflowError)
+        );
+        assert!((Field::from("a") - Field::Integer(1)).is_err());
+    }
+
     #[test]
     pub fn test_comparison() {
         let lhs = Field::Integer(10);
@@ -510,13 +907,330 @@ mod tests {
     pub fn test_serialization() {
         let v = Field::Integer(10);
         let serialized = v.serialize();
-        let deserialized = Field::deserialize(&serialized, DataType::Int);
+        let deserialized = Field::deserialize(&serialized, DataType::Int).unwrap();
 
         assert_eq!(v, deserialized);
 
         let s = Field::String("testing, 1, 2, 3".to_string());
         let serialized = s.serialize();
-        let deserialized = Field::deserialize(&serialized, DataType::Text);
+        let deserialized = Field::deserialize(&serialized, DataType::Text).unwrap();
         assert_eq!(s, deserialized);
+
+        let b = Field::BigInt(-(1 << 40));
+        let deserialized = Field::deserialize(&b.serialize(), DataType::BigInt).unwrap();
+        assert_eq!(b, deserialized);
+
+        let d = Field::Double(2.718281828459045);
+        let deserialized = Field::deserialize(&d.serialize(), DataType::Double).unwrap();
+        assert_eq!(d, deserialized);
+
+        for (field, data_type) in [
+            (Field::Date("2024-02-29".parse().unwrap()), DataType::Date),
+            (
+                Field::Timestamp("1969-07-20 20:17:40".parse().unwrap()),
+                DataType::Timestamp,
+            ),
+            (
+                Field::TimestampTz("2024-01-01 00:00:00-08:00".parse().unwrap()),
+                DataType::TimestampTz,
+            ),
+            (
+                Field::Interval("1 year -2 days 00:00:00.5".parse().unwrap()),
+                DataType::Interval,
+            ),
+        ] {
+            let serialized = field.serialize();
+            assert_eq!(serialized.len(), data_type.length_bytes() as usize);
+            let deserialized = Field::deserialize(&serialized, data_type).unwrap();
+            assert_eq!(field.to_string(), deserialized.to_string());
+        }
+
+        let dec = Field::Decimal("-123.45".parse().unwrap());
+        let deserialized = Field::deserialize(&dec.serialize(), DataType::Decimal(5, 2)).unwrap();
+        assert_eq!(dec, deserialized);
+
+        // Invalid encodings are errors rather than panics.
+        for (data, data_type) in [
+            (&[0xff, 0xfe][..], DataType::Text),
+            (&[][..], DataType::Bool),
+            (&[1, 2, 3][..], DataType::Int),
+            (&[0; 8][..], DataType::TimestampTz),
+            (&[0; 4][..], DataType::Interval),
+        ] {
+            assert!(matches!(
+                Field::deserialize(data, data_type),
+                Err(Error::InvalidData(_))
+            ));
+        }
+    }
+
+    #[test]
+    pub fn test_promotion() {
+        assert_eq!(
+            Field::Integer(2).checked_add(&Field::BigInt(1 << 40)),
+            Ok(Field::BigInt((1 << 40) + 2))
+        );
+        assert_eq!(
+            Field::BigInt(3).checked_mul(&Field::Decimal("1.5".parse().unwrap())),
+            Ok(Field::Decimal("4.5".parse().unwrap()))
+        );
+        assert_eq!(
+            Field::Integer(1).checked_add(&Field::Float(0.5)),
+            Ok(Field::Float(1.5))
+        );
+        assert_eq!(
+            Field::BigInt(1).checked_add(&Field::Float(0.5)),
+            Ok(Field::Double(1.5))
+        );
+        assert_eq!(
+            Field::Decimal("0.25".parse().unwrap()).checked_add(&Field::Double(0.5)),
+            Ok(Field::Double(0.75))
+        );
+        assert_eq!(
+            Field::BigInt(2).checked_pow(&Field::Integer(40)),
+            Ok(Field::BigInt(1 << 40))
+        );
+        assert_eq!(Field::Null.checked_sub(&Field::BigInt(1)), Ok(Field::Null));
+        assert!(Field::BigInt(1).checked_add(&Field::from("a")).is_err());
+        assert_eq!(
+            Field::BigInt(i64::MAX).checked_add(&Field::Integer(1)),
+            Err(Error::OverflowError)
+        );
+
+        // A value that doesn't fit the common decimal type is an error, not NULL.
+        let wide = Field::Decimal(Decimal::new(10_i128.pow(29), 0));
+        let narrow = Field::Decimal(Decimal::new(1, 20));
+        assert!(matches!(
+            wide.checked_add(&narrow),
+            Err(Error::InvalidInput(_))
+        ));
+        assert!(wide.promote(&narrow).is_err());
+    }
+
+    #[test]
+    pub fn test_checked_arithmetic() {
+        let decimal = |s: &str| Field::Decimal(s.parse().unwrap());
+
+        assert_eq!(
+            Field::Integer(7).checked_sub(&Field::Integer(9)),
+            Ok(Field::Integer(-2))
+        );
+        assert_eq!(
+            Field::Integer(7).checked_div(&Field::Integer(2)),
+            Ok(Field::Integer(3))
+        );
+        assert_eq!(
+            Field::Integer(-7).checked_mod(&Field::BigInt(3)),
+            Ok(Field::BigInt(-1))
+        );
+        assert_eq!(
+            Field::Integer(i32::MAX).checked_mul(&Field::BigInt(2)),
+            Ok(Field::BigInt(i32::MAX as i64 * 2))
+        );
+        assert_eq!(
+            Field::Integer(3).checked_mul(&decimal("0.25")),
+            Ok(decimal("0.75"))
+        );
+        assert_eq!(
+            Field::Float(1.5).checked_add(&Field::Float(0.25)),
+            Ok(Field::Float(1.75))
+        );
+        assert_eq!(
+            Field::Float(0.5).checked_mul(&Field::Double(3.0)),
+            Ok(Field::Double(1.5))
+        );
+        assert_eq!(
+            Field::Double(1.0).checked_div(&Field::Double(0.0)),
+            Ok(Field::Double(f64::INFINITY))
+        );
+
+        // Integer overflow and division by zero are errors rather than NULL.
+        assert_eq!(
+            Field::Integer(i32::MAX).checked_add(&Field::Integer(1)),
+            Err(Error::OverflowError)
+        );
+        assert_eq!(
+            Field::Integer(i32::MIN).checked_div(&Field::Integer(-1)),
+            Err(Error::OverflowError)
+        );
+        assert_eq!(
+            Field::BigInt(i64::MIN).checked_sub(&Field::Integer(1)),
+            Err(Error::OverflowError)
+        );
+        assert_eq!(
+            Field::BigInt(i64::MAX).checked_mul(&Field::BigInt(2)),
+            Err(Error::OverflowError)
+        );
+        assert_eq!(
+            Field::Integer(1).checked_div(&Field::Integer(0)),
+            Err(Error::OverflowError)
+        );
+        assert_eq!(
+            Field::BigInt(1).checked_mod(&Field::BigInt(0)),
+            Err(Error::OverflowError)
+        );
+        assert_eq!(
+            decimal("1.5").checked_div(&decimal("0.0")),
+            Err(Error::OverflowError)
+        );
+
+        // Decimal division keeps at least 6 fractional digits, rounding half away from zero.
+        assert_eq!(
+            decimal("1").checked_div(&decimal("3")),
+            Ok(decimal("0.333333"))
+        );
+        assert_eq!(
+            decimal("-2").checked_div(&decimal("3")),
+            Ok(decimal("-0.666667"))
+        );
+        assert_eq!(
+            decimal("1.00000000").checked_div(&decimal("8")),
+            Ok(decimal("0.12500000"))
+        );
+        assert_eq!(
+            decimal("10.5").checked_mod(&Field::Integer(4)),
+            Ok(decimal("2.5"))
+        );
+
+        assert_eq!(Field::Integer(1).checked_mul(&Field::Null), Ok(Field::Null));
+        assert_eq!(Field::Null.checked_mod(&Field::Null), Ok(Field::Null));
+        assert!(Field::from("1").checked_add(&Field::Integer(1)).is_err());
+        assert!(Field::Boolean(true).checked_div(&Field::Null).is_err());
+    }
+
+    #[test]
+    pub fn test_temporal_arithmetic() {
+        let date = |s: &str| Field::Date(s.parse().unwrap());
+        let timestamp = |s: &str| Field::Timestamp(s.parse().unwrap());
+        let timestamptz = |s: &str| Field::TimestampTz(s.parse().unwrap());
+        let interval = |s: &str| Field::Interval(s.parse().unwrap());
+
+        assert_eq!(
+            date("2024-02-28").checked_add(&Field::Integer(2)),
+            Ok(date("2024-03-01"))
+        );
+        assert_eq!(
+            date("2024-03-01").checked_sub(&date("2024-02-01")),
+            Ok(Field::Integer(29))
+        );
+        assert_eq!(
+            date("2024-03-01").checked_add(&interval("1 day 12:00:00")),
+            Ok(timestamp("2024-03-02 12:00:00"))
+        );
+        assert_eq!(
+            interval("1 mon").checked_add(&timestamp("2024-01-31 08:00:00")),
+            Ok(timestamp("2024-02-29 08:00:00"))
+        );
+        assert_eq!(
+            timestamp("2024-03-01 00:00:00").checked_sub(&timestamp("2024-02-28 12:00:00")),
+            Ok(interval("1 day 12:00:00"))
+        );
+        assert_eq!(
+            timestamptz("2024-03-30 12:00:00+01:00").checked_add(&interval("1 day")),
+            Ok(timestamptz("2024-03-31 11:00:00Z"))
+        );
+        assert_eq!(
+            interval("1 day").checked_sub(&interval("02:00:00")),
+            Ok(interval("1 day -02:00:00"))
+        );
+        assert_eq!(Field::Null.checked_add(&interval("1 day")), Ok(Field::Null));
+        assert!(interval("1 day").checked_sub(&date("2024-01-01")).is_err());
+        assert!(date("2024-01-01").checked_mul(&Field::Integer(2)).is_err());
+
+        assert!(date("2024-01-01") < date("2024-01-02"));
+        assert!(timestamptz("2024-01-01 12:00:00+05:00") < timestamptz("2024-01-01 08:00:00Z"));
+        assert!(interval("1 mon") > interval("29 days"));
+    }
+
+    #[test]
+    pub fn test_binary_values() {
+        let uuid: Uuid = "123E4567-e89b-12d3-a456-426614174000".parse().unwrap();
+        assert_eq!(
+            Field::from(uuid).to_string(),
+            "123e4567-e89b-12d3-a456-426614174000"
+        );
+        assert_eq!(uuid, "123e4567e89b12d3a456426614174000".parse().unwrap());
+        assert!("123e4567-e89b-12d3-a456-42661417400"
+            .parse::<Uuid>()
+            .is_err());
+        assert!("123e4567-e89b-12d3-a456-42661417400g"
+            .parse::<Uuid>()
+            .is_err());
+        assert!(Field::from(uuid) < Field::from(Uuid::from_bytes([0xff; 16])));
+
+        let blob = Field::from(vec![0x00, 0xff, 0x10]);
+        assert_eq!(blob.to_string(), "00ff10");
+        assert_eq!(format!("{blob}"), "x'00ff10'");
+        assert!(blob < Field::from(vec![0x01]));
+        assert!(Field::from(vec![0x00]) < blob);
+        assert_eq!(
+            Field::deserialize(&blob.serialize(), DataType::Blob).unwrap(),
+            blob
+        );
+    }
+
+    #[test]
+    pub fn test_json_extraction() {
+        let doc = Field::from(
+            r#"{"name": "widget", "tags": ["a", "b"], "size": {"w": 2}, "gone": null}"#
+                .parse::<Json>()
+                .unwrap(),
+        );
+
+        assert_eq!(
+            doc.json_get(&Field::from("tags")).unwrap().to_string(),
+            r#"["a","b"]"#
+        );
+        assert_eq!(
+            doc.json_get_text(&Field::from("name")),
+            Ok(Field::from("widget"))
+        );
+        assert_eq!(
+            doc.json_get_text(&Field::from("size")),
+            Ok(Field::from(r#"{"w":2}"#))
+        );
+        assert_eq!(doc.json_get_text(&Field::from("gone")), Ok(Field::Null));
+        assert_eq!(doc.json_get(&Field::from("missing")), Ok(Field::Null));
+        assert_eq!(
+            doc.json_get_path(&[Field::from("tags"), Field::from(-1)])
+                .and_then(|tag| tag.json_get_text(&Field::Null)),
+            Ok(Field::Null)
+        );
+        assert_eq!(
+            doc.json_get_path(&[Field::from("tags"), Field::from(-1)]),
+            Ok(Field::from(Json::String("b".to_string())))
+        );
+        assert_eq!(doc.json_get(&Field::Null), Ok(Field::Null));
+        assert!(doc.json_get(&Field::from(true)).is_err());
+        assert!(Field::from("{}").json_get(&Field::from("a")).is_err());
+
+        // Documents with the same members are equal, and can be grouped on.
+        let reordered = Field::from(
+            r#"{"gone": null, "size": {"w": 2}, "tags": ["a", "b"], "name": "widget"}"#
+                .parse::<Json>()
+                .unwrap(),
+        );
+        let groups: std::collections::HashSet<Field> = [doc, reordered].into_iter().collect();
+        assert_eq!(groups.len(), 1);
+    }
+
+    #[test]
+    pub fn test_decimal_arithmetic() {
+        let dec = |s: &str| Field::Decimal(s.parse().unwrap());
+
+        assert_eq!(dec("1.10").checked_add(&dec("2.205")), Ok(dec("3.305")));
+        assert_eq!(dec("1").checked_sub(&dec("0.01")), Ok(dec("0.99")));
+        assert_eq!(dec("1").checked_div(&dec("3")), Ok(dec("0.333333")));
+        assert_eq!(
+            dec("2").checked_div(&dec("3")).unwrap().to_string(),
+            "0.666667"
+        );
+        assert_eq!(dec("7.5").checked_mod(&dec("2")), Ok(dec("1.5")));
+        assert_eq!(dec("1").checked_div(&dec("0")), Err(Error::OverflowError));
+
+        // Decimals of equal value are equal, whatever their scale.
+        assert_eq!(dec("1.5"), dec("1.500"));
+        assert!(dec("1.5") < dec("1.51"));
+        assert!(dec("-2") < dec("1.5"));
     }
 }
@@ -0,0 +1,445 @@
+use crate::common::{Error, Result};
+use crate::errinput;
+use bincode::Options;
+use serde::{Deserialize, Serialize};
+use std::cmp::Ordering;
+use std::collections::BTreeMap;
+use std::hash::{Hash, Hasher};
+
+/// How deeply arrays and objects may be nested in a document.
+const MAX_DEPTH: usize = 128;
+
+/// A JSON document. Objects keep their members ordered by key, and a duplicate key keeps the last
+/// value given for it, so that documents with the same members are equal regardless of the order
+/// they were written in.
+///
+/// Documents are stored in a compact binary encoding, see [`Json::to_bytes`], and written as
+/// compact JSON text.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub enum Json {
+    Null,
+    Bool(bool),
+    Number(f64),
+    String(String),
+    Array(Vec<Json>),
+    Object(BTreeMap<String, Json>),
+}
+
+impl Json {
+    /// Returns the member of an object with the given key.
+    pub fn get_key(&self, key: &str) -> Option<&Json> {
+        match self {
+            Json::Object(members) => members.get(key),
+            _ => None,
+        }
+    }
+
+    /// Returns the element of an array at the given index, counting from the end if negative.
+    pub fn get_index(&self, index: i64) -> Option<&Json> {
+        match self {
+            Json::Array(elements) => {
+                let index = match index < 0 {
+                    true => elements.len() as i64 + index,
+                    false => index,
+                };
+                elements.get(usize::try_from(index).ok()?)
+            }
+            _ => None,
+        }
+    }
+
+    /// Encodes the document with variable length integers, which keeps small documents small.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        bincode::DefaultOptions::new()
+            .serialize(self)
+            .expect("json documents are always serializable")
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Result<Json> {
+        Ok(bincode::DefaultOptions::new().deserialize(bytes)?)
+    }
+
+    /// The position of the value's type in the ordering of values of different types.
+    pub(crate) fn type_rank(&self) -> u8 {
+        match self {
+            Json::Null => 0,
+            Json::String(_) => 1,
+            Json::Number(_) => 2,
+            Json::Bool(_) => 3,
+            Json::Array(_) => 4,
+            Json::Object(_) => 5,
+        }
+    }
+}
+
+impl PartialEq for Json {
+    fn eq(&self, other: &Json) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for Json {}
+
+impl Hash for Json {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.type_rank().hash(state);
+        match self {
+            Json::Null => {}
+            Json::Bool(b) => b.hash(state),
+            // -0 and 0 are equal, so must hash the same.
+            Json::Number(n) if *n == 0.0 => 0_u64.hash(state),
+            Json::Number(n) => n.to_bits().hash(state),
+            Json::String(s) => s.hash(state),
+            Json::Array(elements) => elements.hash(state),
+            Json::Object(members) => members.hash(state),
+        }
+    }
+}
+
+impl Ord for Json {
+    fn cmp(&self, other: &Json) -> Ordering {
+        match (self, other) {
+            (Json::Null, Json::Null) => Ordering::Equal,
+            (Json::Bool(b), Json::Bool(b2)) => b.cmp(b2),
+            // Parsed numbers are always finite.
+            (Json::Number(n), Json::Number(n2)) => n.partial_cmp(n2).unwrap_or(Ordering::Equal),
+            (Json::String(s), Json::String(s2)) => s.cmp(s2),
+            (Json::Array(a), Json::Array(a2)) => a.cmp(a2),
+            (Json::Object(o), Json::Object(o2)) => o.cmp(o2),
+            (lhs, rhs) => lhs.type_rank().cmp(&rhs.type_rank()),
+        }
+    }
+}
+
+impl PartialOrd for Json {
+    fn partial_cmp(&self, other: &Json) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl std::str::FromStr for Json {
+    type Err = Error;
+
+    /// Parses JSON text, as specified by RFC 8259.
+    fn from_str(s: &str) -> Result<Json> {
+        let mut parser = Parser { input: s, pos: 0 };
+        let json = parser.parse_value(0)?;
+        parser.skip_whitespace();
+        match parser.peek() {
+            Some(_) => parser.error("unexpected trailing characters"),
+            None => Ok(json),
+        }
+    }
+}
+
+impl std::fmt::Display for Json {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            Json::Null => f.write_str("null"),
+            Json::Bool(b) => b.fmt(f),
+            // Integers are written without a fraction, and other numbers in their shortest form.
+            Json::Number(n) if n.fract() == 0.0 && n.abs() < 1e15 => write!(f, "{}", *n as i64),
+            Json::Number(n) => write!(f, "{n:?}"),
+            Json::String(s) => write_string(f, s),
+            Json::Array(elements) => {
+                f.write_str("[")?;
+                for (i, element) in elements.iter().enumerate() {
+                    if i > 0 {
+                        f.write_str(",")?;
+                    }
+                    element.fmt(f)?;
+                }
+                f.write_str("]")
+            }
+            Json::Object(members) => {
+                f.write_str("{")?;
+                for (i, (key, value)) in members.iter().enumerate() {
+                    if i > 0 {
+                        f.write_str(",")?;
+                    }
+                    write_string(f, key)?;
+                    f.write_str(":")?;
+                    value.fmt(f)?;
+                }
+                f.write_str("}")
+            }
+        }
+    }
+}
+
+/// Writes a string as a JSON string literal.
+fn write_string(f: &mut std::fmt::Formatter, s: &str) -> std::fmt::Result {
+    f.write_str("\"")?;
+    for c in s.chars() {
+        match c {
+            '"' => f.write_str("\\\"")?,
+            '\\' => f.write_str("\\\\")?,
+            '\n' => f.write_str("\\n")?,
+            '\r' => f.write_str("\\r")?,
+            '\t' => f.write_str("\\t")?,
+            c if (c as u32) < 0x20 => write!(f, "\\u{:04x}", c as u32)?,
+            c => write!(f, "{c}")?,
+        }
+    }
+    f.write_str("\"")
+}
+
+/// A recursive descent parser for JSON text.
+struct Parser<'a> {
+    input: &'a str,
+    /// The byte offset of the next character.
+    pos: usize,
+}
+
+impl Parser<'_> {
+    fn error<T>(&self, msg: &str) -> Result<T> {
+        errinput!("invalid json at offset {}: {msg}", self.pos)
+    }
+
+    fn peek(&self) -> Option<u8> {
+        self.input.as_bytes().get(self.pos).copied()
+    }
+
+    fn skip_whitespace(&mut self) {
+        while matches!(self.peek(), Some(b' ' | b'\t' | b'\n' | b'\r')) {
+            self.pos += 1;
+        }
+    }
+
+    /// Skips digits, returning whether there were any.
+    fn skip_digits(&mut self) -> bool {
+        let start = self.pos;
+        while matches!(self.peek(), Some(b'0'..=b'9')) {
+            self.pos += 1;
+        }
+        self.pos > start
+    }
+
+    fn expect(&mut self, token: &str) -> Result<()> {
+        match self.input[self.pos..].starts_with(token) {
+            true => {
+                self.pos += token.len();
+                Ok(())
+            }
+            false => self.error(&format!("expected {token}")),
+        }
+    }
+
+    fn parse_value(&mut self, depth: usize) -> Result<Json> {
+        if depth > MAX_DEPTH {
+            return self.error("document is nested too deeply");
+        }
+        self.skip_whitespace();
+        match self.peek() {
+            Some(b'n') => self.expect("null").map(|_| Json::Null),
+            Some(b't') => self.expect("true").map(|_| Json::Bool(true)),
+            Some(b'f') => self.expect("false").map(|_| Json::Bool(false)),
+            Some(b'"') => self.parse_string().map(Json::String),
+            Some(b'-' | b'0'..=b'9') => self.parse_number(),
+            Some(b'[') => {
+                self.pos += 1;
+                let mut elements = Vec::new();
+                self.skip_whitespace();
+                if self.peek() == Some(b']') {
+                    self.pos += 1;
+                    return Ok(Json::Array(elements));
+                }
+                loop {
+                    elements.push(self.parse_value(depth + 1)?);
+                    self.skip_whitespace();
+                    match self.peek() {
+                        Some(b',') => self.pos += 1,
+                        Some(b']') => {
+                            self.pos += 1;
+                            return Ok(Json::Array(elements));
+                        }
+                        _ => return self.error("expected , or ]"),
+                    }
+                }
+            }
+            Some(b'{') => {
+                self.pos += 1;
+                let mut members = BTreeMap::new();
+                self.skip_whitespace();
+                if self.peek() == Some(b'}') {
+                    self.pos += 1;
+                    return Ok(Json::Object(members));
+                }
+                loop {
+                    self.skip_whitespace();
+                    if self.peek() != Some(b'"') {
+                        return self.error("expected a string key");
+                    }
+                    let key = self.parse_string()?;
+                    self.skip_whitespace();
+                    self.expect(":")?;
+                    members.insert(key, self.parse_value(depth + 1)?);
+                    self.skip_whitespace();
+                    match self.peek() {
+                        Some(b',') => self.pos += 1,
+                        Some(b'}') => {
+                            self.pos += 1;
+                            return Ok(Json::Object(members));
+                        }
+                        _ => return self.error("expected , or }"),
+                    }
+                }
+            }
+            Some(_) => self.error("unexpected character"),
+            None => self.error("unexpected end of input"),
+        }
+    }
+
+    fn parse_number(&mut self) -> Result<Json> {
+        let start = self.pos;
+        if self.peek() == Some(b'-') {
+            self.pos += 1;
+        }
+        match self.peek() {
+            Some(b'0') => self.pos += 1,
+            Some(b'1'..=b'9') => {
+                self.skip_digits();
+            }
+            _ => return self.error("invalid number"),
+        }
+        if self.peek() == Some(b'.') {
+            self.pos += 1;
+            if !self.skip_digits() {
+                return self.error("invalid number");
+            }
+        }
+        if matches!(self.peek(), Some(b'e' | b'E')) {
+            self.pos += 1;
+            if matches!(self.peek(), Some(b'+' | b'-')) {
+                self.pos += 1;
+            }
+            if !self.skip_digits() {
+                return self.error("invalid number");
+            }
+        }
+        match self.input[start..self.pos].parse::<f64>() {
+            Ok(n) if n.is_finite() => Ok(Json::Number(n)),
+            _ => self.error("number out of range"),
+        }
+    }
+
+    fn parse_string(&mut self) -> Result<String> {
+        // Skip the opening quote.
+        self.pos += 1;
+        let mut string = String::new();
+        loop {
+            match self.peek() {
+                None => return self.error("unterminated string"),
+                Some(b'"') => {
+                    self.pos += 1;
+                    return Ok(string);
+                }
+                Some(b'\\') => {
+                    self.pos += 1;
+                    let c = match self.peek() {
+                        Some(b'"') => '"',
+                        Some(b'\\') => '\\',
+                        Some(b'/') => '/',
+                        Some(b'b') => '\u{8}',
+                        Some(b'f') => '\u{c}',
+                        Some(b'n') => '\n',
+                        Some(b'r') => '\r',
+                        Some(b't') => '\t',
+                        Some(b'u') => {
+                            self.pos += 1;
+                            string.push(self.parse_unicode_escape()?);
+                            continue;
+                        }
+                        _ => return self.error("invalid escape"),
+                    };
+                    self.pos += 1;
+                    string.push(c);
+                }
+                Some(c) if c < 0x20 => return self.error("control character in string"),
+                Some(_) => {
+                    let c = self.input[self.pos..].chars().next().unwrap();
+                    self.pos += c.len_utf8();
+                    string.push(c);
+                }
+            }
+        }
+    }
+
+    /// Parses the hex digits of a `\u` escape, including the second escape of a surrogate pair.
+    fn parse_unicode_escape(&mut self) -> Result<char> {
+        let mut code = self.parse_hex4()?;
+        if (0xd800..0xdc00).contains(&code) {
+            self.expect("\\u")?;
+            let low = self.parse_hex4()?;
+            if !(0xdc00..0xe000).contains(&low) {
+                return self.error("invalid surrogate pair");
+            }
+            code = 0x10000 + ((code - 0xd800) << 10) + (low - 0xdc00);
+        }
+        match char::from_u32(code) {
+            Some(c) => Ok(c),
+            None => self.error("invalid unicode escape"),
+        }
+    }
+
+    fn parse_hex4(&mut self) -> Result<u32> {
+        let hex = self
+            .input
+            .get(self.pos..self.pos + 4)
+            .filter(|hex| hex.bytes().all(|b| b.is_ascii_hexdigit()));
+        match hex {
+            Some(hex) => {
+                self.pos += 4;
+                Ok(u32::from_str_radix(hex, 16).unwrap())
+            }
+            None => self.error("invalid unicode escape"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_and_format() {
+        let json: Json = r#" { "b": [1, 2.5, -3e2, true, null], "a": "x\"\u00e9\ud83d\ude00" } "#
+            .parse()
+            .unwrap();
+        assert_eq!(
+            json.to_string(),
+            r#"{"a":"x\"é😀","b":[1,2.5,-300,true,null]}"#
+        );
+        assert_eq!(json, json.to_string().parse().unwrap());
+        assert_eq!(json, Json::from_bytes(&json.to_bytes()).unwrap());
+
+        for invalid in [
+            "",
+            "[1,]",
+            "{\"a\" 1}",
+            "01",
+            "\"\\x\"",
+            "tru",
+            "1 2",
+            "\"\t\"",
+        ] {
+            assert!(invalid.parse::<Json>().is_err(), "{invalid}");
+        }
+        assert!("[".repeat(MAX_DEPTH + 2).parse::<Json>().is_err());
+    }
+
+    #[test]
+    fn test_equality_ignores_member_order() {
+        let json: Json = r#"{"a": 1, "b": {"c": [0]}}"#.parse().unwrap();
+        let reordered: Json = r#"{"b": {"c": [-0.0]}, "a": 1.0}"#.parse().unwrap();
+        assert_eq!(json, reordered);
+
+        let hash = |json: &Json| {
+            let mut hasher = std::collections::hash_map::DefaultHasher::new();
+            json.hash(&mut hasher);
+            hasher.finish()
+        };
+        assert_eq!(hash(&json), hash(&reordered));
+        assert_ne!(json, r#"{"a": 1, "b": {"c": [1]}}"#.parse().unwrap());
+    }
+}
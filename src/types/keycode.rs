@@ -0,0 +1,594 @@
+use crate::common::{Error, Result};
+use crate::errdata;
+use crate::types::field::Field;
+use crate::types::Collation;
+use crate::types::{DataType, Date, Decimal, Interval, Json, Timestamp, TimestampTz, Uuid};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// Precedes a NULL key column sorted before other values.
+const NULL_FIRST: u8 = 0x00;
+/// Precedes a non-NULL key column.
+const VALUE: u8 = 0x01;
+/// Precedes a NULL key column sorted after other values.
+const NULL_LAST: u8 = 0x02;
+
+/// Precedes the continuation byte of an escaped 0x00 in strings and blobs, and ends them when
+/// followed by another 0x00.
+const ESCAPE: u8 = 0x00;
+/// Follows [`ESCAPE`] for a 0x00 byte.
+const ESCAPED_ZERO: u8 = 0xff;
+
+/// The order of a key column: whether its values sort ascending or descending, whether NULLs
+/// sort before or after the other values, and the collation of text. The default, ascending with
+/// NULLs first and binary text, is the order of [`Field`]'s `Ord` implementation.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct KeyOrder {
+    pub descending: bool,
+    pub nulls_last: bool,
+    pub collation: Collation,
+}
+
+/// Order-preserving ("memcomparable") key encoding: the keys of two values compare bytewise like
+/// the values themselves, so that keys can be kept in any byte-ordered structure. The encoding of
+/// a value is never a prefix of the encoding of another value of the same type, which lets the
+/// keys of a row's values be concatenated, see [`Row::encode_key`](crate::storage::tuple::Row).
+///
+/// Values that are equal encode to the same key, even if they are written differently, so keys
+/// decode to a canonical form: decimals at their column's scale, timestamptz values in UTC,
+/// intervals as whole 30-day months, days and microseconds, and text folded by its collation.
+impl Field {
+    /// Encodes the value as a key column in the given order.
+    pub fn encode_key(&self, order: KeyOrder) -> Vec<u8> {
+        if self.is_null() {
+            return match order.nulls_last {
+                true => vec![NULL_LAST],
+                false => vec![NULL_FIRST],
+            };
+        }
+        let mut key = vec![VALUE];
+        encode_value(self, order.collation, &mut key);
+        // Flipping every bit reverses the order of prefix-free encodings.
+        if order.descending {
+            key[1..].iter_mut().for_each(|b| *b = !*b);
+        }
+        key
+    }
+
+    /// Decodes a key column of the given type and order from the start of `key`, and advances
+    /// `key` past it.
+    pub fn decode_key(key: &mut &[u8], data_type: DataType, order: KeyOrder) -> Result<Field> {
+        let mut decoder = Decoder {
+            data: key,
+            invert: false,
+        };
+        let field = match decoder.byte()? {
+            NULL_FIRST | NULL_LAST => Field::Null,
+            VALUE => {
+                decoder.invert = order.descending;
+                decode_value(&mut decoder, data_type)?
+            }
+            b => return errdata!("invalid key column marker {b:#04x}"),
+        };
+        *key = decoder.data;
+        Ok(field)
+    }
+}
+
+fn encode_value(field: &Field, collation: Collation, key: &mut Vec<u8>) {
+    match field {
+        Field::Null => unreachable!("NULL has no value encoding"),
+        Field::Boolean(b) => key.push(*b as u8),
+        // Flipping the sign bit sorts negative numbers before positive ones.
+        Field::Integer(i) => key.extend((*i as u32 ^ 1 << 31).to_be_bytes()),
+        Field::BigInt(i) => key.extend((*i as u64 ^ 1 << 63).to_be_bytes()),
+        Field::Float(f) => key.extend(encode_f32(*f)),
+        Field::Double(f) => key.extend(encode_f64(*f)),
+        Field::Decimal(d) => encode_decimal(d, key),
+        Field::Date(d) => key.extend((d.days() as u32 ^ 1 << 31).to_be_bytes()),
+        Field::Timestamp(t) => key.extend((t.micros() as u64 ^ 1 << 63).to_be_bytes()),
+        Field::TimestampTz(t) => key.extend((t.utc().micros() as u64 ^ 1 << 63).to_be_bytes()),
+        Field::Interval(i) => key.extend((i.total_micros() as u128 ^ 1 << 127).to_be_bytes()),
+        Field::Uuid(u) => key.extend(u.as_bytes()),
+        Field::String(s) => encode_bytes(collation.fold(s).as_bytes(), key),
+        Field::Blob(b) => encode_bytes(b, key),
+        Field::Json(j) => encode_json(j, key),
+    }
+}
+
+fn decode_value(decoder: &mut Decoder, data_type: DataType) -> Result<Field> {
+    Ok(match data_type {
+        DataType::Bool => match decoder.byte()? {
+            0 => Field::Boolean(false),
+            1 => Field::Boolean(true),
+            b => return errdata!("invalid boolean key {b:#04x}"),
+        },
+        DataType::Int => Field::Integer((u32::from_be_bytes(decoder.array()?) ^ 1 << 31) as i32),
+        DataType::BigInt => Field::BigInt((u64::from_be_bytes(decoder.array()?) ^ 1 << 63) as i64),
+        DataType::Float => Field::Float(decode_f32(decoder.array()?)),
+        DataType::Double => Field::Double(decode_f64(decoder.array()?)),
+        DataType::Decimal(_, scale) => Field::Decimal(decode_decimal(decoder, scale)?),
+        DataType::Date => Field::Date(Date::from_days(
+            (u32::from_be_bytes(decoder.array()?) ^ 1 << 31) as i32,
+        )),
+        DataType::Timestamp => Field::Timestamp(decode_timestamp(decoder)?),
+        DataType::TimestampTz => {
+            Field::TimestampTz(TimestampTz::new(decode_timestamp(decoder)?, 0)?)
+        }
+        DataType::Interval => {
+            let total = (u128::from_be_bytes(decoder.array()?) ^ 1 << 127) as i128;
+            match Interval::from_total_micros(total) {
+                Some(interval) => Field::Interval(interval),
+                None => return errdata!("interval key of {total} microseconds is out of range"),
+            }
+        }
+        DataType::Uuid => Field::Uuid(Uuid::from_bytes(decoder.array()?)),
+        DataType::Text => Field::String(String::from_utf8(decode_bytes(decoder)?)?),
+        DataType::Blob => Field::Blob(decode_bytes(decoder)?),
+        DataType::Json => Field::Json(decode_json(decoder)?),
+        DataType::Invalid => return errdata!("can't decode a key of type {data_type}"),
+    })
+}
+
+fn decode_timestamp(decoder: &mut Decoder) -> Result<Timestamp> {
+    let micros = (u64::from_be_bytes(decoder.array()?) ^ 1 << 63) as i64;
+    Ok(Timestamp::from_micros(micros))
+}
+
+/// Floats are encoded as their bits, with the sign bit flipped for positive numbers and all bits
+/// flipped for negative ones. NaN is encoded as a single positive NaN, sorting after infinity,
+/// and -0 as 0, as they are equal.
+fn encode_f32(f: f32) -> [u8; 4] {
+    let bits = match f {
+        f if f.is_nan() => f32::NAN.to_bits(),
+        0.0 => 0,
+        f => f.to_bits(),
+    };
+    match bits >> 31 {
+        1 => !bits,
+        _ => bits | 1 << 31,
+    }
+    .to_be_bytes()
+}
+
+fn decode_f32(bytes: [u8; 4]) -> f32 {
+    let bits = u32::from_be_bytes(bytes);
+    f32::from_bits(match bits >> 31 {
+        1 => bits & !(1 << 31),
+        _ => !bits,
+    })
+}
+
+/// Like [`encode_f32`].
+fn encode_f64(f: f64) -> [u8; 8] {
+    let bits = match f {
+        f if f.is_nan() => f64::NAN.to_bits(),
+        0.0 => 0,
+        f => f.to_bits(),
+    };
+    match bits >> 63 {
+        1 => !bits,
+        _ => bits | 1 << 63,
+    }
+    .to_be_bytes()
+}
+
+fn decode_f64(bytes: [u8; 8]) -> f64 {
+    let bits = u64::from_be_bytes(bytes);
+    f64::from_bits(match bits >> 63 {
+        1 => bits & !(1 << 63),
+        _ => !bits,
+    })
+}
+
+/// Decimals are encoded independently of their scale, so that equal decimals have equal keys: a
+/// sign byte (negative, zero, positive), then for a non-zero decimal the position of its decimal
+/// point relative to its first digit as a big-endian i16 with the sign bit flipped, and its
+/// digits without trailing zeros as ASCII, ended by a 0x00. The bits after the sign byte of a
+/// negative decimal are flipped, so that larger magnitudes sort first.
+fn encode_decimal(decimal: &Decimal, key: &mut Vec<u8>) {
+    let value = decimal.value();
+    key.push((value.signum() + 1) as u8);
+    if value == 0 {
+        return;
+    }
+    let digits = value.unsigned_abs().to_string();
+    let exponent = digits.len() as i16 - decimal.scale() as i16;
+    let start = key.len();
+    key.extend((exponent as u16 ^ 1 << 15).to_be_bytes());
+    key.extend(digits.trim_end_matches('0').bytes());
+    key.push(0);
+    if value < 0 {
+        key[start..].iter_mut().for_each(|b| *b = !*b);
+    }
+}
+
+fn decode_decimal(decoder: &mut Decoder, scale: u8) -> Result<Decimal> {
+    let negative = match decoder.byte()? {
+        0 => true,
+        1 => return Ok(Decimal::new(0, scale)),
+        2 => false,
+        b => return errdata!("invalid decimal key sign {b:#04x}"),
+    };
+    let flip = |b: u8| if negative { !b } else { b };
+    let exponent = (u16::from_be_bytes(decoder.array()?.map(flip)) ^ 1 << 15) as i16;
+    let mut digits = String::new();
+    loop {
+        match flip(decoder.byte()?) {
+            0 => break,
+            b @ b'0'..=b'9' => digits.push(b as char),
+            b => return errdata!("invalid decimal key digit {b:#04x}"),
+        }
+    }
+    let mut value = i128::try_from(digits.parse::<u128>()?)?;
+    let mut digit_scale = digits.len() as i32 - exponent as i32;
+    if digit_scale < 0 {
+        value = 10_i128
+            .checked_pow(digit_scale.unsigned_abs())
+            .and_then(|factor| value.checked_mul(factor))
+            .ok_or_else(|| Error::InvalidData(format!("decimal key {digits} is out of range")))?;
+        digit_scale = 0;
+    }
+    if negative {
+        value = -value;
+    }
+    match Decimal::new(value, u8::try_from(digit_scale)?).rescale(scale) {
+        Some(decimal) => Ok(decimal),
+        None => errdata!("decimal key {digits} doesn't fit scale {scale}"),
+    }
+}
+
+/// Strings and blobs are encoded as their bytes with 0x00 escaped as 0x00 0xff, ended by 0x00
+/// 0x00, so that a string sorts before any string it is a prefix of.
+fn encode_bytes(bytes: &[u8], key: &mut Vec<u8>) {
+    for &b in bytes {
+        match b {
+            0 => key.extend([ESCAPE, ESCAPED_ZERO]),
+            b => key.push(b),
+        }
+    }
+    key.extend([ESCAPE, ESCAPE]);
+}
+
+fn decode_bytes(decoder: &mut Decoder) -> Result<Vec<u8>> {
+    let mut bytes = Vec::new();
+    loop {
+        match decoder.byte()? {
+            ESCAPE => match decoder.byte()? {
+                ESCAPE => return Ok(bytes),
+                ESCAPED_ZERO => bytes.push(0),
+                b => return errdata!("invalid escape sequence 0x00 {b:#04x} in key"),
+            },
+            b => bytes.push(b),
+        }
+    }
+}
+
+/// JSON documents are encoded as the rank of their type, see [`Json`]'s `Ord` implementation,
+/// followed by their value. Array elements and object members are each preceded by 0x01 and the
+/// array or object is ended by 0x00, so that it sorts before any it is a prefix of.
+fn encode_json(json: &Json, key: &mut Vec<u8>) {
+    key.push(json.type_rank());
+    match json {
+        Json::Null => {}
+        Json::Bool(b) => key.push(*b as u8),
+        Json::Number(n) => key.extend(encode_f64(*n)),
+        Json::String(s) => encode_bytes(s.as_bytes(), key),
+        Json::Array(elements) => {
+            for element in elements {
+                key.push(1);
+                encode_json(element, key);
+            }
+            key.push(0);
+        }
+        Json::Object(members) => {
+            for (name, value) in members {
+                key.push(1);
+                encode_bytes(name.as_bytes(), key);
+                encode_json(value, key);
+            }
+            key.push(0);
+        }
+    }
+}
+
+fn decode_json(decoder: &mut Decoder) -> Result<Json> {
+    let rank = decoder.byte()?;
+    Ok(match rank {
+        0 => Json::Null,
+        1 => Json::String(String::from_utf8(decode_bytes(decoder)?)?),
+        2 => Json::Number(decode_f64(decoder.array()?)),
+        3 => Json::Bool(decoder.byte()? == 1),
+        4 => {
+            let mut elements = Vec::new();
+            while decoder.byte()? == 1 {
+                elements.push(decode_json(decoder)?);
+            }
+            Json::Array(elements)
+        }
+        5 => {
+            let mut members = BTreeMap::new();
+            while decoder.byte()? == 1 {
+                let name = String::from_utf8(decode_bytes(decoder)?)?;
+                members.insert(name, decode_json(decoder)?);
+            }
+            Json::Object(members)
+        }
+        _ => return errdata!("invalid json key type {rank:#04x}"),
+    })
+}
+
+/// Reads a key, flipping the bits of each byte for descending columns.
+struct Decoder<'a> {
+    data: &'a [u8],
+    invert: bool,
+}
+
+impl Decoder<'_> {
+    fn byte(&mut self) -> Result<u8> {
+        let (&b, rest) = self
+            .data
+            .split_first()
+            .ok_or_else(|| Error::InvalidData("key ended unexpectedly".to_string()))?;
+        self.data = rest;
+        Ok(if self.invert { !b } else { b })
+    }
+
+    fn array<const N: usize>(&mut self) -> Result<[u8; N]> {
+        let mut bytes = [0; N];
+        for b in bytes.iter_mut() {
+            *b = self.byte()?;
+        }
+        Ok(bytes)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ORDERS: [KeyOrder; 4] = [
+        KeyOrder {
+            descending: false,
+            nulls_last: false,
+            collation: Collation::Binary,
+        },
+        KeyOrder {
+            descending: false,
+            nulls_last: true,
+            collation: Collation::Binary,
+        },
+        KeyOrder {
+            descending: true,
+            nulls_last: false,
+            collation: Collation::Binary,
+        },
+        KeyOrder {
+            descending: true,
+            nulls_last: true,
+            collation: Collation::Binary,
+        },
+    ];
+
+    /// Values of each type in ascending order, without NULL.
+    fn sorted_values() -> Vec<(DataType, Vec<Field>)> {
+        let decimal = |s: &str| Field::Decimal(s.parse::<Decimal>().unwrap().rescale(3).unwrap());
+        vec![
+            (DataType::Bool, vec![Field::from(false), Field::from(true)]),
+            (
+                DataType::Int,
+                [i32::MIN, -256, -1, 0, 1, 255, 256, i32::MAX]
+                    .map(Field::from)
+                    .to_vec(),
+            ),
+            (
+                DataType::BigInt,
+                [i64::MIN, -1, 0, 1, i64::MAX].map(Field::from).to_vec(),
+            ),
+            (
+                DataType::Float,
+                [
+                    f32::NEG_INFINITY,
+                    -1.5,
+                    -1e-30,
+                    0.0,
+                    1e-30,
+                    2.0,
+                    f32::INFINITY,
+                    f32::NAN,
+                ]
+                .map(Field::from)
+                .to_vec(),
+            ),
+            (
+                DataType::Double,
+                [f64::MIN, -0.5, 0.0, f64::MIN_POSITIVE, f64::MAX, f64::NAN]
+                    .map(Field::Double)
+                    .to_vec(),
+            ),
+            (
+                DataType::Decimal(10, 3),
+                [
+                    "-1000",
+                    "-999.999",
+                    "-10.5",
+                    "-10.05",
+                    "-10",
+                    "-0.001",
+                    "0",
+                    "0.001",
+                    "0.01",
+                    "0.1",
+                    "1",
+                    "1.001",
+                    "10",
+                    "10.05",
+                    "10.5",
+                    "9999999.999",
+                ]
+                .map(decimal)
+                .to_vec(),
+            ),
+            (
+                DataType::Date,
+                ["0001-01-01", "1969-12-31", "1970-01-01", "9999-12-31"]
+                    .map(|s| Field::Date(s.parse().unwrap()))
+                    .to_vec(),
+            ),
+            (
+                DataType::Timestamp,
+                [
+                    "1969-12-31 23:59:59.999999",
+                    "1970-01-01 00:00:00",
+                    "2024-02-29 12:00:00",
+                ]
+                .map(|s| Field::Timestamp(s.parse().unwrap()))
+                .to_vec(),
+            ),
+            (
+                DataType::TimestampTz,
+                ["1970-01-01 00:00:00+00", "2024-02-29 12:00:00+00"]
+                    .map(|s| Field::TimestampTz(s.parse().unwrap()))
+                    .to_vec(),
+            ),
+            (
+                DataType::Interval,
+                [
+                    Interval::new(-1, 0, 0),
+                    Interval::new(0, -1, 0),
+                    Interval::new(0, 0, 0),
+                    Interval::new(0, 1, 5),
+                    Interval::new(1, 2, 3),
+                ]
+                .map(Field::from)
+                .to_vec(),
+            ),
+            (
+                DataType::Uuid,
+                [[0; 16], [0x12; 16], [0xff; 16]]
+                    .map(|b| Field::from(Uuid::from_bytes(b)))
+                    .to_vec(),
+            ),
+            (
+                DataType::Text,
+                [
+                    "", "\0", "\0\0", "\0a", "a", "a\0", "a\0b", "a\u{1}", "ab", "b",
+                ]
+                .map(Field::from)
+                .to_vec(),
+            ),
+            (
+                DataType::Blob,
+                [vec![], vec![0], vec![0, 0xff], vec![1], vec![0xff, 0xff]]
+                    .map(Field::from)
+                    .to_vec(),
+            ),
+            (
+                DataType::Json,
+                [
+                    "null",
+                    r#""""#,
+                    r#""a""#,
+                    "-1",
+                    "0",
+                    "2.5",
+                    "false",
+                    "true",
+                    "[]",
+                    "[null]",
+                    "[1]",
+                    "[1,2]",
+                    "[2]",
+                    "{}",
+                    r#"{"a":1}"#,
+                    r#"{"a":1,"b":null}"#,
+                    r#"{"a":2}"#,
+                    r#"{"b":[]}"#,
+                ]
+                .map(|s| Field::Json(s.parse().unwrap()))
+                .to_vec(),
+            ),
+        ]
+    }
+
+    #[test]
+    fn test_round_trip() {
+        for (data_type, values) in sorted_values() {
+            for value in values.into_iter().chain([Field::Null]) {
+                for order in ORDERS {
+                    let key = value.encode_key(order);
+                    let mut rest = key.as_slice();
+                    let decoded = Field::decode_key(&mut rest, data_type, order).unwrap();
+                    assert_eq!(decoded, value, "{data_type} {value} {order:?}");
+                    assert!(rest.is_empty(), "{data_type} {value} {order:?}");
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_order() {
+        for (data_type, values) in sorted_values() {
+            for order in ORDERS {
+                let mut expect = values.clone();
+                if order.descending {
+                    expect.reverse();
+                }
+                match order.nulls_last {
+                    true => expect.push(Field::Null),
+                    false => expect.insert(0, Field::Null),
+                }
+                let mut keys: Vec<_> = values
+                    .iter()
+                    .chain([&Field::Null])
+                    .map(|value| value.encode_key(order))
+                    .collect();
+                keys.sort();
+                let sorted: Vec<_> = keys
+                    .iter()
+                    .map(|key| Field::decode_key(&mut key.as_slice(), data_type, order).unwrap())
+                    .collect();
+                assert_eq!(sorted, expect, "{data_type} {order:?}");
+                keys.dedup();
+                assert_eq!(keys.len(), expect.len(), "{data_type} {order:?}");
+            }
+        }
+    }
+
+    #[test]
+    fn test_canonical_keys() {
+        let order = KeyOrder::default();
+        let decimal = |s: &str| Field::Decimal(s.parse().unwrap());
+        assert_eq!(
+            decimal("1.5").encode_key(order),
+            decimal("1.500").encode_key(order)
+        );
+        assert_eq!(
+            Field::from(-0.0).encode_key(order),
+            Field::from(0.0).encode_key(order)
+        );
+
+        let month = Field::from(Interval::new(1, 0, 0));
+        let key = month.encode_key(order);
+        assert_eq!(key, Field::from(Interval::new(0, 30, 0)).encode_key(order));
+        assert_eq!(
+            Field::decode_key(&mut key.as_slice(), DataType::Interval, order),
+            Ok(month)
+        );
+
+        let local: TimestampTz = "2024-01-01 02:00:00+02".parse().unwrap();
+        let key = Field::from(local).encode_key(order);
+        let utc = Field::decode_key(&mut key.as_slice(), DataType::TimestampTz, order).unwrap();
+        assert_eq!(utc.to_string(), "2024-01-01 00:00:00+00:00");
+    }
+
+    #[test]
+    fn test_invalid_keys() {
+        let order = KeyOrder::default();
+        assert!(Field::decode_key(&mut [].as_slice(), DataType::Int, order).is_err());
+        assert!(Field::decode_key(&mut [VALUE, 0, 0].as_slice(), DataType::Int, order).is_err());
+        assert!(Field::decode_key(&mut [0x07].as_slice(), DataType::Int, order).is_err());
+        assert!(Field::decode_key(&mut [VALUE, b'a'].as_slice(), DataType::Text, order).is_err());
+        assert!(Field::decode_key(&mut [VALUE, 2].as_slice(), DataType::Bool, order).is_err());
+    }
+}
@@ -0,0 +1,190 @@
+use crate::common::Result;
+use crate::errinput;
+use crate::types::field::Field;
+use std::cmp::Ordering;
+
+/// SQL predicates and boolean operators, which use three-valued logic: a predicate is TRUE, FALSE
+/// or UNKNOWN, where UNKNOWN is represented by [`Field::Null`]. Comparing NULL with anything is
+/// UNKNOWN, unlike the total order of [`Field`]'s `Ord` and `PartialEq` implementations, which
+/// are for sorting and indexing and consider NULL equal to itself and less than any other value.
+impl Field {
+    /// `=`: compares the values after converting them to their common type.
+    pub fn checked_eq(&self, other: &Field) -> Result<Field> {
+        self.compare(other, |ordering| ordering == Ordering::Equal)
+    }
+
+    /// `<>`
+    pub fn checked_ne(&self, other: &Field) -> Result<Field> {
+        self.compare(other, |ordering| ordering != Ordering::Equal)
+    }
+
+    /// `<`
+    pub fn checked_lt(&self, other: &Field) -> Result<Field> {
+        self.compare(other, |ordering| ordering == Ordering::Less)
+    }
+
+    /// `<=`
+    pub fn checked_le(&self, other: &Field) -> Result<Field> {
+        self.compare(other, |ordering| ordering != Ordering::Greater)
+    }
+
+    /// `>`
+    pub fn checked_gt(&self, other: &Field) -> Result<Field> {
+        self.compare(other, |ordering| ordering == Ordering::Greater)
+    }
+
+    /// `>=`
+    pub fn checked_ge(&self, other: &Field) -> Result<Field> {
+        self.compare(other, |ordering| ordering != Ordering::Less)
+    }
+
+    /// `AND`: FALSE if either operand is FALSE, even if the other is UNKNOWN.
+    pub fn checked_and(&self, other: &Field) -> Result<Field> {
+        Ok(match (self, other) {
+            (Field::Boolean(false), Field::Boolean(_) | Field::Null)
+            | (Field::Boolean(_) | Field::Null, Field::Boolean(false)) => Field::Boolean(false),
+            (Field::Boolean(true), Field::Boolean(true)) => Field::Boolean(true),
+            (Field::Boolean(_) | Field::Null, Field::Boolean(_) | Field::Null) => Field::Null,
+            (lhs, rhs) => return errinput!("can't AND {lhs} and {rhs}"),
+        })
+    }
+
+    /// `OR`: TRUE if either operand is TRUE, even if the other is UNKNOWN.
+    pub fn checked_or(&self, other: &Field) -> Result<Field> {
+        Ok(match (self, other) {
+            (Field::Boolean(true), Field::Boolean(_) | Field::Null)
+            | (Field::Boolean(_) | Field::Null, Field::Boolean(true)) => Field::Boolean(true),
+            (Field::Boolean(false), Field::Boolean(false)) => Field::Boolean(false),
+            (Field::Boolean(_) | Field::Null, Field::Boolean(_) | Field::Null) => Field::Null,
+            (lhs, rhs) => return errinput!("can't OR {lhs} and {rhs}"),
+        })
+    }
+
+    /// `NOT`: UNKNOWN stays UNKNOWN.
+    pub fn checked_not(&self) -> Result<Field> {
+        Ok(match self {
+            Field::Boolean(b) => Field::Boolean(!b),
+            Field::Null => Field::Null,
+            value => return errinput!("can't NOT {value}"),
+        })
+    }
+
+    /// `IS NULL`, which is never UNKNOWN.
+    pub fn sql_is_null(&self) -> Field {
+        Field::Boolean(self.is_null())
+    }
+
+    /// `IS NOT NULL`, which is never UNKNOWN.
+    pub fn sql_is_not_null(&self) -> Field {
+        Field::Boolean(!self.is_null())
+    }
+
+    /// `IS DISTINCT FROM`: like `<>`, but NULL is distinct from any other value and not from
+    /// itself, so the result is never UNKNOWN.
+    pub fn is_distinct_from(&self, other: &Field) -> Result<Field> {
+        Ok(match (self, other) {
+            (Field::Null, Field::Null) => Field::Boolean(false),
+            (Field::Null, _) | (_, Field::Null) => Field::Boolean(true),
+            (lhs, rhs) => lhs.checked_ne(rhs)?,
+        })
+    }
+
+    /// `IS NOT DISTINCT FROM`, see [`Field::is_distinct_from`].
+    pub fn is_not_distinct_from(&self, other: &Field) -> Result<Field> {
+        self.is_distinct_from(other)?.checked_not()
+    }
+
+    /// Returns true if the value is TRUE, e.g. for a `WHERE` clause, which skips rows that are
+    /// FALSE or UNKNOWN.
+    pub fn is_true(&self) -> bool {
+        matches!(self, Field::Boolean(true))
+    }
+
+    /// Compares the values with [`Field::checked_cmp`], and returns whether the ordering matches,
+    /// or UNKNOWN if either value is NULL.
+    fn compare(&self, other: &Field, matches: impl Fn(Ordering) -> bool) -> Result<Field> {
+        Ok(match self.checked_cmp(other)? {
+            Some(ordering) => Field::Boolean(matches(ordering)),
+            None => Field::Null,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TRUE: Field = Field::Boolean(true);
+    const FALSE: Field = Field::Boolean(false);
+    const UNKNOWN: Field = Field::Null;
+
+    #[test]
+    fn test_truth_tables() {
+        let values = [TRUE, FALSE, UNKNOWN];
+        #[rustfmt::skip]
+        let and = [
+            [TRUE, FALSE, UNKNOWN],
+            [FALSE, FALSE, FALSE],
+            [UNKNOWN, FALSE, UNKNOWN],
+        ];
+        #[rustfmt::skip]
+        let or = [
+            [TRUE, TRUE, TRUE],
+            [TRUE, FALSE, UNKNOWN],
+            [TRUE, UNKNOWN, UNKNOWN],
+        ];
+        for (i, lhs) in values.iter().enumerate() {
+            for (j, rhs) in values.iter().enumerate() {
+                assert_eq!(
+                    lhs.checked_and(rhs),
+                    Ok(and[i][j].clone()),
+                    "{lhs} AND {rhs}"
+                );
+                assert_eq!(lhs.checked_or(rhs), Ok(or[i][j].clone()), "{lhs} OR {rhs}");
+            }
+        }
+        assert_eq!(TRUE.checked_not(), Ok(FALSE));
+        assert_eq!(FALSE.checked_not(), Ok(TRUE));
+        assert_eq!(UNKNOWN.checked_not(), Ok(UNKNOWN));
+
+        assert!(TRUE.checked_and(&Field::from(1)).is_err());
+        assert!(Field::from("t").checked_or(&UNKNOWN).is_err());
+        assert!(Field::from(0).checked_not().is_err());
+    }
+
+    #[test]
+    fn test_comparisons() {
+        let one = Field::from(1);
+        assert_eq!(one.checked_eq(&Field::from(1i64)), Ok(TRUE));
+        assert_eq!(one.checked_ne(&Field::from(1.0)), Ok(FALSE));
+        assert_eq!(one.checked_lt(&Field::from(2)), Ok(TRUE));
+        assert_eq!(one.checked_le(&Field::from(1)), Ok(TRUE));
+        assert_eq!(one.checked_gt(&Field::from(1)), Ok(FALSE));
+        assert_eq!(one.checked_ge(&Field::from(0)), Ok(TRUE));
+
+        // Comparisons with NULL are UNKNOWN, even with NULL itself.
+        assert_eq!(one.checked_eq(&UNKNOWN), Ok(UNKNOWN));
+        assert_eq!(UNKNOWN.checked_eq(&UNKNOWN), Ok(UNKNOWN));
+        assert_eq!(UNKNOWN.checked_ne(&one), Ok(UNKNOWN));
+        assert!(!UNKNOWN.checked_eq(&UNKNOWN).unwrap().is_true());
+
+        assert!(one.checked_eq(&Field::from("1")).is_err());
+    }
+
+    #[test]
+    fn test_null_predicates() {
+        assert_eq!(UNKNOWN.sql_is_null(), TRUE);
+        assert_eq!(Field::from(0).sql_is_null(), FALSE);
+        assert_eq!(UNKNOWN.sql_is_not_null(), FALSE);
+        assert_eq!(Field::from("").sql_is_not_null(), TRUE);
+
+        let one = Field::from(1);
+        assert_eq!(UNKNOWN.is_distinct_from(&UNKNOWN), Ok(FALSE));
+        assert_eq!(UNKNOWN.is_distinct_from(&one), Ok(TRUE));
+        assert_eq!(one.is_distinct_from(&UNKNOWN), Ok(TRUE));
+        assert_eq!(one.is_distinct_from(&Field::from(1i64)), Ok(FALSE));
+        assert_eq!(one.is_distinct_from(&Field::from(2)), Ok(TRUE));
+        assert_eq!(UNKNOWN.is_not_distinct_from(&UNKNOWN), Ok(TRUE));
+        assert_eq!(one.is_not_distinct_from(&UNKNOWN), Ok(FALSE));
+    }
+}
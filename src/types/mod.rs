@@ -1,4 +1,18 @@
+mod binary;
+mod cast;
+mod collation;
+mod datetime;
+mod decimal;
 pub mod field;
+mod json;
+mod keycode;
+mod logic;
 mod schema;
 
-pub use schema::{Column, DataType, Table, TableBuilder};
+pub use binary::Uuid;
+pub use collation::Collation;
+pub use datetime::{Date, Interval, Timestamp, TimestampTz};
+pub use decimal::{Decimal, MAX_DECIMAL_PRECISION};
+pub use json::Json;
+pub use keycode::KeyOrder;
+pub use schema::{Column, DataType, SchemaChange, Table, TableBuilder};
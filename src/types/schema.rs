@@ -1,4 +1,9 @@
+use crate::common::Result;
+use crate::config::config::MAX_STRING_LENGTH;
+use crate::types::decimal::MAX_DECIMAL_PRECISION;
 use crate::types::field::Field;
+use crate::types::{Collation, KeyOrder};
+use crate::{errdata, errinput};
 use core::ops::Deref;
 use serde::{Deserialize, Serialize};
 use std::fmt;
@@ -8,8 +13,24 @@ use std::sync::Arc;
 pub enum DataType {
     Bool,
     Int,
+    BigInt,
     Float,
+    Double,
+    /// A fixed-point number with `precision` significant digits, `scale` of which are after the
+    /// decimal point.
+    Decimal(u8, u8),
+    Date,
+    /// A date and time without a time zone.
+    Timestamp,
+    /// A point in time, along with the UTC offset it was given in.
+    TimestampTz,
+    Interval,
+    Uuid,
     Text,
+    /// Raw bytes, stored with variable length like text.
+    Blob,
+    /// A JSON document, stored in a binary encoding with variable length.
+    Json,
     Invalid,
 }
 
@@ -18,8 +39,18 @@ impl fmt::Display for DataType {
         match self {
             DataType::Bool => write!(f, "bool"),
             DataType::Int => write!(f, "int"),
+            DataType::BigInt => write!(f, "bigint"),
             DataType::Float => write!(f, "float"),
+            DataType::Double => write!(f, "double"),
+            DataType::Decimal(precision, scale) => write!(f, "decimal({precision},{scale})"),
+            DataType::Date => write!(f, "date"),
+            DataType::Timestamp => write!(f, "timestamp"),
+            DataType::TimestampTz => write!(f, "timestamptz"),
+            DataType::Interval => write!(f, "interval"),
+            DataType::Uuid => write!(f, "uuid"),
             DataType::Text => write!(f, "varchar"),
+            DataType::Blob => write!(f, "blob"),
+            DataType::Json => write!(f, "json"),
             DataType::Invalid => write!(f, "invalid"),
         }
     }
@@ -27,24 +58,69 @@ impl fmt::Display for DataType {
 
 impl DataType {
     pub fn from_string(data_type: &str) -> DataType {
-        match data_type {
+        Self::parse(data_type).expect("Unknown data type")
+    }
+
+    /// Parses a data type from its stored name, returning an error for unknown types.
+    pub fn parse(data_type: &str) -> Result<DataType> {
+        Ok(match data_type {
             "Bool" => DataType::Bool,
             "Int" => DataType::Int,
+            "BigInt" => DataType::BigInt,
             "Float" => DataType::Float,
+            "Double" => DataType::Double,
+            "Date" => DataType::Date,
+            "Timestamp" => DataType::Timestamp,
+            "TimestampTz" => DataType::TimestampTz,
+            "Interval" => DataType::Interval,
+            "Uuid" => DataType::Uuid,
             "Text" => DataType::Text,
+            "Blob" => DataType::Blob,
+            "Json" => DataType::Json,
             "Invalid" => DataType::Invalid,
             "Null" => DataType::Invalid,
-            _ => panic!("Unknown data type"),
+            _ => match Self::decimal_from_string(data_type) {
+                Some(decimal) => decimal,
+                None => return errdata!("unknown data type {data_type}"),
+            },
+        })
+    }
+
+    /// Parses a decimal data type written as `Decimal(precision,scale)`.
+    fn decimal_from_string(data_type: &str) -> Option<DataType> {
+        let arguments = data_type.strip_prefix("Decimal(")?.strip_suffix(')')?;
+        let (precision, scale) = arguments.split_once(',')?;
+        let precision: u8 = precision.trim().parse().ok()?;
+        let scale: u8 = scale.trim().parse().ok()?;
+        match (1..=MAX_DECIMAL_PRECISION).contains(&precision) && scale <= precision {
+            true => Some(DataType::Decimal(precision, scale)),
+            false => None,
         }
     }
 
+    /// Returns true if values of the type have a variable length, and are therefore stored in the
+    /// variable length area of a row.
+    pub fn is_variable_length(&self) -> bool {
+        matches!(self, DataType::Text | DataType::Blob | DataType::Json)
+    }
+
     // not for use with strings
     pub fn length_bytes(&self) -> u16 {
         match self {
             DataType::Bool => 1,
             DataType::Int => 4,
+            DataType::BigInt => 8,
             DataType::Float => 4,
+            DataType::Double => 8,
+            DataType::Decimal(_, _) => 16,
+            DataType::Date => 4,
+            DataType::Timestamp => 8,
+            DataType::TimestampTz => 12,
+            DataType::Interval => 16,
+            DataType::Uuid => 16,
             DataType::Text => 0,
+            DataType::Blob => 0,
+            DataType::Json => 0,
             DataType::Invalid => 0,
         }
     }
@@ -62,13 +138,15 @@ pub struct Column {
     /// value. Must match the column datatype. Nullable columns require a
     /// default (often Null), and Null is only a valid default when nullable.,
     default: Option<Field>,
-    /// 0 for varchar / bound of MAX_STRING_LENGTH
+    /// 0 for varchar / bound of MAX_STRING_LENGTH. Also bounds the number of bytes of a blob.
     max_str_len: u16,
     /// For fixed length fields: The offset in bytes of the field from the start of the field data
     /// For variable length fields: The index of the offset, rather than the offset itself.
     ///
     /// See `[crate::Row::to_bytes()]` for more detail about the data layout.
     stored_offset: u16,
+    /// How text values of the column compare, group and encode as keys.
+    collation: Collation,
 }
 
 impl Column {
@@ -90,6 +168,7 @@ impl Column {
             },
             max_str_len: max_str_chars.unwrap_or(0),
             stored_offset: 0,
+            collation: Collation::default(),
         }
     }
 
@@ -98,12 +177,14 @@ impl Column {
     }
 
     pub fn to_string(&self) -> String {
-        let base = format!("{}:{}", self.name, self.data_type.to_string());
-        if self.data_type == DataType::Text {
-            format!("{}({})", base, self.max_str_len)
-        } else {
-            base
+        let mut base = format!("{}:{}", self.name, self.data_type.to_string());
+        if self.data_type.is_variable_length() {
+            base = format!("{}({})", base, self.max_str_len);
+        }
+        if self.collation != Collation::Binary {
+            base = format!("{} collate {}", base, self.collation);
         }
+        base
     }
 
     pub fn set_data_type(&mut self, data_type: DataType) {
@@ -122,6 +203,28 @@ impl Column {
         self.name.clone()
     }
 
+    pub fn is_nullable(&self) -> bool {
+        self.nullable
+    }
+
+    pub fn collation(&self) -> Collation {
+        self.collation
+    }
+
+    pub fn set_collation(&mut self, collation: Collation) {
+        self.collation = collation;
+    }
+
+    /// Returns the order of the column's values in an ascending key, which compares text by the
+    /// column's collation, e.g. so that a unique key on a case-insensitive column rejects names
+    /// differing only in case.
+    pub fn key_order(&self) -> KeyOrder {
+        KeyOrder {
+            collation: self.collation,
+            ..KeyOrder::default()
+        }
+    }
+
     pub fn default(&self) -> Option<&Field> {
         self.default.as_ref()
     }
@@ -137,6 +240,16 @@ impl Column {
     pub fn get_max_str_len(&self) -> u16 {
         self.max_str_len
     }
+
+    /// Returns the maximum length in bytes of a text or blob value of the column, so a string of
+    /// multi-byte characters holds fewer characters. Columns declared without a bound are limited
+    /// to [`MAX_STRING_LENGTH`].
+    pub fn max_str_bytes(&self) -> usize {
+        match self.max_str_len {
+            0 => MAX_STRING_LENGTH,
+            len => len as usize,
+        }
+    }
 }
 
 pub struct ColumnBuilder {
@@ -145,6 +258,7 @@ pub struct ColumnBuilder {
     nullable: Option<bool>,
     default: Option<Field>,
     max_str_len: Option<u16>,
+    collation: Option<Collation>,
 }
 
 impl ColumnBuilder {
@@ -155,6 +269,7 @@ impl ColumnBuilder {
             nullable: None,
             default: None,
             max_str_len: None,
+            collation: None,
         }
     }
 
@@ -185,6 +300,11 @@ impl ColumnBuilder {
         self
     }
 
+    pub fn collation(mut self, collation: Collation) -> Self {
+        self.collation = Some(collation);
+        self
+    }
+
     pub fn build(self) -> Column {
         let nullable = self.nullable.unwrap_or(false);
         Column {
@@ -200,6 +320,7 @@ impl ColumnBuilder {
             },
             max_str_len: self.max_str_len.unwrap_or(0),
             stored_offset: 0,
+            collation: self.collation.unwrap_or_default(),
         }
     }
 }
@@ -213,6 +334,7 @@ impl From<DataType> for Column {
             default: None,
             max_str_len: 0,
             stored_offset: 0,
+            collation: Collation::default(),
         }
     }
 }
@@ -226,10 +348,25 @@ impl From<(DataType, u16)> for Column {
             default: None,
             max_str_len: str_len,
             stored_offset: 0,
+            collation: Collation::default(),
         }
     }
 }
 
+/// A change to the schema of an existing table, i.e. an `ALTER TABLE`. See [`Table::alter`].
+#[derive(PartialEq, Eq, Hash, Clone, Debug, Serialize, Deserialize)]
+pub enum SchemaChange {
+    /// Adds a column after the existing ones. Existing rows get the column's default value, so it
+    /// must have one, which nullable columns do.
+    AddColumn(Column),
+    /// Drops the column with the given name.
+    DropColumn(String),
+    /// Renames a column.
+    RenameColumn { from: String, to: String },
+    /// Renames the table.
+    RenameTable(String),
+}
+
 #[derive(PartialEq, Eq, Hash, Clone, Debug, Serialize, Deserialize)]
 pub struct Table {
     /// The name of the table
@@ -238,6 +375,13 @@ pub struct Table {
     fixed_field_size_bytes: u16,
     /// The column definitions of the table
     columns: Vec<Column>,
+    /// The indexes of the columns that make up the table's primary key, in key order. Empty if
+    /// the table has no primary key.
+    primary_key: Vec<usize>,
+    /// The changes made to the table since it was created, along with the schema each was made
+    /// to. The table's version is the number of changes, and the schema at version `v` is
+    /// `history[v].0`. Rows are stored with the version of the schema they were written with.
+    history: Vec<(Table, SchemaChange)>,
 }
 
 impl Table {
@@ -246,6 +390,8 @@ impl Table {
             name: table_name.to_string(),
             fixed_field_size_bytes: 0,
             columns: Vec::new(),
+            primary_key: Vec::new(),
+            history: Vec::new(),
         }
     }
 
@@ -257,15 +403,235 @@ impl Table {
         &self.name
     }
 
-    pub fn set_name(mut self, table_name: &str) {
+    pub fn set_name(&mut self, table_name: &str) {
         self.name = table_name.to_string();
     }
 
+    /// Returns the indexes of the primary key columns, which is empty if the table has no primary
+    /// key.
+    pub fn primary_key(&self) -> &[usize] {
+        &self.primary_key
+    }
+
+    /// Makes the given columns the table's primary key, in the given order. Primary key columns
+    /// can't be nullable, and no two rows of the table can have the same values for them.
+    pub fn set_primary_key(&mut self, columns: &[&str]) -> Result<()> {
+        let mut primary_key = Vec::with_capacity(columns.len());
+        for name in columns {
+            let index = self.must_get_column_index(name)?;
+            if primary_key.contains(&index) {
+                return errinput!("column {name} appears twice in the primary key");
+            }
+            if self.columns[index].is_nullable() {
+                return errinput!("primary key column {name} can't be nullable");
+            }
+            primary_key.push(index);
+        }
+        self.primary_key = primary_key;
+        Ok(())
+    }
+
+    /// Encodes the primary key of a row of the table, given the row's values, see
+    /// [`Field::encode_key`]. Returns `None` if the table has no primary key.
+    pub fn primary_key_of(&self, values: &[Field]) -> Result<Option<Vec<u8>>> {
+        if self.primary_key.is_empty() {
+            return Ok(None);
+        }
+        let key = self
+            .primary_key
+            .iter()
+            .map(|&index| match values.get(index) {
+                Some(value) => Ok(value.clone()),
+                None => errdata!(
+                    "row has no value for column {}",
+                    self.get_column_name(index)
+                ),
+            })
+            .collect::<Result<Vec<_>>>()?;
+        self.encode_primary_key(&key).map(Some)
+    }
+
+    /// Encodes the given primary key values, one for each primary key column, as a key that sorts
+    /// like the values. Values of another type are converted to the column's type if it's the
+    /// common type of both, e.g. an integer for a bigint column, and numbers for a decimal column
+    /// if they fit.
+    pub fn encode_primary_key(&self, key: &[Field]) -> Result<Vec<u8>> {
+        if self.primary_key.is_empty() {
+            return errinput!("table {} has no primary key", self.name);
+        }
+        if key.len() != self.primary_key.len() {
+            return errinput!(
+                "primary key of table {} has {} columns, got {} values",
+                self.name,
+                self.primary_key.len(),
+                key.len()
+            );
+        }
+        let mut encoded = Vec::new();
+        for (value, &index) in key.iter().zip(&self.primary_key) {
+            let column = &self.columns[index];
+            let data_type = column.get_data_type();
+            let value = match value.get_type().common_type(&data_type) {
+                _ if value.is_null() => {
+                    return errinput!("NULL value not allowed for column {}", column.get_name())
+                }
+                Some(common) if common == data_type => value.cast(data_type)?,
+                // Decimals and integers are fitted to the column's precision and scale.
+                Some(DataType::Decimal(..)) if matches!(data_type, DataType::Decimal(..)) => {
+                    value.cast(data_type)?
+                }
+                _ => {
+                    return errinput!(
+                        "invalid datatype {} for {data_type} column {}",
+                        value.get_type(),
+                        column.get_name()
+                    )
+                }
+            };
+            encoded.extend(value.encode_key(column.key_order()));
+        }
+        Ok(encoded)
+    }
+
+    /// Returns the version of the schema, which starts at 0 and increases with every change made
+    /// with [`Table::alter`].
+    pub fn version(&self) -> u16 {
+        self.history.len() as u16
+    }
+
+    /// Returns the changes made to the table, along with the schema each was made to.
+    pub(crate) fn history(&self) -> &[(Table, SchemaChange)] {
+        &self.history
+    }
+
+    /// Restores the history of a table recreated from its current schema, e.g. when loading it
+    /// from the system catalog.
+    pub(crate) fn set_history(&mut self, history: Vec<(Table, SchemaChange)>) {
+        self.history = history;
+    }
+
+    /// Returns the schema as it was at the given version, if it isn't newer than the table's.
+    pub fn at_version(&self, version: u16) -> Option<&Table> {
+        match (version as usize).cmp(&self.history.len()) {
+            std::cmp::Ordering::Less => Some(&self.history[version as usize].0),
+            std::cmp::Ordering::Equal => Some(self),
+            std::cmp::Ordering::Greater => None,
+        }
+    }
+
+    /// Applies a change to the schema and bumps its version. Rows written at an earlier version
+    /// keep their layout, and are converted to the current schema with [`Table::upgrade`] when
+    /// read, so altering a table doesn't rewrite its rows.
+    pub fn alter(&mut self, change: SchemaChange) -> Result<()> {
+        if self.history.len() >= u16::MAX as usize {
+            return errinput!("table {} can't be altered any more", self.name);
+        }
+        let previous = Table {
+            history: Vec::new(),
+            ..self.clone()
+        };
+        let change = match change {
+            SchemaChange::AddColumn(column) => SchemaChange::AddColumn(self.column_to_add(column)?),
+            change => change,
+        };
+        match &change {
+            SchemaChange::AddColumn(column) => self.add_column(column),
+            SchemaChange::DropColumn(name) => {
+                let index = self.must_get_column_index(name)?;
+                if self.col_count() == 1 {
+                    return errinput!("can't drop {name}, the only column of table {}", self.name);
+                }
+                if self.primary_key.contains(&index) {
+                    return errinput!(
+                        "can't drop {name}, part of the primary key of {}",
+                        self.name
+                    );
+                }
+                let mut columns = std::mem::take(&mut self.columns);
+                columns.remove(index);
+                self.fixed_field_size_bytes = 0;
+                self.with_columns(columns);
+                for key_index in self.primary_key.iter_mut().filter(|i| **i > index) {
+                    *key_index -= 1;
+                }
+            }
+            SchemaChange::RenameColumn { from, to } => {
+                let index = self.must_get_column_index(from)?;
+                if self.field_name_to_index(Some(to)).is_some() {
+                    return errinput!("column {to} already exists in table {}", self.name);
+                }
+                self.columns[index].set_name(to);
+            }
+            SchemaChange::RenameTable(name) => self.set_name(name),
+        }
+        self.history.push((previous, change));
+        Ok(())
+    }
+
+    /// Converts the values of a row written at an earlier version of the schema to the current
+    /// one, by replaying the changes made since: added columns get their default value, and
+    /// dropped columns are removed.
+    pub fn upgrade(&self, mut values: Vec<Field>, version: u16) -> Result<Vec<Field>> {
+        let Some(changes) = self.history.get(version as usize..) else {
+            return errdata!(
+                "schema version {version} is newer than version {} of table {}",
+                self.version(),
+                self.name
+            );
+        };
+        for (schema, change) in changes {
+            match change {
+                SchemaChange::AddColumn(column) => {
+                    values.push(column.default().cloned().unwrap_or(Field::Null))
+                }
+                SchemaChange::DropColumn(name) => {
+                    values.remove(schema.must_get_column_index(name)?);
+                }
+                SchemaChange::RenameColumn { .. } | SchemaChange::RenameTable(_) => {}
+            }
+        }
+        Ok(values)
+    }
+
+    /// Checks that a column can be added to the table, and returns it with its default value
+    /// fitted to its type. The default is filled into existing rows as is, so must be valid.
+    fn column_to_add(&self, mut column: Column) -> Result<Column> {
+        let name = column.get_name();
+        if self.field_name_to_index(Some(&name)).is_some() {
+            return errinput!("column {name} already exists in table {}", self.name);
+        }
+        column.default = match (column.default, column.data_type) {
+            (None, _) => return errinput!("column {name} needs a default value for existing rows"),
+            (Some(Field::Null), _) if !column.nullable => {
+                return errinput!("NULL value not allowed for column {name}")
+            }
+            (Some(Field::Decimal(d)), DataType::Decimal(precision, scale)) => {
+                Some(Field::Decimal(d.fit(precision, scale)?))
+            }
+            (Some(default), data_type) if !default.is_null() && default.get_type() != data_type => {
+                return errinput!(
+                    "invalid datatype {} for {data_type} column {name}",
+                    default.get_type()
+                )
+            }
+            (default, _) => default,
+        };
+        Ok(column)
+    }
+
+    /// Returns the index of the column with the given name, failing if there is none.
+    fn must_get_column_index(&self, name: &str) -> Result<usize> {
+        match self.columns.iter().position(|column| column.name == name) {
+            Some(index) => Ok(index),
+            None => errinput!("column {name} does not exist in table {}", self.name),
+        }
+    }
+
     pub fn add_column(&mut self, column: &Column) {
         let data_type = column.get_data_type();
         let mut to_push = column.clone();
 
-        if data_type == DataType::Text {
+        if data_type.is_variable_length() {
             to_push.stored_offset = self.variable_length_fields() as u16;
             self.columns.push(to_push);
         } else {
@@ -316,6 +682,11 @@ impl Table {
         self.columns[index].get_data_type()
     }
 
+    /// Returns the key order of each column, see [`Column::key_order`].
+    pub fn key_orders(&self) -> Vec<KeyOrder> {
+        self.columns.iter().map(Column::key_order).collect()
+    }
+
     // if a field exists return its offset in the schema
     // otherwise return None
     pub fn field_name_to_index(&self, field_name: Option<&String>) -> Option<usize> {
@@ -346,7 +717,7 @@ impl Table {
     pub fn variable_length_fields(&self) -> usize {
         self.columns
             .iter()
-            .filter(|&col| col.get_data_type() == DataType::Text)
+            .filter(|&col| col.get_data_type().is_variable_length())
             .count()
     }
 
@@ -356,10 +727,14 @@ impl Table {
         schema.columns.append(&mut d2.columns.clone());
 
         schema.fixed_field_size_bytes = 0;
+        let mut variable_length_fields = 0;
         for i in 0..schema.col_count() {
-            if schema.columns[i].data_type != DataType::Text {
+            if !schema.columns[i].data_type.is_variable_length() {
                 schema.columns[i].stored_offset = schema.fixed_field_size_bytes;
                 schema.fixed_field_size_bytes += schema.columns[i].data_type.length_bytes();
+            } else {
+                schema.columns[i].stored_offset = variable_length_fields;
+                variable_length_fields += 1;
             }
         }
         schema
@@ -412,6 +787,7 @@ impl Deref for Table {
 pub struct TableBuilder {
     name: Option<String>,
     columns: Vec<Column>,
+    primary_key: Vec<String>,
 }
 
 impl TableBuilder {
@@ -448,6 +824,12 @@ impl TableBuilder {
         self
     }
 
+    /// Sets the columns of the primary key, see [`Table::set_primary_key`].
+    pub fn primary_key(&mut self, columns: &[&str]) -> &mut Self {
+        self.primary_key = columns.iter().map(|name| name.to_string()).collect();
+        self
+    }
+
     pub fn build(&mut self) -> Table {
         let name = self
             .name
@@ -457,6 +839,10 @@ impl TableBuilder {
         self.columns
             .iter()
             .for_each(|column| table_definition.add_column(column));
+        let primary_key: Vec<&str> = self.primary_key.iter().map(String::as_str).collect();
+        table_definition
+            .set_primary_key(&primary_key)
+            .expect("Cannot build a Table with an invalid primary key.");
         table_definition
     }
 
@@ -467,10 +853,11 @@ impl TableBuilder {
 
 #[cfg(test)]
 mod tests {
-    use crate::common::utility;
+    use crate::common::{utility, Error};
     use rand::Rng;
 
     use super::*;
+    use crate::types::Decimal;
 
     #[test]
     pub fn test_column_declaration() {
@@ -495,6 +882,36 @@ mod tests {
         assert_eq!(test2.to_string(), "c2:varchar(7)");
     }
 
+    #[test]
+    pub fn test_numeric_data_types() {
+        assert_eq!(DataType::from_string("BigInt"), DataType::BigInt);
+        assert_eq!(DataType::from_string("Double"), DataType::Double);
+        assert_eq!(
+            DataType::from_string("Decimal(10,2)"),
+            DataType::Decimal(10, 2)
+        );
+        assert_eq!(DataType::Decimal(10, 2).to_string(), "decimal(10,2)");
+        assert_eq!(DataType::BigInt.length_bytes(), 8);
+        assert_eq!(DataType::Double.length_bytes(), 8);
+        assert_eq!(DataType::Decimal(38, 0).length_bytes(), 16);
+    }
+
+    #[test]
+    pub fn test_parse_unknown_data_type() {
+        assert_eq!(
+            DataType::parse("Decimal(10,2)"),
+            Ok(DataType::Decimal(10, 2))
+        );
+        assert!(matches!(
+            DataType::parse("Nonsense"),
+            Err(Error::InvalidData(_))
+        ));
+        assert!(matches!(
+            DataType::parse("Decimal(2,10)"),
+            Err(Error::InvalidData(_))
+        ));
+    }
+
     #[test]
     pub fn test_get_type() {
         let td = utility::create_table_definition(1, "d1").clone();
@@ -688,4 +1105,94 @@ mod tests {
         assert_eq!(schema.get_column(5).stored_offset, 8);
         assert_eq!(schema.get_column(6).stored_offset, 9);
     }
+
+    #[test]
+    pub fn test_alter() {
+        let mut schema = Table::builder()
+            .name("test_table")
+            .column("column1", DataType::Int, false, None, None)
+            .column("column2", DataType::Text, false, None, Some(30))
+            .column("column3", DataType::Float, false, None, None)
+            .column("column4", DataType::Text, false, None, Some(20))
+            .build();
+        let original = schema.clone();
+
+        schema
+            .alter(SchemaChange::DropColumn("column1".to_string()))
+            .unwrap();
+        schema
+            .alter(SchemaChange::DropColumn("column2".to_string()))
+            .unwrap();
+        assert_eq!(schema.col_count(), 2);
+        assert_eq!(schema.fixed_field_size_bytes(), 4);
+        assert_eq!(schema.get_column(0).stored_offset, 0);
+        assert_eq!(schema.get_column(1).stored_offset, 0);
+
+        let column5 = Column::new("column5", DataType::Decimal(4, 2), false, None, None);
+        let column5 = Column {
+            default: Some(Field::from(Decimal::from(1))),
+            ..column5
+        };
+        schema.alter(SchemaChange::AddColumn(column5)).unwrap();
+        schema.set_name("renamed");
+        assert_eq!(schema.name(), "renamed");
+        assert_eq!(schema.version(), 3);
+        assert_eq!(schema.at_version(0).unwrap(), &original);
+        assert!(schema.at_version(4).is_none());
+
+        let values = vec![
+            Field::from(1),
+            Field::from("a"),
+            Field::from(2.0),
+            Field::from("b"),
+        ];
+        let expected = vec![
+            Field::from(2.0),
+            Field::from("b"),
+            Field::from(Decimal::new(100, 2)),
+        ];
+        assert_eq!(schema.upgrade(values, 0).unwrap(), expected);
+        assert!(schema.upgrade(vec![], 4).is_err());
+    }
+
+    #[test]
+    pub fn test_primary_key() {
+        let mut schema = Table::builder()
+            .name("test_table")
+            .column("column1", DataType::Text, true, None, Some(10))
+            .column("column2", DataType::BigInt, false, None, None)
+            .column("column3", DataType::Text, false, None, Some(10))
+            .primary_key(&["column3", "column2"])
+            .build();
+        assert_eq!(schema.primary_key(), [2, 1]);
+        assert!(schema.set_primary_key(&["column1"]).is_err());
+        assert!(schema.set_primary_key(&["column2", "column2"]).is_err());
+        assert!(schema.set_primary_key(&["column4"]).is_err());
+        assert_eq!(schema.primary_key(), [2, 1]);
+
+        let key = schema
+            .encode_primary_key(&[Field::from("a"), Field::from(1)])
+            .unwrap();
+        let values = [Field::Null, Field::BigInt(1), Field::from("a")];
+        assert_eq!(schema.primary_key_of(&values).unwrap(), Some(key));
+        assert!(schema.encode_primary_key(&[Field::from("a")]).is_err());
+        assert!(schema
+            .encode_primary_key(&[Field::from("a"), Field::from(1.5)])
+            .is_err());
+        assert!(schema
+            .encode_primary_key(&[Field::Null, Field::from(1)])
+            .is_err());
+
+        assert!(schema
+            .alter(SchemaChange::DropColumn("column2".to_string()))
+            .is_err());
+        schema
+            .alter(SchemaChange::DropColumn("column1".to_string()))
+            .unwrap();
+        assert_eq!(schema.primary_key(), [1, 0]);
+        assert_eq!(
+            Table::from(DataType::Int).primary_key_of(&[]).unwrap(),
+            None
+        );
+    }
 }